@@ -38,6 +38,7 @@ pub trait EvidenceApi {
         Returns:
             None
     */
+    #[allow(clippy::ptr_arg)]
     fn dump_cc_report(report: &Vec<u8>);
 
     /***
@@ -1,5 +1,6 @@
 use crate::cc_type::TeeType;
-use crate::tcg::TcgDigest;
+use crate::tcg::{EventLogEntry, TcgDigest};
+use hashbrown::HashMap;
 
 /***
  ************************************
@@ -72,7 +73,92 @@ pub struct Algorithm {
  * API replay_eventlog() related data *
  ********************************************
  */
+#[derive(Clone)]
 pub struct ReplayResult {
     pub imr_index: u32,
     pub digests: Vec<TcgDigest>,
 }
+
+// options of replay_eventlog()
+#[derive(Clone, Default)]
+pub struct ReplayOptions {
+    // recompute the template hash of IMA events from the template data
+    pub verify_ima_template: bool,
+    // algorithm of the register IMA events are extended to, e.g. SHA384 for TDX RTMR
+    pub ima_extend_algo_id: Option<u16>,
+    // extend the SHA1 template hash padded with zeros instead of the recomputed one
+    pub ima_extend_padded_sha1: bool,
+    // record the value of the IMR after each extend
+    pub trace: bool,
+}
+
+// IMA event whose template hash does not match its template data
+#[derive(Clone)]
+pub struct ImaTemplateMismatch {
+    pub event_index: usize,
+    pub imr_index: u32,
+    pub template_name: String,
+    pub file_name: String,
+    pub algo_id: u16,
+    pub logged_hash: Vec<u8>,
+    pub computed_hash: Option<Vec<u8>>,
+}
+
+// value of the IMR after extending the digest of an event in the replay trace
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayTraceEntry {
+    pub event_index: usize,
+    pub imr_index: u32,
+    pub algo_id: u16,
+    pub digest: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+// return of replay_eventlog() with options
+pub struct ReplayReport {
+    pub replay_results: Vec<ReplayResult>,
+    pub ima_mismatches: Vec<ImaTemplateMismatch>,
+    pub trace: Vec<ReplayTraceEntry>,
+    // startup locality of the IMRs starting from non-zero values
+    pub startup_localities: HashMap<u32, u8>,
+}
+
+// how the replayed value of an IMR bank diverges from the expected one
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayDivergence {
+    Matched,
+    ExtraEvents {
+        last_matched_event: Option<usize>,
+        extra_events: Vec<usize>,
+    },
+    Diverged {
+        first_mismatch_event: Option<usize>,
+    },
+    AlteredOrMissingEvents {
+        first_mismatch_event: Option<usize>,
+    },
+}
+
+// diagnosis of an IMR bank by ReplayReport::diagnose()
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayDiagnosis {
+    pub imr_index: u32,
+    pub algo_id: u16,
+    pub expected: Vec<u8>,
+    pub replayed: Vec<u8>,
+    pub divergence: ReplayDivergence,
+}
+
+// update of ImaWatcher with the IMA events appended since the last poll and the running
+// replay results of the IMRs they extend
+#[derive(Clone)]
+pub struct ImaWatchUpdate {
+    pub events: Vec<EventLogEntry>,
+    pub replay_results: Vec<ReplayResult>,
+    // lines of the list failing to parse, skipped, which stop the running replay so
+    // replay_results is empty and the next polls fail until the list is replaced
+    pub invalid_lines: Vec<String>,
+    // whether the list was replaced since the last poll, so it is read again from the start
+    // on top of the boot time replay
+    pub restarted: bool,
+}
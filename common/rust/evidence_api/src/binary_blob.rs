@@ -7,7 +7,8 @@ use log::info;
 ...
 */
 
-pub fn dump_data(data: &Vec<u8>) {
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+pub fn dump_data(data: &[u8]) {
     let mut index: usize = 0;
     let mut linestr = "".to_string();
     let mut printstr = "".to_string();
@@ -69,3 +70,98 @@ pub fn get_u16(data: Vec<u8>) -> u16 {
 pub fn get_u32(data: Vec<u8>) -> u32 {
     u32::from_le_bytes(data[0..4].try_into().unwrap())
 }
+
+// get the string of an EFI_GUID, e.g. "63da758d-e664-4564-adc5-f4b93be8accd"
+pub fn get_guid_str(guid: &[u8; 16]) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{}-{}",
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+        hex::encode(&guid[8..10]),
+        hex::encode(&guid[10..16])
+    )
+}
+
+// get the string of UCS-2 encoded data, stopping at the first null character
+pub fn get_ucs2_str(data: &[u8]) -> String {
+    let chars: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|c| *c != 0)
+        .collect();
+    String::from_utf16_lossy(&chars)
+}
+
+/***
+    Bounds checked reader over a byte slice.
+    Each read advances the position and fails instead of panicking when the data is short.
+*/
+pub struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    pub fn new(data: &'a [u8]) -> BinaryReader<'a> {
+        BinaryReader { data, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], anyhow::Error> {
+        if len > self.remaining() {
+            return Err(anyhow::anyhow!(
+                "[read_bytes] need {} bytes at offset {}, but only {} left",
+                len,
+                self.pos,
+                self.remaining()
+            ));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], anyhow::Error> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, anyhow::Error> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u16_le(&mut self) -> Result<u16, anyhow::Error> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32_le(&mut self) -> Result<u32, anyhow::Error> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64_le(&mut self) -> Result<u64, anyhow::Error> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u16_be(&mut self) -> Result<u16, anyhow::Error> {
+        Ok(u16::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32_be(&mut self) -> Result<u32, anyhow::Error> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64_be(&mut self) -> Result<u64, anyhow::Error> {
+        Ok(u64::from_be_bytes(self.read_array()?))
+    }
+}
@@ -0,0 +1,51 @@
+/***
+    Constants of Arm CCA attestation token defined at
+    https://developer.arm.com/documentation/den0137/latest (Realm Management Monitor specification)
+    A7.2 Realm attestation token format
+*/
+// CBOR tag of the CCA attestation token
+pub const CCA_TOKEN_TAG: u64 = 399;
+// CBOR tag of COSE_Sign1
+pub const COSE_SIGN1_TAG: u64 = 18;
+
+// keys of the CCA token collection
+pub const CCA_PLATFORM_TOKEN_KEY: i128 = 44234;
+pub const CCA_REALM_TOKEN_KEY: i128 = 44241;
+
+// claims of the realm token
+pub const CCA_REALM_CHALLENGE: i128 = 10;
+pub const CCA_REALM_PROFILE: i128 = 265;
+pub const CCA_REALM_PERSONALIZATION_VALUE: i128 = 44235;
+pub const CCA_REALM_HASH_ALGO_ID: i128 = 44236;
+pub const CCA_REALM_PUBLIC_KEY: i128 = 44237;
+pub const CCA_REALM_INITIAL_MEASUREMENT: i128 = 44238;
+pub const CCA_REALM_EXTENSIBLE_MEASUREMENTS: i128 = 44239;
+pub const CCA_REALM_PUBLIC_KEY_HASH_ALGO_ID: i128 = 44240;
+
+// claims of the platform token
+pub const CCA_PLATFORM_CHALLENGE: i128 = 10;
+pub const CCA_PLATFORM_INSTANCE_ID: i128 = 256;
+pub const CCA_PLATFORM_PROFILE: i128 = 265;
+pub const CCA_PLATFORM_IMPLEMENTATION_ID: i128 = 2396;
+pub const CCA_PLATFORM_LIFECYCLE: i128 = 2395;
+pub const CCA_PLATFORM_BOOT_SEED: i128 = 2397;
+pub const CCA_PLATFORM_SW_COMPONENTS: i128 = 2399;
+pub const CCA_PLATFORM_VERIFICATION_SERVICE: i128 = 2400;
+pub const CCA_PLATFORM_CONFIG: i128 = 2401;
+pub const CCA_PLATFORM_HASH_ALGO_ID: i128 = 2402;
+
+// keys of the software component map in the platform token
+pub const CCA_SW_COMPONENT_TYPE: i128 = 1;
+pub const CCA_SW_COMPONENT_MEASUREMENT_VALUE: i128 = 2;
+pub const CCA_SW_COMPONENT_VERSION: i128 = 4;
+pub const CCA_SW_COMPONENT_SIGNER_ID: i128 = 5;
+pub const CCA_SW_COMPONENT_HASH_ALGO_ID: i128 = 6;
+
+// number of Realm Extensible Measurements
+pub const CCA_REM_COUNT: usize = 4;
+
+// COSE header parameter of the signature algorithm and the supported algorithms
+pub const COSE_HEADER_ALG: i128 = 1;
+pub const COSE_ALG_ES256: i128 = -7;
+pub const COSE_ALG_ES384: i128 = -35;
+pub const COSE_ALG_ES512: i128 = -36;
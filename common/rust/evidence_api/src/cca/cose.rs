@@ -0,0 +1,115 @@
+use crate::cca::common::*;
+use anyhow::anyhow;
+use ciborium::value::Value;
+use log::info;
+
+/***
+    COSE_Sign1 structure defined at https://www.rfc-editor.org/rfc/rfc9052#section-4.2
+    Definition:
+    COSE_Sign1 = [
+        protected : bstr .cbor header_map,
+        unprotected : header_map,
+        payload : bstr / nil,
+        signature : bstr
+    ]
+
+    Attributes:
+        protected: the raw protected header, which is covered by the signature.
+        algorithm: the signature algorithm found in the protected header.
+        payload: the raw payload, i.e. the CBOR encoded claims.
+        signature: the signature, r || s for ECDSA.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoseSign1 {
+    pub protected: Vec<u8>,
+    pub algorithm: Option<i128>,
+    pub payload: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    pub fn new(data: &[u8]) -> Result<CoseSign1, anyhow::Error> {
+        let value = decode_cbor(data)?;
+        let value = match value {
+            Value::Tag(COSE_SIGN1_TAG, value) => *value,
+            Value::Tag(tag, _) => {
+                return Err(anyhow!("[CoseSign1] unexpected CBOR tag: {}", tag));
+            }
+            value => value,
+        };
+        let items = match value {
+            Value::Array(items) if items.len() == 4 => items,
+            _ => {
+                return Err(anyhow!(
+                    "[CoseSign1] COSE_Sign1 must be an array of 4 items"
+                ))
+            }
+        };
+        let mut items = items.into_iter();
+
+        let protected = match items.next() {
+            Some(Value::Bytes(protected)) => protected,
+            _ => return Err(anyhow!("[CoseSign1] invalid protected header")),
+        };
+        let algorithm = if protected.is_empty() {
+            None
+        } else {
+            let header = decode_cbor(&protected)?;
+            match header.as_map() {
+                Some(header) => get_claim(header, COSE_HEADER_ALG).and_then(get_int),
+                None => return Err(anyhow!("[CoseSign1] protected header must be a map")),
+            }
+        };
+        if !matches!(items.next(), Some(Value::Map(_))) {
+            return Err(anyhow!("[CoseSign1] invalid unprotected header"));
+        }
+        let payload = match items.next() {
+            Some(Value::Bytes(payload)) => payload,
+            _ => return Err(anyhow!("[CoseSign1] detached payload is not supported")),
+        };
+        let signature = match items.next() {
+            Some(Value::Bytes(signature)) => signature,
+            _ => return Err(anyhow!("[CoseSign1] invalid signature")),
+        };
+
+        Ok(CoseSign1 {
+            protected,
+            algorithm,
+            payload,
+            signature,
+        })
+    }
+
+    // Get the claims carried in the payload
+    pub fn get_claims(&self) -> Result<Vec<(Value, Value)>, anyhow::Error> {
+        match decode_cbor(&self.payload)? {
+            Value::Map(claims) => Ok(claims),
+            _ => Err(anyhow!("[get_claims] payload must be a map of claims")),
+        }
+    }
+
+    pub fn show(&self) {
+        info!("show the data of CoseSign1");
+        info!("protected = {:02X?}", self.protected);
+        info!("algorithm = {:?}", self.algorithm);
+        info!("signature = {:02X?}", self.signature);
+    }
+}
+
+pub(crate) fn decode_cbor(data: &[u8]) -> Result<Value, anyhow::Error> {
+    match ciborium::de::from_reader::<Value, _>(data) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(anyhow!("[decode_cbor] invalid CBOR data: {:?}", e)),
+    }
+}
+
+// get the value of an integer key in a CBOR map
+pub(crate) fn get_claim(map: &[(Value, Value)], key: i128) -> Option<&Value> {
+    map.iter()
+        .find(|(k, _)| k.as_integer().map(i128::from) == Some(key))
+        .map(|(_, v)| v)
+}
+
+pub(crate) fn get_int(value: &Value) -> Option<i128> {
+    value.as_integer().map(i128::from)
+}
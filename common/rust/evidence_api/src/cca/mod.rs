@@ -0,0 +1,4 @@
+pub mod common;
+pub mod cose;
+pub mod token;
+pub mod verify;
@@ -0,0 +1,612 @@
+use crate::api::ParseCcReport;
+use crate::api_data::CcReport;
+use crate::binary_blob::dump_data;
+use crate::cca::common::*;
+use crate::cca::cose::*;
+use anyhow::anyhow;
+use ciborium::value::Value;
+use log::info;
+
+/***
+    Security lifecycle state of the CCA platform defined at
+    https://www.ietf.org/archive/id/draft-tschofenig-rats-psa-token-24.html#section-4.3.1
+    The lower 8 bits of the claim are implementation defined and ignored here.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum CcaSecurityLifecycle {
+    Unknown,
+    AssemblyAndTest,
+    PsaRotProvisioning,
+    Secured,
+    NonPsaRotDebug,
+    RecoverablePsaRotDebug,
+    Decommissioned,
+}
+
+impl CcaSecurityLifecycle {
+    pub fn new(lifecycle: u32) -> CcaSecurityLifecycle {
+        match lifecycle >> 8 {
+            0x10 => CcaSecurityLifecycle::AssemblyAndTest,
+            0x20 => CcaSecurityLifecycle::PsaRotProvisioning,
+            0x30 => CcaSecurityLifecycle::Secured,
+            0x40 => CcaSecurityLifecycle::NonPsaRotDebug,
+            0x50 => CcaSecurityLifecycle::RecoverablePsaRotDebug,
+            0x60 => CcaSecurityLifecycle::Decommissioned,
+            _ => CcaSecurityLifecycle::Unknown,
+        }
+    }
+}
+
+/***
+    Software component of the platform token.
+
+    Attributes:
+        component_type: short string describing the component, e.g. "BL2".
+        measurement_value: digest of the component.
+        version: version of the component.
+        signer_id: hash of the key that signed the component.
+        hash_algo_id: hash algorithm of measurement_value.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcaSwComponent {
+    pub component_type: Option<String>,
+    pub measurement_value: Vec<u8>,
+    pub version: Option<String>,
+    pub signer_id: Vec<u8>,
+    pub hash_algo_id: Option<String>,
+}
+
+/***
+    CCA platform token, signed by the CCA Platform Attestation Key (CPAK).
+    Definition reference:
+    https://developer.arm.com/documentation/den0137/latest
+    A7.2.3.1 CCA platform token claims
+
+    Attributes:
+        cose: the COSE_Sign1 envelope of the token.
+        profile: the profile of the token.
+        challenge: hash of the Realm Attestation Key (RAK).
+        implementation_id: identifies the implementation of the CCA platform.
+        instance_id: identifies the CPAK of the CCA platform.
+        config: the CCA platform configuration.
+        lifecycle: the raw security lifecycle state.
+        hash_algo_id: hash algorithm used to compute the challenge.
+        sw_components: software components measured by the CCA platform.
+        verification_service: optional hint of the verification service.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcaPlatformToken {
+    pub cose: CoseSign1,
+    pub profile: Option<String>,
+    pub challenge: Vec<u8>,
+    pub implementation_id: Vec<u8>,
+    pub instance_id: Vec<u8>,
+    pub config: Vec<u8>,
+    pub lifecycle: u32,
+    pub hash_algo_id: String,
+    pub sw_components: Vec<CcaSwComponent>,
+    pub verification_service: Option<String>,
+}
+
+impl CcaPlatformToken {
+    pub fn new(data: &[u8]) -> Result<CcaPlatformToken, anyhow::Error> {
+        let cose = CoseSign1::new(data)?;
+        let claims = cose.get_claims()?;
+
+        let lifecycle = match get_claim(&claims, CCA_PLATFORM_LIFECYCLE).and_then(get_int) {
+            Some(lifecycle) => lifecycle as u32,
+            None => {
+                return Err(anyhow!(
+                    "[CcaPlatformToken] security lifecycle claim not found"
+                ))
+            }
+        };
+        let sw_components = match get_claim(&claims, CCA_PLATFORM_SW_COMPONENTS) {
+            Some(Value::Array(components)) => {
+                let mut sw_components = Vec::new();
+                for component in components {
+                    sw_components.push(get_sw_component(component)?);
+                }
+                sw_components
+            }
+            Some(_) => {
+                return Err(anyhow!(
+                    "[CcaPlatformToken] software components must be an array"
+                ))
+            }
+            None => Vec::new(),
+        };
+
+        Ok(CcaPlatformToken {
+            profile: get_text_claim(&claims, CCA_PLATFORM_PROFILE),
+            challenge: get_bytes_claim(&claims, CCA_PLATFORM_CHALLENGE, "challenge")?,
+            implementation_id: get_bytes_claim(
+                &claims,
+                CCA_PLATFORM_IMPLEMENTATION_ID,
+                "implementation id",
+            )?,
+            instance_id: get_bytes_claim(&claims, CCA_PLATFORM_INSTANCE_ID, "instance id")?,
+            config: get_bytes_claim(&claims, CCA_PLATFORM_CONFIG, "config")?,
+            lifecycle,
+            hash_algo_id: get_required_text_claim(
+                &claims,
+                CCA_PLATFORM_HASH_ALGO_ID,
+                "hash algorithm id",
+            )?,
+            sw_components,
+            verification_service: get_text_claim(&claims, CCA_PLATFORM_VERIFICATION_SERVICE),
+            cose,
+        })
+    }
+
+    pub fn get_lifecycle(&self) -> CcaSecurityLifecycle {
+        CcaSecurityLifecycle::new(self.lifecycle)
+    }
+
+    pub fn show(&self) {
+        info!("show the data of CcaPlatformToken");
+        info!("profile = {:?}", self.profile);
+        info!("challenge:");
+        dump_data(&self.challenge);
+        info!("implementation_id:");
+        dump_data(&self.implementation_id);
+        info!("instance_id:");
+        dump_data(&self.instance_id);
+        info!("config:");
+        dump_data(&self.config);
+        info!(
+            "lifecycle = {:#06x} ({:?})",
+            self.lifecycle,
+            self.get_lifecycle()
+        );
+        info!("hash_algo_id = {}", self.hash_algo_id);
+        for (index, component) in self.sw_components.iter().enumerate() {
+            info!(
+                "sw_components[{}] type = {:?} version = {:?} hash_algo_id = {:?}",
+                index, component.component_type, component.version, component.hash_algo_id
+            );
+            info!("sw_components[{}] measurement_value:", index);
+            dump_data(&component.measurement_value);
+            info!("sw_components[{}] signer_id:", index);
+            dump_data(&component.signer_id);
+        }
+        info!("verification_service = {:?}", self.verification_service);
+    }
+}
+
+/***
+    CCA realm token, signed by the Realm Attestation Key (RAK).
+    Definition reference:
+    https://developer.arm.com/documentation/den0137/latest
+    A7.2.3.2 Realm token claims
+
+    Attributes:
+        cose: the COSE_Sign1 envelope of the token.
+        profile: the profile of the token, if present.
+        challenge: the challenge provided by the realm when requesting the token.
+        personalization_value: the Realm Personalization Value (RPV).
+        initial_measurement: the Realm Initial Measurement (RIM).
+        extensible_measurements: the four Realm Extensible Measurements (REMs).
+        hash_algo_id: hash algorithm of the RIM and REMs.
+        public_key: the RAK public key.
+        public_key_hash_algo_id: hash algorithm used to compute the platform challenge
+                                 from the RAK.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcaRealmToken {
+    pub cose: CoseSign1,
+    pub profile: Option<String>,
+    pub challenge: Vec<u8>,
+    pub personalization_value: Vec<u8>,
+    pub initial_measurement: Vec<u8>,
+    pub extensible_measurements: Vec<Vec<u8>>,
+    pub hash_algo_id: String,
+    pub public_key: Vec<u8>,
+    pub public_key_hash_algo_id: String,
+}
+
+impl CcaRealmToken {
+    pub fn new(data: &[u8]) -> Result<CcaRealmToken, anyhow::Error> {
+        let cose = CoseSign1::new(data)?;
+        let claims = cose.get_claims()?;
+
+        let extensible_measurements = match get_claim(&claims, CCA_REALM_EXTENSIBLE_MEASUREMENTS) {
+            Some(Value::Array(rems)) if rems.len() == CCA_REM_COUNT => {
+                let mut extensible_measurements = Vec::new();
+                for rem in rems {
+                    match rem.as_bytes() {
+                        Some(rem) => extensible_measurements.push(rem.clone()),
+                        None => return Err(anyhow!("[CcaRealmToken] REM must be bytes")),
+                    }
+                }
+                extensible_measurements
+            }
+            _ => {
+                return Err(anyhow!(
+                    "[CcaRealmToken] {} extensible measurements are expected",
+                    CCA_REM_COUNT
+                ))
+            }
+        };
+
+        Ok(CcaRealmToken {
+            profile: get_text_claim(&claims, CCA_REALM_PROFILE),
+            challenge: get_bytes_claim(&claims, CCA_REALM_CHALLENGE, "challenge")?,
+            personalization_value: get_bytes_claim(
+                &claims,
+                CCA_REALM_PERSONALIZATION_VALUE,
+                "personalization value",
+            )?,
+            initial_measurement: get_bytes_claim(
+                &claims,
+                CCA_REALM_INITIAL_MEASUREMENT,
+                "initial measurement",
+            )?,
+            extensible_measurements,
+            hash_algo_id: get_required_text_claim(
+                &claims,
+                CCA_REALM_HASH_ALGO_ID,
+                "hash algorithm id",
+            )?,
+            public_key: get_bytes_claim(&claims, CCA_REALM_PUBLIC_KEY, "public key")?,
+            public_key_hash_algo_id: get_required_text_claim(
+                &claims,
+                CCA_REALM_PUBLIC_KEY_HASH_ALGO_ID,
+                "public key hash algorithm id",
+            )?,
+            cose,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of CcaRealmToken");
+        info!("profile = {:?}", self.profile);
+        info!("challenge:");
+        dump_data(&self.challenge);
+        info!("personalization_value:");
+        dump_data(&self.personalization_value);
+        info!("initial_measurement:");
+        dump_data(&self.initial_measurement);
+        for (index, rem) in self.extensible_measurements.iter().enumerate() {
+            info!("extensible_measurements[{}]:", index);
+            dump_data(rem);
+        }
+        info!("hash_algo_id = {}", self.hash_algo_id);
+        info!("public_key:");
+        dump_data(&self.public_key);
+        info!("public_key_hash_algo_id = {}", self.public_key_hash_algo_id);
+    }
+}
+
+/***
+    CCA attestation token, i.e. the collection of the platform token and the realm
+    token, as returned by RSI_ATTEST_TOKEN_CONTINUE or configfs-tsm.
+    Definition reference:
+    https://developer.arm.com/documentation/den0137/latest
+    A7.2.2 CCA attestation token
+    Definition:
+    cca-token = #6.399(cca-token-collection)
+    cca-token-collection = {
+        44234 => bytes .cbor COSE_Sign1_Tagged  ; CCA platform token
+        44241 => bytes .cbor COSE_Sign1_Tagged  ; realm token
+    }
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcaToken {
+    pub platform_token: CcaPlatformToken,
+    pub realm_token: CcaRealmToken,
+}
+
+impl CcaToken {
+    pub fn parse_cca_token(token: Vec<u8>) -> Result<CcaToken, anyhow::Error> {
+        let collection = match decode_cbor(&token)? {
+            Value::Tag(CCA_TOKEN_TAG, collection) => *collection,
+            Value::Tag(tag, _) => {
+                return Err(anyhow!("[parse_cca_token] unexpected CBOR tag: {}", tag));
+            }
+            collection => collection,
+        };
+        let collection = match collection.as_map() {
+            Some(collection) => collection,
+            None => {
+                return Err(anyhow!(
+                    "[parse_cca_token] CCA token collection must be a map"
+                ))
+            }
+        };
+
+        let platform_token =
+            match get_claim(collection, CCA_PLATFORM_TOKEN_KEY).and_then(|v| v.as_bytes()) {
+                Some(platform_token) => CcaPlatformToken::new(platform_token)?,
+                None => return Err(anyhow!("[parse_cca_token] platform token not found")),
+            };
+        let realm_token =
+            match get_claim(collection, CCA_REALM_TOKEN_KEY).and_then(|v| v.as_bytes()) {
+                Some(realm_token) => CcaRealmToken::new(realm_token)?,
+                None => return Err(anyhow!("[parse_cca_token] realm token not found")),
+            };
+
+        Ok(CcaToken {
+            platform_token,
+            realm_token,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("======================================");
+        info!("show the data of CCA attestation token");
+        self.platform_token.show();
+        self.realm_token.show();
+    }
+}
+
+fn get_sw_component(component: &Value) -> Result<CcaSwComponent, anyhow::Error> {
+    let component = match component.as_map() {
+        Some(component) => component,
+        None => {
+            return Err(anyhow!(
+                "[get_sw_component] software component must be a map"
+            ))
+        }
+    };
+    Ok(CcaSwComponent {
+        component_type: get_text_claim(component, CCA_SW_COMPONENT_TYPE),
+        measurement_value: get_bytes_claim(
+            component,
+            CCA_SW_COMPONENT_MEASUREMENT_VALUE,
+            "measurement value",
+        )?,
+        version: get_text_claim(component, CCA_SW_COMPONENT_VERSION),
+        signer_id: get_bytes_claim(component, CCA_SW_COMPONENT_SIGNER_ID, "signer id")?,
+        hash_algo_id: get_text_claim(component, CCA_SW_COMPONENT_HASH_ALGO_ID),
+    })
+}
+
+fn get_bytes_claim(
+    claims: &[(Value, Value)],
+    key: i128,
+    name: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    match get_claim(claims, key).and_then(|v| v.as_bytes()) {
+        Some(value) => Ok(value.clone()),
+        None => Err(anyhow!("[get_bytes_claim] {} claim not found", name)),
+    }
+}
+
+fn get_text_claim(claims: &[(Value, Value)], key: i128) -> Option<String> {
+    get_claim(claims, key)
+        .and_then(|v| v.as_text())
+        .map(|v| v.to_string())
+}
+
+fn get_required_text_claim(
+    claims: &[(Value, Value)],
+    key: i128,
+    name: &str,
+) -> Result<String, anyhow::Error> {
+    match get_text_claim(claims, key) {
+        Some(value) => Ok(value),
+        None => Err(anyhow!(
+            "[get_required_text_claim] {} claim not found",
+            name
+        )),
+    }
+}
+
+// API function parses raw cc report to CcaToken struct
+impl ParseCcReport<CcaToken> for CcReport {
+    fn parse_cc_report(report: Vec<u8>) -> Result<CcaToken, anyhow::Error> {
+        match CcaToken::parse_cca_token(report) {
+            Ok(cca_token) => Ok(cca_token),
+            Err(e) => Err(anyhow!("[parse_cc_report] error parse cca token: {:?}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_parse_cca_token {
+    use super::*;
+
+    fn int(value: i128) -> Value {
+        Value::Integer(i64::try_from(value).unwrap().into())
+    }
+
+    fn encode(value: &Value) -> Vec<u8> {
+        let mut data = Vec::new();
+        ciborium::ser::into_writer(value, &mut data).unwrap();
+        data
+    }
+
+    fn get_cose_sign1(claims: Vec<(Value, Value)>) -> Vec<u8> {
+        let protected = encode(&Value::Map(vec![(
+            int(COSE_HEADER_ALG),
+            int(COSE_ALG_ES384),
+        )]));
+        encode(&Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(Vec::new()),
+                Value::Bytes(encode(&Value::Map(claims))),
+                Value::Bytes(vec![0x5a; 96]),
+            ])),
+        ))
+    }
+
+    fn get_realm_claims() -> Vec<(Value, Value)> {
+        vec![
+            (int(CCA_REALM_CHALLENGE), Value::Bytes(vec![0x01; 64])),
+            (
+                int(CCA_REALM_PERSONALIZATION_VALUE),
+                Value::Bytes(vec![0x02; 64]),
+            ),
+            (
+                int(CCA_REALM_INITIAL_MEASUREMENT),
+                Value::Bytes(vec![0x03; 32]),
+            ),
+            (
+                int(CCA_REALM_EXTENSIBLE_MEASUREMENTS),
+                Value::Array((4..8).map(|i| Value::Bytes(vec![i; 32])).collect()),
+            ),
+            (
+                int(CCA_REALM_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+            (int(CCA_REALM_PUBLIC_KEY), Value::Bytes(vec![0x04; 97])),
+            (
+                int(CCA_REALM_PUBLIC_KEY_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+        ]
+    }
+
+    fn get_platform_claims() -> Vec<(Value, Value)> {
+        let component = Value::Map(vec![
+            (int(CCA_SW_COMPONENT_TYPE), Value::Text("BL2".to_string())),
+            (
+                int(CCA_SW_COMPONENT_MEASUREMENT_VALUE),
+                Value::Bytes(vec![0x11; 32]),
+            ),
+            (
+                int(CCA_SW_COMPONENT_VERSION),
+                Value::Text("2.1.0".to_string()),
+            ),
+            (
+                int(CCA_SW_COMPONENT_SIGNER_ID),
+                Value::Bytes(vec![0x12; 32]),
+            ),
+            (
+                int(CCA_SW_COMPONENT_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+        ]);
+        vec![
+            (
+                int(CCA_PLATFORM_PROFILE),
+                Value::Text("tag:arm.com,2023:cca_platform#1.0.0".to_string()),
+            ),
+            (int(CCA_PLATFORM_CHALLENGE), Value::Bytes(vec![0x21; 32])),
+            (
+                int(CCA_PLATFORM_IMPLEMENTATION_ID),
+                Value::Bytes(vec![0x22; 32]),
+            ),
+            (int(CCA_PLATFORM_INSTANCE_ID), Value::Bytes(vec![0x23; 33])),
+            (int(CCA_PLATFORM_CONFIG), Value::Bytes(vec![0x24; 4])),
+            (int(CCA_PLATFORM_LIFECYCLE), int(0x3003)),
+            (
+                int(CCA_PLATFORM_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+            (
+                int(CCA_PLATFORM_SW_COMPONENTS),
+                Value::Array(vec![component]),
+            ),
+        ]
+    }
+
+    fn get_token(
+        platform_claims: Vec<(Value, Value)>,
+        realm_claims: Vec<(Value, Value)>,
+    ) -> Vec<u8> {
+        encode(&Value::Tag(
+            CCA_TOKEN_TAG,
+            Box::new(Value::Map(vec![
+                (
+                    int(CCA_PLATFORM_TOKEN_KEY),
+                    Value::Bytes(get_cose_sign1(platform_claims)),
+                ),
+                (
+                    int(CCA_REALM_TOKEN_KEY),
+                    Value::Bytes(get_cose_sign1(realm_claims)),
+                ),
+            ])),
+        ))
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let token: CcaToken =
+            CcReport::parse_cc_report(get_token(get_platform_claims(), get_realm_claims()))
+                .unwrap();
+
+        let realm = &token.realm_token;
+        assert_eq!(realm.cose.algorithm, Some(COSE_ALG_ES384));
+        assert_eq!(realm.cose.signature, vec![0x5a; 96]);
+        assert_eq!(realm.challenge, vec![0x01; 64]);
+        assert_eq!(realm.personalization_value, vec![0x02; 64]);
+        assert_eq!(realm.initial_measurement, vec![0x03; 32]);
+        assert_eq!(realm.extensible_measurements.len(), CCA_REM_COUNT);
+        assert_eq!(realm.extensible_measurements[3], vec![0x07; 32]);
+        assert_eq!(realm.hash_algo_id, "sha-256");
+        assert_eq!(realm.public_key, vec![0x04; 97]);
+        assert_eq!(realm.public_key_hash_algo_id, "sha-256");
+        assert_eq!(realm.profile, None);
+
+        let platform = &token.platform_token;
+        assert_eq!(
+            platform.profile,
+            Some("tag:arm.com,2023:cca_platform#1.0.0".to_string())
+        );
+        assert_eq!(platform.challenge, vec![0x21; 32]);
+        assert_eq!(platform.implementation_id, vec![0x22; 32]);
+        assert_eq!(platform.instance_id, vec![0x23; 33]);
+        assert_eq!(platform.config, vec![0x24; 4]);
+        assert_eq!(platform.lifecycle, 0x3003);
+        assert_eq!(platform.get_lifecycle(), CcaSecurityLifecycle::Secured);
+        assert_eq!(platform.hash_algo_id, "sha-256");
+        assert_eq!(platform.verification_service, None);
+        assert_eq!(
+            platform.sw_components,
+            vec![CcaSwComponent {
+                component_type: Some("BL2".to_string()),
+                measurement_value: vec![0x11; 32],
+                version: Some("2.1.0".to_string()),
+                signer_id: vec![0x12; 32],
+                hash_algo_id: Some("sha-256".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_missing_claims() {
+        let mut realm_claims = get_realm_claims();
+        realm_claims.retain(|(k, _)| k != &int(CCA_REALM_INITIAL_MEASUREMENT));
+        assert!(CcaToken::parse_cca_token(get_token(get_platform_claims(), realm_claims)).is_err());
+
+        let mut realm_claims = get_realm_claims();
+        realm_claims[3].1 = Value::Array(vec![Value::Bytes(vec![0; 32])]);
+        assert!(CcaToken::parse_cca_token(get_token(get_platform_claims(), realm_claims)).is_err());
+
+        let mut platform_claims = get_platform_claims();
+        platform_claims.retain(|(k, _)| k != &int(CCA_PLATFORM_LIFECYCLE));
+        assert!(CcaToken::parse_cca_token(get_token(platform_claims, get_realm_claims())).is_err());
+    }
+
+    #[test]
+    fn test_invalid_token() {
+        assert!(CcaToken::parse_cca_token(Vec::new()).is_err());
+        assert!(CcaToken::parse_cca_token(vec![0xd9, 0x01, 0x8f]).is_err());
+        let token = get_token(get_platform_claims(), get_realm_claims());
+        assert!(CcaToken::parse_cca_token(token[..token.len() - 1].to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_security_lifecycle() {
+        assert_eq!(
+            CcaSecurityLifecycle::new(0x0000),
+            CcaSecurityLifecycle::Unknown
+        );
+        assert_eq!(
+            CcaSecurityLifecycle::new(0x1000),
+            CcaSecurityLifecycle::AssemblyAndTest
+        );
+        assert_eq!(
+            CcaSecurityLifecycle::new(0x50ff),
+            CcaSecurityLifecycle::RecoverablePsaRotDebug
+        );
+        assert_eq!(
+            CcaSecurityLifecycle::new(0x6000),
+            CcaSecurityLifecycle::Decommissioned
+        );
+    }
+}
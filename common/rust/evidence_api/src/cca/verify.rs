@@ -0,0 +1,423 @@
+use crate::cca::common::*;
+use crate::cca::cose::*;
+use crate::cca::token::*;
+use anyhow::anyhow;
+use ciborium::value::Value;
+use log::info;
+use p256::ecdsa::signature::Verifier;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fs;
+use x509_cert::der::{Decode, DecodePem};
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+
+// COSE_Key parameters of EC2 keys defined at https://www.rfc-editor.org/rfc/rfc9053#section-7.1.1
+const COSE_KEY_KTY: i128 = 1;
+const COSE_KEY_KTY_EC2: i128 = 2;
+const COSE_KEY_EC2_CRV: i128 = -1;
+const COSE_KEY_EC2_X: i128 = -2;
+const COSE_KEY_EC2_Y: i128 = -3;
+
+impl CoseSign1 {
+    /***
+        Get the Sig_structure covered by the signature.
+        Definition reference:
+        https://www.rfc-editor.org/rfc/rfc9052#section-4.4
+        Sig_structure = [
+            context : "Signature1",
+            body_protected : empty_or_serialized_map,
+            external_aad : bstr,
+            payload : bstr
+        ]
+    */
+    pub fn get_sig_structure(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let sig_structure = Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(self.protected.clone()),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(self.payload.clone()),
+        ]);
+        let mut data = Vec::new();
+        match ciborium::ser::into_writer(&sig_structure, &mut data) {
+            Ok(_) => Ok(data),
+            Err(e) => Err(anyhow!("[get_sig_structure] encoding error: {:?}", e)),
+        }
+    }
+
+    /***
+        Verify the signature with the given public key.
+        Supported algorithms are ES256 and ES384.
+
+        Args:
+            public_key: SEC1 encoded EC public key
+
+        Returns:
+            true if the signature is valid
+    */
+    pub fn verify(&self, public_key: &[u8]) -> Result<bool, anyhow::Error> {
+        let data = self.get_sig_structure()?;
+        match self.algorithm {
+            Some(COSE_ALG_ES256) => {
+                let key = match p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key) {
+                    Ok(key) => key,
+                    Err(e) => return Err(anyhow!("[verify] invalid P-256 key: {:?}", e)),
+                };
+                Ok(match p256::ecdsa::Signature::from_slice(&self.signature) {
+                    Ok(signature) => key.verify(&data, &signature).is_ok(),
+                    Err(_) => false,
+                })
+            }
+            Some(COSE_ALG_ES384) => {
+                let key = match p384::ecdsa::VerifyingKey::from_sec1_bytes(public_key) {
+                    Ok(key) => key,
+                    Err(e) => return Err(anyhow!("[verify] invalid P-384 key: {:?}", e)),
+                };
+                Ok(match p384::ecdsa::Signature::from_slice(&self.signature) {
+                    Ok(signature) => key.verify(&data, &signature).is_ok(),
+                    Err(_) => false,
+                })
+            }
+            algorithm => Err(anyhow!(
+                "[verify] unsupported COSE algorithm: {:?}",
+                algorithm
+            )),
+        }
+    }
+}
+
+/***
+    Result of the offline verification of a CCA attestation token.
+    Each attribute tells whether the corresponding check passed.
+
+    Attributes:
+        realm_signature: the realm token signature verifies against the RAK in the token.
+        platform_signature: the platform token signature verifies against the CPAK.
+        rak_binding: the platform token challenge equals the hash of the RAK.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CcaTokenVerifyResult {
+    pub realm_signature: bool,
+    pub platform_signature: bool,
+    pub rak_binding: bool,
+}
+
+impl CcaTokenVerifyResult {
+    pub fn is_valid(&self) -> bool {
+        self.realm_signature && self.platform_signature && self.rak_binding
+    }
+
+    pub fn show(&self) {
+        info!("show the data of CcaTokenVerifyResult");
+        info!("realm_signature = {}", self.realm_signature);
+        info!("platform_signature = {}", self.platform_signature);
+        info!("rak_binding = {}", self.rak_binding);
+    }
+}
+
+impl CcaRealmToken {
+    // Get the RAK as SEC1 encoded EC point, whether it is carried raw or as COSE_Key
+    pub fn get_public_key(&self) -> Result<Vec<u8>, anyhow::Error> {
+        if self.public_key.first() == Some(&0x04) {
+            return Ok(self.public_key.clone());
+        }
+        let key = match decode_cbor(&self.public_key) {
+            Ok(Value::Map(key)) => key,
+            _ => return Err(anyhow!("[get_public_key] unsupported RAK encoding")),
+        };
+        if get_claim(&key, COSE_KEY_KTY).and_then(get_int) != Some(COSE_KEY_KTY_EC2) {
+            return Err(anyhow!("[get_public_key] RAK must be an EC2 key"));
+        }
+        match (
+            get_claim(&key, COSE_KEY_EC2_X).and_then(|v| v.as_bytes()),
+            get_claim(&key, COSE_KEY_EC2_Y).and_then(|v| v.as_bytes()),
+        ) {
+            (Some(x), Some(y)) if get_claim(&key, COSE_KEY_EC2_CRV).is_some() => {
+                Ok([&[0x04], &x[..], &y[..]].concat())
+            }
+            _ => Err(anyhow!("[get_public_key] incomplete EC2 key")),
+        }
+    }
+}
+
+impl CcaToken {
+    /***
+        Verify the token without any remote service.
+
+        Following checks are done:
+        1. the realm token signature against the RAK carried in the realm token.
+        2. the platform token signature against the CPAK supplied by the caller.
+        3. the platform token challenge equals the hash of the RAK, which binds
+           the realm token to the platform token.
+
+        Args:
+            cpak: the CPAK public key, as SEC1 encoded EC point or SubjectPublicKeyInfo
+                  in PEM or DER
+
+        Returns:
+            CcaTokenVerifyResult telling which check failed, or error if the keys or
+            the algorithms are not supported
+    */
+    pub fn verify(&self, cpak: &[u8]) -> Result<CcaTokenVerifyResult, anyhow::Error> {
+        let rak = self.realm_token.get_public_key()?;
+        let realm_signature = self.realm_token.cose.verify(&rak)?;
+        let platform_signature = self.platform_token.cose.verify(&load_public_key(cpak)?)?;
+        let rak_hash = compute_digest(
+            &self.realm_token.public_key_hash_algo_id,
+            &self.realm_token.public_key,
+        )?;
+
+        Ok(CcaTokenVerifyResult {
+            realm_signature,
+            platform_signature,
+            rak_binding: rak_hash == self.platform_token.challenge,
+        })
+    }
+
+    // Verify the token with the CPAK read from a file, see ``CcaToken::verify``
+    pub fn verify_with_cpak_file(
+        &self,
+        cpak_file: &str,
+    ) -> Result<CcaTokenVerifyResult, anyhow::Error> {
+        match fs::read(cpak_file) {
+            Ok(cpak) => self.verify(&cpak),
+            Err(e) => Err(anyhow!(
+                "[verify_with_cpak_file] error reading {}: {:?}",
+                cpak_file,
+                e
+            )),
+        }
+    }
+}
+
+// get the SEC1 encoded EC point from raw point or SubjectPublicKeyInfo in PEM or DER
+fn load_public_key(data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    if data.first() == Some(&0x04) {
+        return Ok(data.to_vec());
+    }
+    let spki = if data.starts_with(b"-----BEGIN") {
+        SubjectPublicKeyInfoOwned::from_pem(data)
+    } else {
+        SubjectPublicKeyInfoOwned::from_der(data)
+    };
+    match spki {
+        Ok(spki) => Ok(spki.subject_public_key.raw_bytes().to_vec()),
+        Err(e) => Err(anyhow!("[load_public_key] invalid public key: {:?}", e)),
+    }
+}
+
+// compute digest with the hash algorithm named as in the CCA token, e.g. "sha-256"
+fn compute_digest(hash_algo_id: &str, data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    match hash_algo_id {
+        "sha-256" => Ok(Sha256::digest(data).to_vec()),
+        "sha-384" => Ok(Sha384::digest(data).to_vec()),
+        "sha-512" => Ok(Sha512::digest(data).to_vec()),
+        _ => Err(anyhow!(
+            "[compute_digest] unsupported hash algorithm: {}",
+            hash_algo_id
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test_verify_cca_token {
+    use super::*;
+    use p256::pkcs8::{EncodePublicKey, LineEnding};
+    use std::env;
+
+    fn int(value: i128) -> Value {
+        Value::Integer(i64::try_from(value).unwrap().into())
+    }
+
+    fn encode(value: &Value) -> Vec<u8> {
+        let mut data = Vec::new();
+        ciborium::ser::into_writer(value, &mut data).unwrap();
+        data
+    }
+
+    fn get_rak() -> p384::ecdsa::SigningKey {
+        p384::ecdsa::SigningKey::from_slice(&[0x42; 48]).unwrap()
+    }
+
+    fn get_cpak() -> p256::ecdsa::SigningKey {
+        p256::ecdsa::SigningKey::from_slice(&[0x24; 32]).unwrap()
+    }
+
+    fn get_rak_public_key() -> Vec<u8> {
+        get_rak()
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    // build a tagged COSE_Sign1 with the signature made by sign over Sig_structure
+    fn get_cose_sign1(
+        alg: i128,
+        claims: Vec<(Value, Value)>,
+        sign: &dyn Fn(&[u8]) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let mut cose = CoseSign1 {
+            protected: encode(&Value::Map(vec![(int(COSE_HEADER_ALG), int(alg))])),
+            algorithm: Some(alg),
+            payload: encode(&Value::Map(claims)),
+            signature: Vec::new(),
+        };
+        cose.signature = sign(&cose.get_sig_structure().unwrap());
+        encode(&Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(cose.protected),
+                Value::Map(Vec::new()),
+                Value::Bytes(cose.payload),
+                Value::Bytes(cose.signature),
+            ])),
+        ))
+    }
+
+    fn get_token(rak_public_key: Vec<u8>, platform_challenge: Vec<u8>) -> CcaToken {
+        let realm_claims = vec![
+            (int(CCA_REALM_CHALLENGE), Value::Bytes(vec![0x01; 64])),
+            (
+                int(CCA_REALM_PERSONALIZATION_VALUE),
+                Value::Bytes(vec![0x02; 64]),
+            ),
+            (
+                int(CCA_REALM_INITIAL_MEASUREMENT),
+                Value::Bytes(vec![0x03; 32]),
+            ),
+            (
+                int(CCA_REALM_EXTENSIBLE_MEASUREMENTS),
+                Value::Array((4..8).map(|i| Value::Bytes(vec![i; 32])).collect()),
+            ),
+            (
+                int(CCA_REALM_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+            (int(CCA_REALM_PUBLIC_KEY), Value::Bytes(rak_public_key)),
+            (
+                int(CCA_REALM_PUBLIC_KEY_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+        ];
+        let platform_claims = vec![
+            (
+                int(CCA_PLATFORM_CHALLENGE),
+                Value::Bytes(platform_challenge),
+            ),
+            (
+                int(CCA_PLATFORM_IMPLEMENTATION_ID),
+                Value::Bytes(vec![0x22; 32]),
+            ),
+            (int(CCA_PLATFORM_INSTANCE_ID), Value::Bytes(vec![0x23; 33])),
+            (int(CCA_PLATFORM_CONFIG), Value::Bytes(Vec::new())),
+            (int(CCA_PLATFORM_LIFECYCLE), int(0x3000)),
+            (
+                int(CCA_PLATFORM_HASH_ALGO_ID),
+                Value::Text("sha-256".to_string()),
+            ),
+        ];
+
+        let realm_token = get_cose_sign1(COSE_ALG_ES384, realm_claims, &|data| {
+            let signature: p384::ecdsa::Signature =
+                p384::ecdsa::signature::Signer::sign(&get_rak(), data);
+            signature.to_bytes().to_vec()
+        });
+        let platform_token = get_cose_sign1(COSE_ALG_ES256, platform_claims, &|data| {
+            let signature: p256::ecdsa::Signature =
+                p256::ecdsa::signature::Signer::sign(&get_cpak(), data);
+            signature.to_bytes().to_vec()
+        });
+        let token = encode(&Value::Tag(
+            CCA_TOKEN_TAG,
+            Box::new(Value::Map(vec![
+                (int(CCA_PLATFORM_TOKEN_KEY), Value::Bytes(platform_token)),
+                (int(CCA_REALM_TOKEN_KEY), Value::Bytes(realm_token)),
+            ])),
+        ));
+        CcaToken::parse_cca_token(token).unwrap()
+    }
+
+    fn get_cpak_public_key() -> Vec<u8> {
+        get_cpak()
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let rak = get_rak_public_key();
+        let token = get_token(rak.clone(), Sha256::digest(&rak).to_vec());
+        let result = token.verify(&get_cpak_public_key()).unwrap();
+        assert!(result.is_valid());
+
+        let pem = get_cpak()
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        assert!(token.verify(pem.as_bytes()).unwrap().is_valid());
+
+        let cpak_file = env::temp_dir().join("test_verify_cca_token_cpak.pem");
+        fs::write(&cpak_file, pem).unwrap();
+        let result = token
+            .verify_with_cpak_file(cpak_file.to_str().unwrap())
+            .unwrap();
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_rak_as_cose_key() {
+        let point = get_rak().verifying_key().to_encoded_point(false);
+        let rak = encode(&Value::Map(vec![
+            (int(COSE_KEY_KTY), int(COSE_KEY_KTY_EC2)),
+            (int(COSE_KEY_EC2_CRV), int(2)),
+            (
+                int(COSE_KEY_EC2_X),
+                Value::Bytes(point.x().unwrap().to_vec()),
+            ),
+            (
+                int(COSE_KEY_EC2_Y),
+                Value::Bytes(point.y().unwrap().to_vec()),
+            ),
+        ]));
+        let token = get_token(rak.clone(), Sha256::digest(&rak).to_vec());
+        assert_eq!(
+            token.realm_token.get_public_key().unwrap(),
+            get_rak_public_key()
+        );
+        assert!(token.verify(&get_cpak_public_key()).unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_rak_binding_mismatch() {
+        let token = get_token(get_rak_public_key(), vec![0; 32]);
+        let result = token.verify(&get_cpak_public_key()).unwrap();
+        assert!(result.realm_signature);
+        assert!(result.platform_signature);
+        assert!(!result.rak_binding);
+    }
+
+    #[test]
+    fn test_wrong_keys() {
+        let rak = get_rak_public_key();
+        let token = get_token(rak.clone(), Sha256::digest(&rak).to_vec());
+        let other = p256::ecdsa::SigningKey::from_slice(&[0x25; 32]).unwrap();
+        let other = other.verifying_key().to_encoded_point(false);
+        let result = token.verify(other.as_bytes()).unwrap();
+        assert!(result.realm_signature);
+        assert!(!result.platform_signature);
+
+        let mut tampered = token.clone();
+        tampered.realm_token.cose.payload[0] ^= 0x01;
+        let result = tampered.verify(&get_cpak_public_key()).unwrap();
+        assert!(!result.realm_signature);
+        assert!(result.platform_signature);
+
+        // P-256 CPAK cannot verify ES384
+        assert!(token
+            .realm_token
+            .cose
+            .verify(&get_cpak_public_key())
+            .is_err());
+    }
+}
@@ -1,6 +1,11 @@
-use crate::api_data::ReplayResult;
+use crate::api_data::{
+    ImaTemplateMismatch, ReplayDiagnosis, ReplayDivergence, ReplayOptions, ReplayReport,
+    ReplayResult, ReplayTraceEntry,
+};
 use crate::binary_blob::*;
+use crate::ima::*;
 use crate::tcg::*;
+use crate::tcgcel::*;
 use anyhow::anyhow;
 use hashbrown::HashMap;
 use hex;
@@ -23,6 +28,7 @@ use sha2::{Digest, Sha256, Sha384, Sha512};
        event_size: size of the event
        event: raw event information
        extra_info: extra information in the event
+       ima_template: decoded template of IMA events
 */
 #[derive(Clone)]
 pub struct TcgEventLog {
@@ -33,14 +39,18 @@ pub struct TcgEventLog {
     pub event_size: u32,
     pub event: Vec<u8>,
     pub extra_info: HashMap<String, String>,
+    pub ima_template: Option<Box<ImaTemplate>>,
 }
 
 impl TcgEventLog {
-    fn format_event_log(&self, parse_format: u8) -> EventLogEntry {
+    fn format_event_log(&self, parse_format: u8) -> Result<EventLogEntry, anyhow::Error> {
         match parse_format {
-            TCG_PCCLIENT_FORMAT => self.to_tcg_pcclient_format(),
+            TCG_PCCLIENT_FORMAT => Ok(self.to_tcg_pcclient_format()),
             TCG_CANONICAL_FORMAT => self.to_tcg_canonical_format(),
-            0_u8 | 3_u8..=u8::MAX => todo!(),
+            _ => Err(anyhow!(
+                "[format_event_log] invalid event log format {}",
+                parse_format
+            )),
         }
     }
 
@@ -61,11 +71,58 @@ impl TcgEventLog {
             digests: self.digests.clone(),
             event_size: self.event_size,
             event: self.event.clone(),
+            ima_template: self.ima_template.clone(),
         })
     }
 
-    fn to_tcg_canonical_format(&self) -> EventLogEntry {
-        todo!()
+    /***
+        Convert the event log into TCG canonical event log record.
+        IMA events are carried as ima_template content with the binary template data of the
+        measurement list, rebuilt from the decoded template for ascii IMA events, others as
+        pcclient_std content. Ascii IMA events of templates unknown to the parser cannot be
+        converted.
+    */
+    fn to_tcg_canonical_format(&self) -> Result<EventLogEntry, anyhow::Error> {
+        // the digest of the specification ID event is a zero filled field of no algorithm,
+        // which is not extended
+        let digests = if self.event_type == EV_NO_ACTION && self.rec_num == 0 && self.imr_index == 0
+        {
+            Vec::new()
+        } else {
+            self.digests.clone()
+        };
+
+        let content = if self.event_type == IMA_MEASUREMENT_EVENT {
+            TcgCelContent::ImaTemplate {
+                template_name: match self.extra_info.get("template_name") {
+                    Some(template_name) => template_name.to_string(),
+                    None => String::new(),
+                },
+                template_data: match &self.ima_template {
+                    Some(ima_template) => ima_template.get_template_data()?,
+                    None if self.extra_info.get("event_format").map(String::as_str)
+                        == Some("ascii") =>
+                    {
+                        return Err(anyhow!(
+                            "[to_tcg_canonical_format] no binary template data for ascii IMA event of unknown template"
+                        ))
+                    }
+                    None => self.event.clone(),
+                },
+            }
+        } else {
+            TcgCelContent::PcClientStd {
+                event_type: self.event_type,
+                event_data: self.event.clone(),
+            }
+        };
+
+        Ok(EventLogEntry::TcgCanonicalEvent(TcgCanonicalEvent {
+            rec_num: self.rec_num,
+            index: TcgCelIndex::Pcr(self.imr_index),
+            digests,
+            content,
+        }))
     }
 
     pub fn show(&self) {
@@ -85,6 +142,348 @@ impl TcgEventLog {
     }
 }
 
+/***
+    Position of EventLogParser in boot time event logs, to resume parsing from.
+
+    Attributes:
+        offset: byte offset of the next event log
+        count: number of event logs before offset
+        record_numbers: next record number of each IMR
+*/
+#[derive(Clone, Default, PartialEq)]
+pub struct EventLogPosition {
+    pub offset: usize,
+    pub count: u32,
+    pub record_numbers: [u32; 24],
+}
+
+/***
+    Streaming parser of boot time event logs.
+    It parses the event logs over the borrowed raw data one at a time and yields them as
+    EventLogEntry in the requested format, the specification ID event first, until the end
+    of the data or the 0xFFFFFFFF padding of CCEL. Parsing stops at the first error.
+    Parsing can resume from a position returned by position(), e.g. to parse the events
+    appended to the event logs since, or from a record number with seek_record().
+
+    Attributes:
+        data: raw data containing boot time event logs
+        parse_format: event log format to yield
+        position: position of the next event log
+        spec_id_header_event: specification ID event declaring the digest sizes
+        imr_index_offset: difference between the register index in the event logs and
+                          imr_index, 1 for CCEL and 0 for TPM event logs
+        done: whether the end of the event logs or an error is reached
+*/
+pub struct EventLogParser<'a> {
+    data: &'a [u8],
+    parse_format: u8,
+    position: EventLogPosition,
+    spec_id_header_event: TcgEfiSpecIdEvent,
+    imr_index_offset: u32,
+    done: bool,
+}
+
+impl<'a> EventLogParser<'a> {
+    /***
+        Create a parser from the beginning of the data.
+
+        Args:
+            data: raw data containing boot time event logs
+            parse_format: event log format to yield, TCG_PCCLIENT_FORMAT or
+                          TCG_CANONICAL_FORMAT
+
+        Returns:
+            The parser yielding the event logs from the specification ID event
+    */
+    pub fn new(data: &'a [u8], parse_format: u8) -> Result<EventLogParser<'a>, anyhow::Error> {
+        if parse_format != TCG_PCCLIENT_FORMAT && parse_format != TCG_CANONICAL_FORMAT {
+            return Err(anyhow!("[new] invalid event log format {}", parse_format));
+        }
+        Ok(EventLogParser {
+            data,
+            parse_format,
+            position: EventLogPosition::default(),
+            spec_id_header_event: TcgEfiSpecIdEvent::new(),
+            imr_index_offset: 1,
+            done: false,
+        })
+    }
+
+    /***
+        Create a parser resuming from a position returned by position().
+        The specification ID event at the beginning of the data is parsed again for the
+        digest sizes of the events.
+
+        Args:
+            data: raw data containing boot time event logs, the data parsed before the
+                  position must be unchanged
+            parse_format: event log format to yield
+            position: position to resume from
+
+        Returns:
+            The parser yielding the event logs from the position
+    */
+    pub fn resume(
+        data: &'a [u8],
+        parse_format: u8,
+        position: EventLogPosition,
+    ) -> Result<EventLogParser<'a>, anyhow::Error> {
+        let mut parser = EventLogParser::new(data, parse_format)?;
+        if position.count > 0 {
+            parser.seek_record(1)?;
+        }
+        if position.offset < parser.position.offset
+            || position.offset > data.len()
+            || position.count < parser.position.count
+            || (position.count == 0 && position.offset != 0)
+        {
+            return Err(anyhow!(
+                "[resume] invalid position at offset {} after {} event logs",
+                position.offset,
+                position.count
+            ));
+        }
+        parser.position = position;
+        Ok(parser)
+    }
+
+    /***
+        Move the parser to the event log of the record number, counting all event logs
+        from the specification ID event as 0, so the next yielded event log is that one.
+
+        Args:
+            count: record number of the next event log to yield
+    */
+    pub fn seek_record(&mut self, count: u32) -> Result<(), anyhow::Error> {
+        if count < self.position.count {
+            *self = EventLogParser::new(self.data, self.parse_format)?;
+        }
+        while self.position.count < count {
+            match self.parse_next() {
+                Ok(Some(_)) => (),
+                Ok(None) => {
+                    return Err(anyhow!(
+                        "[seek_record] record {} beyond the {} event logs",
+                        count,
+                        self.position.count
+                    ))
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn position(&self) -> EventLogPosition {
+        self.position.clone()
+    }
+
+    pub fn spec_id_header_event(&self) -> &TcgEfiSpecIdEvent {
+        &self.spec_id_header_event
+    }
+
+    pub fn imr_index_offset(&self) -> u32 {
+        self.imr_index_offset
+    }
+
+    // Parse the event log at the position, None at the end of the event logs
+    fn parse_next(&mut self) -> Result<Option<TcgEventLog>, anyhow::Error> {
+        let mut reader = BinaryReader::new(&self.data[self.position.offset..]);
+        if reader.is_empty() {
+            return Ok(None);
+        }
+        let pcr_index = reader.read_u32_le()?;
+        if pcr_index == 0xFFFFFFFF {
+            return Ok(None);
+        }
+        let event_type = reader.read_u32_le()?;
+
+        let event_log = if event_type == EV_NO_ACTION && self.position.count == 0 {
+            self.parse_spec_id_event_log(pcr_index, event_type, &mut reader)?
+        } else {
+            self.parse_event_log(pcr_index, event_type, &mut reader)?
+        };
+        self.position.offset += reader.position();
+        self.position.count += 1;
+        Ok(Some(event_log))
+    }
+
+    /***
+       Fetch the record number maintained separately by index.
+       Increment the number to be prepared for next measurement.
+
+       Args:
+           pcr_index: the register index in the event logs
+
+       Returns:
+           The imr index and the record number
+    */
+    fn get_record_number(&mut self, pcr_index: u32) -> Result<(u32, u32), anyhow::Error> {
+        let imr_index = match pcr_index.checked_sub(self.imr_index_offset) {
+            Some(imr_index) if (imr_index as usize) < self.position.record_numbers.len() => {
+                imr_index
+            }
+            _ => {
+                return Err(anyhow!(
+                    "[get_record_number] invalid register index {}",
+                    pcr_index
+                ))
+            }
+        };
+        let rec_num = self.position.record_numbers[imr_index as usize];
+        self.position.record_numbers[imr_index as usize] += 1;
+        Ok((imr_index, rec_num))
+    }
+
+    /***
+        Parse TCG specification Id event according to TCG spec at
+        https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf.
+        Event Structure:
+        typedef tdTCG_PCClientPCREvent {
+            2735 UINT32 pcrIndex;
+            UINT32 eventType;
+            BYTE digest[20];
+            UINT32 eventDataSize;
+            BYTE event[eventDataSize]; //This is actually a TCG_EfiSpecIDEventStruct
+        } TCG_PCClientPCREvent;
+        Args:
+            pcr_index: pcrIndex of the event
+            event_type: eventType of the event
+            reader: reader of the event after eventType
+        Returns:
+            A common TcgEventLog containing the Specification ID version event
+    */
+    fn parse_spec_id_event_log(
+        &mut self,
+        pcr_index: u32,
+        event_type: u32,
+        reader: &mut BinaryReader,
+    ) -> Result<TcgEventLog, anyhow::Error> {
+        // the specification ID event is logged to PCR 0 in TPM event logs
+        self.imr_index_offset = if pcr_index == 0 { 0 } else { 1 };
+        let (imr_index, rec_num) = self.get_record_number(pcr_index)?;
+
+        let digests = vec![TcgDigest {
+            algo_id: TPM_ALG_ERROR,
+            hash: reader.read_bytes(20)?.to_vec(),
+        }];
+        let event_size = reader.read_u32_le()?;
+        let event = reader.read_bytes(event_size as usize)?;
+
+        // Parse EFI Spec Id Event structure
+        self.spec_id_header_event = match TcgEfiSpecIdEvent::parse(event) {
+            Ok(spec_id_header_event) => spec_id_header_event,
+            Err(e) => {
+                return Err(anyhow!(
+                    "[parse_spec_id_event_log] invalid specification ID event: {:?}",
+                    e
+                ))
+            }
+        };
+
+        Ok(TcgEventLog {
+            rec_num,
+            imr_index,
+            event_type,
+            digests,
+            event_size,
+            event: event.to_vec(),
+            extra_info: HashMap::new(),
+            ima_template: None,
+        })
+    }
+
+    /***
+        Parse TCG event log body as single event log entry (TcgImrEventLogEntry) defined at
+        https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf
+        typedef struct tdTCG_PCR_EVENT2{
+            UINT32 pcrIndex;
+            UINT32 eventType;
+            TPML_DIGEST_VALUES digests;
+            UINT32 eventSize;
+            BYTE event[eventSize];
+        } TCG_PCR_EVENT2;
+        Args:
+            pcr_index: pcrIndex of the event
+            event_type: eventType of the event
+            reader: reader of the event after eventType
+        Returns:
+            A TcgEventLog containing the event information
+    */
+    fn parse_event_log(
+        &mut self,
+        pcr_index: u32,
+        event_type: u32,
+        reader: &mut BinaryReader,
+    ) -> Result<TcgEventLog, anyhow::Error> {
+        let (imr_index, rec_num) = self.get_record_number(pcr_index)?;
+
+        // Fetch digest count and get each digest and its algorithm
+        let digest_count = reader.read_u32_le()?;
+        let mut digests: Vec<TcgDigest> = Vec::new();
+        for _ in 0..digest_count {
+            let algo_id = reader.read_u16_le()?;
+            let digest_size = match self.spec_id_header_event.get_digest_size(algo_id) {
+                Some(digest_size) => digest_size,
+                None => {
+                    return Err(anyhow!(
+                        "[parse_event_log] No algorithm with such algo_id {}",
+                        algo_id
+                    ))
+                }
+            };
+            digests.push(TcgDigest {
+                algo_id,
+                hash: reader.read_bytes(digest_size as usize)?.to_vec(),
+            });
+        }
+
+        let event_size = reader.read_u32_le()?;
+        let event = reader.read_bytes(event_size as usize)?.to_vec();
+
+        Ok(TcgEventLog {
+            rec_num,
+            imr_index,
+            event_type,
+            digests,
+            event_size,
+            event,
+            extra_info: HashMap::new(),
+            ima_template: None,
+        })
+    }
+}
+
+impl<'a> Iterator for EventLogParser<'a> {
+    type Item = Result<EventLogEntry, anyhow::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.parse_next() {
+            Ok(Some(event_log)) => match event_log.format_event_log(self.parse_format) {
+                Ok(event_log) => Some(Ok(event_log)),
+                Err(e) => {
+                    self.done = true;
+                    Some(Err(e))
+                }
+            },
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 /***
     EventLogs struct.
     This struct contains the all event logs available on the system.
@@ -92,7 +491,13 @@ impl TcgEventLog {
     Attributes:
         boot_time_data: raw data containing all boot time event logs
         runtime_data: raw data containing runtime event logs(now IMA events)
+        run_time_binary_data: binary IMA runtime measurement lists with the algorithm id of
+                              the template hash, e.g. binary_runtime_measurements with SHA1
+                              and binary_runtime_measurements_sha256 with SHA256
         event_logs: all parsed event logs
+        imr_index_offset: difference between the register index in the boot time event log
+                          and imr_index, 1 for CCEL which is logged with the CC measurement
+                          register index, 0 for TPM event logs logged with the PCR index
         count: total number of event logs
         parse_format: event log format used
 */
@@ -101,10 +506,12 @@ pub struct EventLogs {
     pub spec_id_header_event: TcgEfiSpecIdEvent,
     pub boot_time_data: Vec<u8>,
     pub run_time_data: Vec<String>,
+    pub run_time_binary_data: Vec<(u16, Vec<u8>)>,
     pub event_logs: Vec<EventLogEntry>,
     pub count: u32,
     pub parse_format: u8,
     pub event_logs_record_number_list: [u32; 24],
+    pub imr_index_offset: u32,
 }
 
 impl EventLogs {
@@ -113,10 +520,33 @@ impl EventLogs {
             spec_id_header_event: TcgEfiSpecIdEvent::new(),
             boot_time_data,
             run_time_data,
+            run_time_binary_data: Vec::new(),
             event_logs: Vec::new(),
             count: 0,
             parse_format,
             event_logs_record_number_list: [0; 24],
+            imr_index_offset: 1,
+        }
+    }
+
+    /***
+        Create EventLogs with the binary IMA runtime measurement lists as runtime event logs.
+        The lists of different banks hold the same entries, which are merged into events
+        carrying the template hash of each bank.
+
+        Args:
+            boot_time_data: raw data containing all boot time event logs
+            run_time_binary_data: list of (algorithm id of the template hash, list content)
+            parse_format: event log format used
+    */
+    pub fn new_with_binary_ima(
+        boot_time_data: Vec<u8>,
+        run_time_binary_data: Vec<(u16, Vec<u8>)>,
+        parse_format: u8,
+    ) -> EventLogs {
+        EventLogs {
+            run_time_binary_data,
+            ..EventLogs::new(boot_time_data, Vec::new(), parse_format)
         }
     }
 
@@ -131,10 +561,13 @@ impl EventLogs {
         start: Option<u32>,
         count: Option<u32>,
     ) -> Result<Vec<EventLogEntry>, anyhow::Error> {
-        match self.parse() {
-            Ok(_) => (),
-            Err(e) => {
-                return Err(anyhow!("[select] error in parse function {:?}", e));
+        // event logs are parsed once, parse() leaves no event log only on empty event logs
+        if self.event_logs.is_empty() {
+            match self.parse() {
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(anyhow!("[select] error in parse function {:?}", e));
+                }
             }
         }
 
@@ -169,6 +602,58 @@ impl EventLogs {
         Ok((self.event_logs[begin as usize..end as usize]).to_vec())
     }
 
+    /***
+        Parse ascii IMA events appended to the runtime event logs since they are parsed,
+        e.g. the lines appended to ascii_runtime_measurements, without parsing the event
+        logs again. The new events continue the record numbers of the event logs and are
+        kept only if all of them are parsed.
+
+        Args:
+            run_time_data: new ascii IMA events
+
+        Returns:
+            The new event logs in the format of the event logs
+    */
+    pub fn append_run_time_data(
+        &mut self,
+        run_time_data: Vec<String>,
+    ) -> Result<Vec<EventLogEntry>, anyhow::Error> {
+        if self.event_logs.is_empty() {
+            match self.parse() {
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[append_run_time_data] error in parse function {:?}",
+                        e
+                    ));
+                }
+            }
+        }
+
+        let record_numbers = self.event_logs_record_number_list;
+        let mut event_logs = Vec::new();
+        for data in &run_time_data {
+            match self
+                .parse_ima_event_log(data)
+                .and_then(|event_log| event_log.format_event_log(self.parse_format))
+            {
+                Ok(event_log) => event_logs.push(event_log),
+                Err(e) => {
+                    self.event_logs_record_number_list = record_numbers;
+                    return Err(anyhow!(
+                        "[append_run_time_data] error in parse_ima_event_log function {:?}",
+                        e
+                    ));
+                }
+            }
+        }
+
+        self.run_time_data.extend(run_time_data);
+        self.event_logs.extend(event_logs.iter().cloned());
+        self.count += event_logs.len() as u32;
+        Ok(event_logs)
+    }
+
     /***
        Fetch the record number maintained separately by index.
        Increment the number to be prepared for next measurement.
@@ -189,58 +674,46 @@ impl EventLogs {
         Parse event log data into TCG compatible forms.
         Go through all event log data and parse the contents accordingly
         Save the parsed event logs into EventLogs.
+        Boot time event logs are parsed by EventLogParser, runtime IMA event logs continue
+        the record numbers of the boot time ones.
     */
     fn parse(&mut self) -> Result<bool, anyhow::Error> {
         if self.boot_time_data.is_empty() {
             return Err(anyhow!("[parse] no boot time eventlog provided"));
         }
 
-        let mut index = 0;
-        while index < self.boot_time_data.len() {
-            let start = index;
-            let imr = get_u32(self.boot_time_data[index..index + 4].to_vec());
-            index += 4;
-            let event_type = get_u32(self.boot_time_data[index..index + 4].to_vec());
-            if imr == 0xFFFFFFFF {
-                break;
+        self.event_logs = Vec::new();
+        self.count = 0;
+        let mut parser = match EventLogParser::new(&self.boot_time_data, self.parse_format) {
+            Ok(parser) => parser,
+            Err(e) => {
+                return Err(anyhow!("[parse] error in EventLogParser {:?}", e));
             }
-
-            if event_type == EV_NO_ACTION && self.count == 0 {
-                match self.parse_spec_id_event_log(self.boot_time_data[start..].to_vec()) {
-                    Ok((spec_id_event, event_len)) => {
-                        index = start + event_len as usize;
-                        self.event_logs
-                            .push(spec_id_event.format_event_log(self.parse_format));
-                        self.count += 1;
-                    }
-                    Err(e) => {
-                        return Err(anyhow!(
-                            "[parse] error in parse_spec_id_event_log function {:?}",
-                            e
-                        ));
-                    }
-                }
-            } else {
-                match self.parse_event_log(self.boot_time_data[start..].to_vec()) {
-                    Ok((event_log, event_len)) => {
-                        index = start + event_len as usize;
-                        self.event_logs
-                            .push(event_log.format_event_log(self.parse_format));
-                        self.count += 1;
-                    }
-                    Err(e) => {
-                        return Err(anyhow!("[parse] error in parse_event_log function {:?}", e));
-                    }
+        };
+        let mut event_logs = Vec::new();
+        for event_log in &mut parser {
+            match event_log {
+                Ok(event_log) => event_logs.push(event_log),
+                Err(e) => {
+                    return Err(anyhow!("[parse] error in EventLogParser {:?}", e));
                 }
             }
         }
+        let position = parser.position();
+        self.spec_id_header_event = parser.spec_id_header_event().clone();
+        self.imr_index_offset = parser.imr_index_offset();
+        self.event_logs = event_logs;
+        self.count = position.count;
+        self.event_logs_record_number_list = position.record_numbers;
 
         if !self.run_time_data.is_empty() {
             for index in 0..self.run_time_data.len() {
-                match self.parse_ima_event_log(&self.run_time_data[index].clone()) {
+                match self
+                    .parse_ima_event_log(&self.run_time_data[index].clone())
+                    .and_then(|event_log| event_log.format_event_log(self.parse_format))
+                {
                     Ok(event_log) => {
-                        self.event_logs
-                            .push(event_log.format_event_log(self.parse_format));
+                        self.event_logs.push(event_log);
                         self.count += 1;
                     }
                     Err(e) => {
@@ -253,252 +726,332 @@ impl EventLogs {
             }
         }
 
+        if !self.run_time_binary_data.is_empty() {
+            match self.parse_ima_binary_event_logs() {
+                Ok(event_logs) => {
+                    for event_log in event_logs {
+                        self.event_logs
+                            .push(event_log.format_event_log(self.parse_format)?);
+                        self.count += 1;
+                    }
+                }
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[parse] error in parse_ima_binary_event_logs function {:?}",
+                        e
+                    ));
+                }
+            }
+        }
+
         Ok(true)
     }
 
     /***
-        Parse TCG specification Id event according to TCG spec at
-        https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf.
-        Event Structure:
-        typedef tdTCG_PCClientPCREvent {
-            2735 UINT32 pcrIndex;
-            UINT32 eventType;
-            BYTE digest[20];
-            UINT32 eventDataSize;
-            BYTE event[eventDataSize]; //This is actually a TCG_EfiSpecIDEventStruct
-        } TCG_PCClientPCREvent;
-        Args:
-            data: event log data in bytes
-        Returns:
-            A common TcgEventLog containing the Specification ID version event
-            An int specifying the event size
-    */
-    fn parse_spec_id_event_log(
-        &mut self,
-        data: Vec<u8>,
-    ) -> Result<(TcgEventLog, u32), anyhow::Error> {
-        let mut index = 0;
-
-        let imr_index = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let header_imr = imr_index - 1;
-        let header_event_type = get_u32(data[index..index + 4].to_vec());
-        index += 4;
+       Parse ascii IMA events gathered during runtime.
 
-        let rec_num = self.get_record_number(header_imr);
+       Sample event and format:
+       IMR index | Template hash | Template name | Event data according to template
+       10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha384:74ccc46104f42db070375e6876a23aeaa3c2ae458888475baaa171c3fb7001b0fc385ed08420d5f60620924fc64d0b80 /etc/lsb-release
 
-        let digest_hash = data[index..index + 20].try_into().unwrap();
-        index += 20;
-        let mut digests: Vec<TcgDigest> = Vec::new();
-        let digest = TcgDigest {
-            algo_id: TPM_ALG_ERROR,
-            hash: digest_hash,
-        };
-        digests.push(digest);
+       Args:
+           event: IMA ascii raw event
 
-        let header_event_size = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let header_event = data[index..index + header_event_size as usize]
-            .try_into()
-            .unwrap();
-        let specification_id_header = TcgEventLog {
-            rec_num,
-            imr_index: header_imr,
-            event_type: header_event_type,
-            digests,
-            event_size: header_event_size,
-            event: header_event,
-            extra_info: HashMap::new(),
+       Returns:
+           A TcgEventLog object containing the ima event log
+    */
+    fn parse_ima_event_log(&mut self, data: &str) -> Result<TcgEventLog, anyhow::Error> {
+        /*  after the split, the elements vec has following mapping:
+               elements[0] => IMR index
+               elements[1] => Template hash
+               elements[2] => Template name
+               elements[3] => Event data according to template
+        */
+        let line = data.trim_start_matches(' ').trim_end_matches(['\r', '\n']);
+        let elements: Vec<&str> = line.splitn(4, ' ').collect();
+        if elements.len() < 4 {
+            return Err(anyhow!(
+                "[parse_ima_event_log] incomplete IMA event: {}",
+                line
+            ));
+        }
+
+        let imr_index: u32 = match elements[0].parse() {
+            Ok(imr_index) if (imr_index as usize) < self.event_logs_record_number_list.len() => {
+                imr_index
+            }
+            _ => {
+                return Err(anyhow!(
+                    "[parse_ima_event_log] invalid IMR index: {}",
+                    elements[0]
+                ))
+            }
         };
 
-        // Parse EFI Spec Id Event structure
-        let spec_id_signature = data[index..index + 16].try_into().unwrap();
-        index += 16;
-        let spec_id_platform_cls = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let spec_id_version_minor = get_u8(data[index..index + 1].to_vec());
-        index += 1;
-        let spec_id_version_major = get_u8(data[index..index + 1].to_vec());
-        index += 1;
-        let spec_id_errata = get_u8(data[index..index + 1].to_vec());
-        index += 1;
-        let spec_id_uint_size = get_u8(data[index..index + 1].to_vec());
-        index += 1;
-        let spec_id_num_of_algo = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let mut spec_id_digest_sizes: Vec<TcgEfiSpecIdEventAlgorithmSize> = Vec::new();
-
-        for _ in 0..spec_id_num_of_algo {
-            let algo_id = get_u16(data[index..index + 2].to_vec());
-            index += 2;
-            let digest_size = get_u16(data[index..index + 2].to_vec());
-            index += 2;
-            spec_id_digest_sizes.push(TcgEfiSpecIdEventAlgorithmSize {
-                algo_id,
-                digest_size: digest_size.into(),
-            });
+        let hash = match hex::decode(elements[1]) {
+            Ok(hash) => hash,
+            Err(e) => {
+                return Err(anyhow!(
+                    "[parse_ima_event_log] invalid template hash {}: {:?}",
+                    elements[1],
+                    e
+                ))
+            }
+        };
+        let algo_id = match u8::try_from(hash.len()) {
+            Ok(digest_size) => TcgDigest::get_algorithm_id_from_digest_size(digest_size),
+            Err(_) => TPM_ALG_ERROR,
+        };
+        if algo_id == TPM_ALG_ERROR {
+            return Err(anyhow!(
+                "[parse_ima_event_log] unknown algorithm of template hash {}",
+                elements[1]
+            ));
         }
+        let digests = vec![TcgDigest { algo_id, hash }];
 
-        let spec_id_vendor_size = get_u8(data[index..index + 1].to_vec());
-        index += 1;
-        let mut spec_id_vendor_info = Vec::new();
-        if spec_id_vendor_size > 0 {
-            spec_id_vendor_info = data[index..index + spec_id_vendor_size as usize]
-                .try_into()
-                .unwrap();
-        }
-        index += spec_id_vendor_size as usize;
-
-        self.spec_id_header_event = TcgEfiSpecIdEvent {
-            signature: spec_id_signature,
-            platform_class: spec_id_platform_cls,
-            spec_version_minor: spec_id_version_minor,
-            spec_version_major: spec_id_version_major,
-            spec_errata: spec_id_errata,
-            uintn_ize: spec_id_uint_size,
-            number_of_algorithms: spec_id_num_of_algo,
-            digest_sizes: spec_id_digest_sizes,
-            vendor_info_size: spec_id_vendor_size,
-            vendor_info: spec_id_vendor_info,
+        // templates unknown to the parser are kept undecoded
+        let ima_template = match get_template_fields(elements[2]) {
+            Ok(_) => Some(Box::new(ImaTemplate::parse_ascii(
+                elements[2],
+                elements[3],
+            )?)),
+            Err(_) => None,
         };
+        let rec_num = self.get_record_number(imr_index);
+        let event = elements[3].trim_end_matches(' ').as_bytes().to_vec();
+        let event_size = event.len() as u32;
+
+        let mut extra_info = HashMap::new();
+        extra_info.insert("template_name".to_string(), elements[2].to_string());
+        extra_info.insert("event_format".to_string(), "ascii".to_string());
 
-        Ok((specification_id_header, index.try_into().unwrap()))
+        Ok(TcgEventLog {
+            rec_num,
+            imr_index,
+            event_type: IMA_MEASUREMENT_EVENT,
+            digests,
+            event_size,
+            event,
+            extra_info,
+            ima_template,
+        })
     }
 
     /***
-        Parse TCG event log body as single event log entry (TcgImrEventLogEntry) defined at
-        https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf
-        typedef struct tdTCG_PCR_EVENT2{
-            UINT32 pcrIndex;
-            UINT32 eventType;
-            TPML_DIGEST_VALUES digests;
-            UINT32 eventSize;
-            BYTE event[eventSize];
-        } TCG_PCR_EVENT2;
-        Args:
-            data: event log data in bytes
-        Returns:
-            A TcgImrEvent containing the event information
-            An int specifying the event size
-    */
-    fn parse_event_log(&mut self, data: Vec<u8>) -> Result<(TcgEventLog, u32), anyhow::Error> {
-        let mut index = 0;
-
-        let mut imr_index = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        imr_index -= 1;
-        let event_type = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-
-        let rec_num = self.get_record_number(imr_index);
+       Parse binary IMA runtime measurement lists gathered during runtime.
+       Entries of the lists of different banks are matched by position and must only
+       differ in the template hash.
 
-        // Fetch digest count and get each digest and its algorithm
-        let digest_count = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let mut digests: Vec<TcgDigest> = Vec::new();
-        for _ in 0..digest_count {
-            let alg_id = get_u16(data[index..index + 2].to_vec());
-            index += 2;
-            let mut pos = 0;
+       Returns:
+           A list of TcgEventLog objects containing the ima event logs
+    */
+    fn parse_ima_binary_event_logs(&mut self) -> Result<Vec<TcgEventLog>, anyhow::Error> {
+        let mut banks = Vec::new();
+        for (algo_id, data) in &self.run_time_binary_data {
+            let digest_size = TcgDigest::get_digest_size_from_algorithm_id(*algo_id);
+            if digest_size == 0 {
+                return Err(anyhow!(
+                    "[parse_ima_binary_event_logs] unknown algorithm of template hash {}",
+                    algo_id
+                ));
+            }
+            banks.push((
+                *algo_id,
+                ImaMeasurement::parse_binary_list(data, digest_size as usize)?,
+            ));
+        }
 
-            while pos < self.spec_id_header_event.digest_sizes.len() {
-                if self.spec_id_header_event.digest_sizes[pos].algo_id == alg_id {
-                    break;
-                }
-                pos += 1;
+        let measurements = &banks[0].1;
+        for (algo_id, bank) in &banks[1..] {
+            if bank.len() != measurements.len() {
+                return Err(anyhow!(
+                    "[parse_ima_binary_event_logs] {} entries for algorithm {}, {} expected",
+                    bank.len(),
+                    algo_id,
+                    measurements.len()
+                ));
             }
+        }
 
-            if pos == self.spec_id_header_event.digest_sizes.len() {
+        let mut event_logs = Vec::new();
+        for (index, measurement) in measurements.iter().enumerate() {
+            if measurement.imr_index as usize >= self.event_logs_record_number_list.len() {
                 return Err(anyhow!(
-                    "[parse_event_log] No algorithm with such algo_id {}",
-                    alg_id
+                    "[parse_ima_binary_event_logs] invalid IMR index {} of entry {}",
+                    measurement.imr_index,
+                    index
                 ));
             }
 
-            let alg = &self.spec_id_header_event.digest_sizes[pos];
-            let digest_size = alg.digest_size;
-            let digest_data = data[index..index + digest_size as usize]
-                .try_into()
-                .unwrap();
-            index += digest_size as usize;
-            let digest = TcgDigest {
-                algo_id: alg_id,
-                hash: digest_data,
-            };
-            digests.push(digest);
-        }
+            let mut digests = Vec::new();
+            for (algo_id, bank) in &banks {
+                let entry = &bank[index];
+                if entry.imr_index != measurement.imr_index
+                    || entry.template_name != measurement.template_name
+                    || entry.template_data != measurement.template_data
+                {
+                    return Err(anyhow!(
+                        "[parse_ima_binary_event_logs] entry {} for algorithm {} does not match other banks",
+                        index,
+                        algo_id
+                    ));
+                }
+                digests.push(TcgDigest {
+                    algo_id: *algo_id,
+                    hash: entry.template_hash.clone(),
+                });
+            }
 
-        let event_size = get_u32(data[index..index + 4].to_vec());
-        index += 4;
-        let event = data[index..index + event_size as usize].try_into().unwrap();
-        index += event_size as usize;
+            // templates unknown to the parser are kept undecoded
+            let ima_template = match get_template_fields(&measurement.template_name) {
+                Ok(_) => Some(Box::new(ImaTemplate::parse_binary(
+                    &measurement.template_name,
+                    &measurement.template_data,
+                )?)),
+                Err(_) => None,
+            };
+            let mut extra_info = HashMap::new();
+            extra_info.insert(
+                "template_name".to_string(),
+                measurement.template_name.clone(),
+            );
+            extra_info.insert("event_format".to_string(), "binary".to_string());
 
-        Ok((
-            TcgEventLog {
-                rec_num,
-                imr_index,
-                event_type,
+            event_logs.push(TcgEventLog {
+                rec_num: self.get_record_number(measurement.imr_index),
+                imr_index: measurement.imr_index,
+                event_type: IMA_MEASUREMENT_EVENT,
                 digests,
-                event_size,
-                event,
-                extra_info: HashMap::new(),
-            },
-            index.try_into().unwrap(),
-        ))
+                event_size: measurement.template_data.len() as u32,
+                event: measurement.template_data.clone(),
+                extra_info,
+                ima_template,
+            });
+        }
+        Ok(event_logs)
     }
 
     /***
-       Parse ascii IMA events gathered during runtime.
-
-       Sample event and format:
-       IMR index | Template hash | Template name | Event data according to template
-       10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha384:74ccc46104f42db070375e6876a23aeaa3c2ae458888475baaa171c3fb7001b0fc385ed08420d5f60620924fc64d0b80 /etc/lsb-release
+        Serialize event logs into the binary TCG PC Client event log format, i.e. the
+        specification ID event as TCG_PCClientPCREvent followed by TCG_PCR_EVENT2 events.
+        Definition reference:
+        https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf
+        The specification ID event is taken from the event logs if present, otherwise it is
+        built from spec_id_header_event, so the event logs must have been parsed by select().
+        The pcrIndex is written as imr_index + imr_index_offset, the inverse of parsing.
+        Runtime IMA events and CEL management events are not part of the boot time event log
+        and are skipped.
 
-       Args:
-           event: IMA ascii raw event
+        Args:
+            eventlogs: event logs to serialize, e.g. a filtered result of select()
 
-       Returns:
-           A TcgEventLog object containing the ima event log
+        Returns:
+            The binary event log
     */
-    fn parse_ima_event_log(&mut self, data: &str) -> Result<TcgEventLog, anyhow::Error> {
-        /*  after the split, the elements vec has following mapping:
-               elements[0] => IMR index
-               elements[1] => Template hash
-               elements[2] => Template name
-               elements[3] to end of vec => Event data according to template
-        */
-        let elements: Vec<&str> = data.trim_matches(' ').split(' ').collect();
-
-        let imr_index: u32 = elements[0].parse().unwrap();
-        let rec_num = self.get_record_number(imr_index);
-
-        let event = elements[3..].join(" ").as_bytes().to_vec();
-        let event_size = event.len() as u32;
+    pub fn to_pcclient_binary(
+        &self,
+        eventlogs: &[EventLogEntry],
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        if self.spec_id_header_event.digest_sizes.is_empty() {
+            return Err(anyhow!(
+                "[to_pcclient_binary] no specification ID event parsed"
+            ));
+        }
 
-        let mut digests: Vec<TcgDigest> = Vec::new();
-        let digest_size = elements[1].len() / 2;
-        let algo_id = TcgDigest::get_algorithm_id_from_digest_size(digest_size.try_into().unwrap());
-        let digest = TcgDigest {
-            algo_id,
-            hash: hex::decode(elements[1]).expect("Decoding hash string from IMA record failed"),
+        let mut data = Vec::new();
+        let (header, events) = match eventlogs.first() {
+            Some(EventLogEntry::TcgPcClientImrEvent(header)) => (header.clone(), &eventlogs[1..]),
+            Some(EventLogEntry::TcgCanonicalEvent(header))
+                if is_spec_id_canonical_event(header) =>
+            {
+                let header = match header.to_tcg_imr_event() {
+                    Some(header) => header,
+                    None => {
+                        return Err(anyhow!(
+                            "[to_pcclient_binary] specification ID event is not extended to a PCR"
+                        ))
+                    }
+                };
+                (
+                    TcgPcClientImrEvent {
+                        imr_index: header.imr_index,
+                        event_type: header.event_type,
+                        digest: [0; 20],
+                        event_size: header.event_size,
+                        event: header.event,
+                    },
+                    &eventlogs[1..],
+                )
+            }
+            _ => {
+                let event = self.spec_id_header_event.to_bytes();
+                (
+                    TcgPcClientImrEvent {
+                        imr_index: 0,
+                        event_type: EV_NO_ACTION,
+                        digest: [0; 20],
+                        event_size: event.len() as u32,
+                        event,
+                    },
+                    eventlogs,
+                )
+            }
         };
-        digests.push(digest);
+        data.extend_from_slice(&(header.imr_index + self.imr_index_offset).to_le_bytes());
+        data.extend_from_slice(&header.event_type.to_le_bytes());
+        data.extend_from_slice(&header.digest);
+        data.extend_from_slice(&(header.event.len() as u32).to_le_bytes());
+        data.extend_from_slice(&header.event);
 
-        let mut extra_info = HashMap::new();
-        extra_info.insert("template_name".to_string(), elements[2].to_string());
+        for event_log in events {
+            let tcg_imr_event = match event_log {
+                EventLogEntry::TcgImrEvent(tcg_imr_event) => tcg_imr_event.clone(),
+                EventLogEntry::TcgPcClientImrEvent(_) => continue,
+                EventLogEntry::TcgCanonicalEvent(tcg_canonical_event) => {
+                    match tcg_canonical_event.to_tcg_imr_event() {
+                        Some(tcg_imr_event) => tcg_imr_event,
+                        None => continue,
+                    }
+                }
+            };
+            if tcg_imr_event.event_type == IMA_MEASUREMENT_EVENT {
+                continue;
+            }
 
-        Ok(TcgEventLog {
-            rec_num,
-            imr_index,
-            event_type: IMA_MEASUREMENT_EVENT,
-            digests,
-            event_size,
-            event,
-            extra_info,
-        })
+            data.extend_from_slice(
+                &(tcg_imr_event.imr_index + self.imr_index_offset).to_le_bytes(),
+            );
+            data.extend_from_slice(&tcg_imr_event.event_type.to_le_bytes());
+            data.extend_from_slice(&(tcg_imr_event.digests.len() as u32).to_le_bytes());
+            for digest in &tcg_imr_event.digests {
+                match self.spec_id_header_event.get_digest_size(digest.algo_id) {
+                    Some(digest_size) if digest_size as usize == digest.hash.len() => (),
+                    _ => {
+                        return Err(anyhow!(
+                            "[to_pcclient_binary] digest of algorithm {} with size {} is not declared in specification ID event",
+                            digest.algo_id,
+                            digest.hash.len()
+                        ))
+                    }
+                }
+                data.extend_from_slice(&digest.algo_id.to_le_bytes());
+                data.extend_from_slice(&digest.hash);
+            }
+            data.extend_from_slice(&(tcg_imr_event.event.len() as u32).to_le_bytes());
+            data.extend_from_slice(&tcg_imr_event.event);
+        }
+
+        Ok(data)
     }
+
     /***
        Replay event logs by IMR index.
+       Each IMR keeps a register per hash algorithm starting from zero. Events must carry
+       the digests of the algorithms declared in the specification ID event, if present
+       in the event logs, except for IMA events carrying the template hashes logged by
+       the kernel. A PCR with a StartupLocality event, or measured by an H-CRTM sequence
+       with EV_EFI_HCRTM_EVENT events, starts from the startup locality in its last byte.
        Returns:
            A struct containing the replay result arranged by IMR index and hash algorithm.
            Layer 1 key of the struct is the IMR index, the value is another dict which using the
@@ -510,87 +1063,666 @@ impl EventLogs {
                ]
     */
     pub fn replay(eventlogs: Vec<EventLogEntry>) -> Result<Vec<ReplayResult>, anyhow::Error> {
-        let mut replay_results: Vec<ReplayResult> = Vec::new();
+        Ok(Self::replay_with_options(eventlogs, &ReplayOptions::default())?.replay_results)
+    }
 
-        for event_log in eventlogs {
-            match event_log {
-                EventLogEntry::TcgImrEvent(tcg_imr_event) => {
-                    if tcg_imr_event.event_type == EV_NO_ACTION {
-                        continue;
-                    }
-                    let imr_index = tcg_imr_event.imr_index;
-                    for digest in tcg_imr_event.digests {
-                        let algo_id = digest.algo_id;
-                        let hash = digest.hash;
-                        let digest_size = TcgDigest::get_digest_size_from_algorithm_id(algo_id);
-
-                        let mut imr_pos = usize::MAX;
-                        let mut algo_pos = usize::MAX;
-                        for index1 in 0..replay_results.len() {
-                            if replay_results[index1].imr_index == imr_index {
-                                imr_pos = index1;
-                            }
-                        }
+    /***
+       Replay event logs by IMR index with options on IMA events.
+       With verify_ima_template, the template hash of each IMA event is recomputed from its
+       template data and compared with the logged one, mismatches are reported but the logged
+       template hash is still extended.
+       With any IMA option, IMA events are extended the way the kernel does:
+       violations, logged as a zero template hash, are extended as 0xFF bytes, and with
+       ima_extend_algo_id, only a template hash of that algorithm is extended, which is the
+       logged one, the one recomputed from the template data, or the SHA1 one padded with
+       zeros if ima_extend_padded_sha1 is set or the template data is not available.
+       With trace, the value of the IMR after each extend is recorded, to be checked
+       against the expected values with ReplayReport::diagnose().
 
-                        if imr_pos == usize::MAX {
-                            replay_results.push(ReplayResult {
-                                imr_index,
-                                digests: Vec::new(),
-                            });
-                            imr_pos = replay_results.len() - 1;
-                        } else {
-                            for index2 in 0..replay_results[imr_pos].digests.len() {
-                                if digest.algo_id == algo_id {
-                                    algo_pos = index2;
-                                }
-                            }
-                        }
+       Args:
+           eventlogs: event logs to replay
+           options: replay options
 
-                        if algo_pos == usize::MAX {
-                            replay_results[imr_pos].digests.push(TcgDigest {
-                                algo_id,
-                                hash: vec![0; digest_size.into()],
-                            });
-                            algo_pos = replay_results[imr_pos].digests.len() - 1;
-                        }
+       Returns:
+           A struct containing the replay results as returned by replay(), the IMA events
+           whose template hash mismatches and the replay trace
+    */
+    pub fn replay_with_options(
+        eventlogs: Vec<EventLogEntry>,
+        options: &ReplayOptions,
+    ) -> Result<ReplayReport, anyhow::Error> {
+        let mut replayer = Replayer::new(options);
+        for (event_index, event_log) in eventlogs.into_iter().enumerate() {
+            replayer.replay_event(event_index, event_log)?;
+        }
+        Ok(replayer.into_report())
+    }
+}
+
+/***
+    Incremental replay of event logs, one event at a time, as done by
+    EventLogs::replay_with_options().
+
+    Attributes:
+        options: replay options
+        replay_results: replay results of the events replayed so far
+        ima_mismatches: IMA events whose template hash mismatches
+        trace: replay trace if enabled by the options
+        digest_sizes: algorithms declared in the specification ID event
+        startup_localities: startup locality of the IMRs starting from non-zero values
+*/
+#[derive(Clone)]
+pub(crate) struct Replayer {
+    options: ReplayOptions,
+    replay_results: Vec<ReplayResult>,
+    ima_mismatches: Vec<ImaTemplateMismatch>,
+    trace: Vec<ReplayTraceEntry>,
+    digest_sizes: Vec<TcgEfiSpecIdEventAlgorithmSize>,
+    startup_localities: HashMap<u32, u8>,
+}
+
+impl Replayer {
+    pub(crate) fn new(options: &ReplayOptions) -> Replayer {
+        Replayer {
+            options: options.clone(),
+            replay_results: Vec::new(),
+            ima_mismatches: Vec::new(),
+            trace: Vec::new(),
+            digest_sizes: Vec::new(),
+            startup_localities: HashMap::new(),
+        }
+    }
+
+    /***
+        Replay the event following the ones replayed so far.
 
-                        let hash_input_data =
-                            [replay_results[imr_pos].digests[algo_pos].hash.clone(), hash].concat();
-
-                        match algo_id {
-                            TPM_ALG_SHA1 => {
-                                let mut algo_hasher = Sha1::new();
-                                algo_hasher.update(hash_input_data);
-                                replay_results[imr_pos].digests[algo_pos].hash =
-                                    algo_hasher.finalize().to_vec();
-                            }
-                            TPM_ALG_SHA256 => {
-                                let mut algo_hasher = Sha256::new();
-                                algo_hasher.update(hash_input_data);
-                                replay_results[imr_pos].digests[algo_pos].hash =
-                                    algo_hasher.finalize().to_vec();
-                            }
-                            TPM_ALG_SHA384 => {
-                                let mut algo_hasher = Sha384::new();
-                                algo_hasher.update(hash_input_data);
-                                replay_results[imr_pos].digests[algo_pos].hash =
-                                    algo_hasher.finalize().to_vec();
-                            }
-                            TPM_ALG_SHA512 => {
-                                let mut algo_hasher = Sha512::new();
-                                algo_hasher.update(hash_input_data);
-                                replay_results[imr_pos].digests[algo_pos].hash =
-                                    algo_hasher.finalize().to_vec();
-                            }
-                            0_u16..=3_u16 | 5_u16..=10_u16 | 14_u16..=u16::MAX => (),
+        Args:
+            event_index: index of the event in the event logs
+            event_log: the event to replay
+
+        Returns:
+            The index of the IMR extended by the event, None if the event is not extended
+    */
+    pub(crate) fn replay_event(
+        &mut self,
+        event_index: usize,
+        event_log: EventLogEntry,
+    ) -> Result<Option<u32>, anyhow::Error> {
+        let options = &self.options;
+        let ima_options = options.verify_ima_template || options.ima_extend_algo_id.is_some();
+
+        let tcg_imr_event = match event_log {
+            // Skip TcgPcClientImrEvent during replay, but take the algorithms it declares
+            EventLogEntry::TcgPcClientImrEvent(tcg_pc_client_imr_event) => {
+                if let Some(spec_id_event) = parse_spec_id_event(&tcg_pc_client_imr_event.event) {
+                    self.digest_sizes = spec_id_event.digest_sizes;
+                }
+                return Ok(None);
+            }
+            EventLogEntry::TcgImrEvent(tcg_imr_event) => tcg_imr_event,
+            // Replay canonical event records extended to IMR as TcgImrEvent
+            EventLogEntry::TcgCanonicalEvent(tcg_canonical_event) => {
+                if is_spec_id_canonical_event(&tcg_canonical_event) {
+                    if let TcgCelContent::PcClientStd { event_data, .. } =
+                        &tcg_canonical_event.content
+                    {
+                        if let Some(spec_id_event) = parse_spec_id_event(event_data) {
+                            self.digest_sizes = spec_id_event.digest_sizes;
                         }
                     }
+                    return Ok(None);
                 }
-                EventLogEntry::TcgPcClientImrEvent(_) => (), // Skip TcgPcClientImrEvent during replay
-                EventLogEntry::TcgCanonicalEvent(_) => todo!(),
+                match tcg_canonical_event.to_tcg_imr_event() {
+                    Some(tcg_imr_event) => tcg_imr_event,
+                    None => return Ok(None),
+                }
+            }
+        };
+        if tcg_imr_event.event_type == EV_NO_ACTION {
+            if tcg_imr_event
+                .event
+                .starts_with(TCG_STARTUP_LOCALITY_SIGNATURE)
+            {
+                let startup_locality_event =
+                    TcgEfiStartupLocalityEvent::parse(&tcg_imr_event.event)?;
+                set_startup_locality(
+                    &mut self.startup_localities,
+                    &self.replay_results,
+                    event_index,
+                    tcg_imr_event.imr_index,
+                    startup_locality_event.startup_locality,
+                )?;
+            }
+            return Ok(None);
+        }
+        // an H-CRTM sequence resets the PCR to locality 4 before extending its measurement,
+        // the StartupLocality event may be missing
+        if tcg_imr_event.event_type == EV_EFI_HCRTM_EVENT
+            && self.startup_localities.get(&tcg_imr_event.imr_index) != Some(&TCG_HCRTM_LOCALITY)
+        {
+            set_startup_locality(
+                &mut self.startup_localities,
+                &self.replay_results,
+                event_index,
+                tcg_imr_event.imr_index,
+                TCG_HCRTM_LOCALITY,
+            )?;
+        }
+        for digest in &tcg_imr_event.digests {
+            let digest_size = TcgDigest::get_digest_size_from_algorithm_id(digest.algo_id);
+            if digest_size as usize != digest.hash.len() {
+                return Err(anyhow!(
+                    "[replay] digest of algorithm {} with size {} in event {}",
+                    digest.algo_id,
+                    digest.hash.len(),
+                    event_index
+                ));
+            }
+        }
+        // IMA events only carry the template hash of the banks the kernel logs
+        if tcg_imr_event.event_type != IMA_MEASUREMENT_EVENT && !self.digest_sizes.is_empty() {
+            check_declared_digests(event_index, &tcg_imr_event, &self.digest_sizes)?;
+        }
+
+        let digests = if ima_options && tcg_imr_event.event_type == IMA_MEASUREMENT_EVENT {
+            if options.verify_ima_template {
+                self.ima_mismatches
+                    .extend(verify_ima_template_hash(event_index, &tcg_imr_event));
+            }
+            get_ima_extend_digests(&tcg_imr_event, options)?
+        } else {
+            tcg_imr_event.digests
+        };
+
+        let startup_locality = self
+            .startup_localities
+            .get(&tcg_imr_event.imr_index)
+            .copied()
+            .unwrap_or(0);
+        for digest in digests {
+            let algo_id = digest.algo_id;
+            let hash = digest.hash.clone();
+            let value = extend_replay_result(
+                &mut self.replay_results,
+                tcg_imr_event.imr_index,
+                startup_locality,
+                digest,
+            )?;
+            if self.options.trace {
+                self.trace.push(ReplayTraceEntry {
+                    event_index,
+                    imr_index: tcg_imr_event.imr_index,
+                    algo_id,
+                    digest: hash,
+                    value,
+                });
+            }
+        }
+        Ok(Some(tcg_imr_event.imr_index))
+    }
+
+    pub(crate) fn replay_results(&self) -> &[ReplayResult] {
+        &self.replay_results
+    }
+
+    pub(crate) fn into_report(self) -> ReplayReport {
+        ReplayReport {
+            replay_results: self.replay_results,
+            ima_mismatches: self.ima_mismatches,
+            trace: self.trace,
+            startup_localities: self.startup_localities,
+        }
+    }
+}
+
+// Initial value of a register, zero with the startup locality in the last byte
+fn get_initial_value(digest_size: usize, startup_locality: u8) -> Vec<u8> {
+    let mut value = vec![0; digest_size];
+    if let Some(last) = value.last_mut() {
+        *last = startup_locality;
+    }
+    value
+}
+
+// Extend the digest to the register of its algorithm in the replay result of the IMR,
+// registers start from zero with the startup locality in the last byte, and return the
+// extended value
+fn extend_replay_result(
+    replay_results: &mut Vec<ReplayResult>,
+    imr_index: u32,
+    startup_locality: u8,
+    digest: TcgDigest,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let imr_pos = match replay_results
+        .iter()
+        .position(|replay_result| replay_result.imr_index == imr_index)
+    {
+        Some(imr_pos) => imr_pos,
+        None => {
+            replay_results.push(ReplayResult {
+                imr_index,
+                digests: Vec::new(),
+            });
+            replay_results.len() - 1
+        }
+    };
+
+    let registers = &mut replay_results[imr_pos].digests;
+    let algo_pos = match registers
+        .iter()
+        .position(|register| register.algo_id == digest.algo_id)
+    {
+        Some(algo_pos) => algo_pos,
+        None => {
+            registers.push(TcgDigest {
+                algo_id: digest.algo_id,
+                hash: get_initial_value(digest.hash.len(), startup_locality),
+            });
+            registers.len() - 1
+        }
+    };
+
+    let register = &mut registers[algo_pos];
+    register.hash = compute_digest(digest.algo_id, &[&register.hash[..], &digest.hash].concat())?;
+    Ok(register.hash.clone())
+}
+
+// Take the startup locality of the IMR from a StartupLocality or H-CRTM event, which must
+// be set once before the IMR is extended
+fn set_startup_locality(
+    startup_localities: &mut HashMap<u32, u8>,
+    replay_results: &[ReplayResult],
+    event_index: usize,
+    imr_index: u32,
+    startup_locality: u8,
+) -> Result<(), anyhow::Error> {
+    if startup_localities.contains_key(&imr_index)
+        || replay_results
+            .iter()
+            .any(|replay_result| replay_result.imr_index == imr_index)
+    {
+        return Err(anyhow!(
+            "[replay] event {} sets the startup locality of IMR {} already initialized",
+            event_index,
+            imr_index
+        ));
+    }
+    startup_localities.insert(imr_index, startup_locality);
+    Ok(())
+}
+
+// Get the specification ID event of crypto agile event logs from the event data
+fn parse_spec_id_event(event: &[u8]) -> Option<TcgEfiSpecIdEvent> {
+    if !event.starts_with(TCG_SPEC_ID_EVENT_SIGNATURE) {
+        return None;
+    }
+    TcgEfiSpecIdEvent::parse(event).ok()
+}
+
+// Check the event carries a digest of each algorithm declared in the specification ID event
+fn check_declared_digests(
+    event_index: usize,
+    tcg_imr_event: &TcgImrEvent,
+    digest_sizes: &[TcgEfiSpecIdEventAlgorithmSize],
+) -> Result<(), anyhow::Error> {
+    for digest_size in digest_sizes {
+        if !tcg_imr_event
+            .digests
+            .iter()
+            .any(|digest| digest.algo_id == digest_size.algo_id)
+        {
+            return Err(anyhow!(
+                "[replay] event {} misses the digest of algorithm {} declared in specification ID event",
+                event_index,
+                digest_size.algo_id
+            ));
+        }
+    }
+    for digest in &tcg_imr_event.digests {
+        if !digest_sizes
+            .iter()
+            .any(|digest_size| digest_size.algo_id == digest.algo_id)
+        {
+            return Err(anyhow!(
+                "[replay] event {} has a digest of algorithm {} not declared in specification ID event",
+                event_index,
+                digest.algo_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Hash the data with the TPM hash algorithm
+pub(crate) fn compute_digest(algo_id: u16, data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    match algo_id {
+        TPM_ALG_SHA1 => Ok(Sha1::digest(data).to_vec()),
+        TPM_ALG_SHA256 => Ok(Sha256::digest(data).to_vec()),
+        TPM_ALG_SHA384 => Ok(Sha384::digest(data).to_vec()),
+        TPM_ALG_SHA512 => Ok(Sha512::digest(data).to_vec()),
+        _ => Err(anyhow!(
+            "[compute_digest] unsupported hash algorithm: {:#x}",
+            algo_id
+        )),
+    }
+}
+
+// IMA violations are logged with a zero template hash
+fn is_ima_violation(tcg_imr_event: &TcgImrEvent) -> bool {
+    tcg_imr_event
+        .digests
+        .iter()
+        .all(|digest| digest.hash.iter().all(|byte| *byte == 0))
+}
+
+// Recompute the template hashes of the IMA event and collect the mismatching ones
+fn verify_ima_template_hash(
+    event_index: usize,
+    tcg_imr_event: &TcgImrEvent,
+) -> Vec<ImaTemplateMismatch> {
+    if is_ima_violation(tcg_imr_event) {
+        return Vec::new();
+    }
+
+    let hash_data = tcg_imr_event
+        .ima_template
+        .as_ref()
+        .and_then(|template| template.get_template_hash_data().ok());
+    let (template_name, file_name) = match &tcg_imr_event.ima_template {
+        Some(template) => (template.template_name.clone(), template.file_name.clone()),
+        None => (String::new(), String::new()),
+    };
+
+    let mut mismatches = Vec::new();
+    for digest in &tcg_imr_event.digests {
+        let computed_hash = hash_data
+            .as_ref()
+            .and_then(|hash_data| compute_digest(digest.algo_id, hash_data).ok());
+        if computed_hash.as_ref() != Some(&digest.hash) {
+            mismatches.push(ImaTemplateMismatch {
+                event_index,
+                imr_index: tcg_imr_event.imr_index,
+                template_name: template_name.clone(),
+                file_name: file_name.clone(),
+                algo_id: digest.algo_id,
+                logged_hash: digest.hash.clone(),
+                computed_hash,
+            });
+        }
+    }
+    mismatches
+}
+
+// Get the digests the kernel extends for the IMA event
+fn get_ima_extend_digests(
+    tcg_imr_event: &TcgImrEvent,
+    options: &ReplayOptions,
+) -> Result<Vec<TcgDigest>, anyhow::Error> {
+    let violation = is_ima_violation(tcg_imr_event);
+    let algo_id = match options.ima_extend_algo_id {
+        Some(algo_id) => algo_id,
+        None => {
+            return Ok(tcg_imr_event
+                .digests
+                .iter()
+                .map(|digest| TcgDigest {
+                    algo_id: digest.algo_id,
+                    hash: match violation {
+                        true => vec![0xff; digest.hash.len()],
+                        false => digest.hash.clone(),
+                    },
+                })
+                .collect())
+        }
+    };
+
+    let digest_size = TcgDigest::get_digest_size_from_algorithm_id(algo_id) as usize;
+    if digest_size == 0 {
+        return Err(anyhow!(
+            "[get_ima_extend_digests] unknown algorithm {} to extend IMA events",
+            algo_id
+        ));
+    }
+    let logged = tcg_imr_event
+        .digests
+        .iter()
+        .find(|digest| digest.algo_id == algo_id);
+    let sha1 = tcg_imr_event
+        .digests
+        .iter()
+        .find(|digest| digest.algo_id == TPM_ALG_SHA1);
+    let template = match options.ima_extend_padded_sha1 {
+        true => None,
+        false => tcg_imr_event.ima_template.as_ref(),
+    };
+
+    let hash = if violation {
+        vec![0xff; digest_size]
+    } else if let Some(digest) = logged {
+        digest.hash.clone()
+    } else if let Some(template) = template {
+        compute_digest(algo_id, &template.get_template_hash_data()?)?
+    } else if let Some(digest) = sha1 {
+        let mut hash = digest.hash.clone();
+        hash.resize(digest_size, 0);
+        hash
+    } else {
+        return Err(anyhow!(
+            "[get_ima_extend_digests] no template hash of algorithm {} for IMA event",
+            algo_id
+        ));
+    };
+    Ok(vec![TcgDigest { algo_id, hash }])
+}
+
+// whether the canonical event carries the specification ID event converted by to_tcg_canonical_format
+fn is_spec_id_canonical_event(event: &TcgCanonicalEvent) -> bool {
+    match &event.content {
+        TcgCelContent::PcClientStd {
+            event_type,
+            event_data,
+        } => {
+            *event_type == EV_NO_ACTION
+                && event_data.starts_with(TCG_SPEC_ID_EVENT_SIGNATURE)
+                && event.digests.is_empty()
+                && matches!(event.index, TcgCelIndex::Pcr(_))
+        }
+        _ => false,
+    }
+}
+
+impl ReplayReport {
+    /***
+        Diagnose why replayed IMRs diverge from the expected values, e.g. the quoted ones,
+        using the replay trace, so the event logs must be replayed with the trace option.
+        For each IMR and algorithm bank of the expected values:
+        Matched: the replayed value equals the expected one.
+        ExtraEvents: the expected value is the value of the IMR after an earlier event, so
+                     the events after it are not measured in the IMR.
+        Diverged: no value of the bank matches while another bank of the IMR does, so the
+                  digests of the bank diverge from what was measured.
+        AlteredOrMissingEvents: no value of any bank of the IMR matches, so events measured
+                                in the IMR are missing from the event logs, or events have
+                                been altered.
+        Diverged and AlteredOrMissingEvents carry the first event the bank stops agreeing
+        at, when told by the replay: the first event extended to a matched bank of the IMR
+        but not to the bank or the other way round, or the first event of the bank whose
+        IMA template hash mismatches. None if the events do not tell it.
+
+        Args:
+            expected: the expected values arranged as replay results
+
+        Returns:
+            The diagnosis of each IMR and algorithm bank of the expected values
+    */
+    pub fn diagnose(
+        &self,
+        expected: &[ReplayResult],
+    ) -> Result<Vec<ReplayDiagnosis>, anyhow::Error> {
+        if self.trace.is_empty() && !self.replay_results.is_empty() {
+            return Err(anyhow!("[diagnose] replay trace is not recorded"));
+        }
+
+        let mut diagnoses = Vec::new();
+        for expected_result in expected {
+            for expected_digest in &expected_result.digests {
+                diagnoses.push(self.diagnose_bank(expected_result.imr_index, expected_digest));
+            }
+        }
+
+        // tell the diverged banks from the IMRs with altered or missing events
+        let matched: Vec<(u32, u16)> = diagnoses
+            .iter()
+            .filter(|diagnosis| diagnosis.divergence == ReplayDivergence::Matched)
+            .map(|diagnosis| (diagnosis.imr_index, diagnosis.algo_id))
+            .collect();
+        for diagnosis in diagnoses.iter_mut() {
+            if !matches!(
+                diagnosis.divergence,
+                ReplayDivergence::AlteredOrMissingEvents { .. }
+            ) {
+                continue;
+            }
+            let matched_algo_id = matched
+                .iter()
+                .find(|(imr_index, _)| *imr_index == diagnosis.imr_index)
+                .map(|(_, algo_id)| *algo_id);
+            let first_mismatch_event = self.get_first_mismatch_event(
+                diagnosis.imr_index,
+                diagnosis.algo_id,
+                matched_algo_id,
+            );
+            diagnosis.divergence = match matched_algo_id {
+                Some(_) => ReplayDivergence::Diverged {
+                    first_mismatch_event,
+                },
+                None => ReplayDivergence::AlteredOrMissingEvents {
+                    first_mismatch_event,
+                },
+            };
+        }
+        Ok(diagnoses)
+    }
+
+    // first event of the bank disagreeing with the matched bank or with its IMA template
+    fn get_first_mismatch_event(
+        &self,
+        imr_index: u32,
+        algo_id: u16,
+        matched_algo_id: Option<u16>,
+    ) -> Option<usize> {
+        let mut events: Vec<usize> = self
+            .ima_mismatches
+            .iter()
+            .filter(|mismatch| mismatch.imr_index == imr_index && mismatch.algo_id == algo_id)
+            .map(|mismatch| mismatch.event_index)
+            .collect();
+
+        if let Some(matched_algo_id) = matched_algo_id {
+            let get_events = |algo_id: u16| -> Vec<usize> {
+                self.trace
+                    .iter()
+                    .filter(|entry| entry.imr_index == imr_index && entry.algo_id == algo_id)
+                    .map(|entry| entry.event_index)
+                    .collect()
+            };
+            let bank = get_events(algo_id);
+            let matched = get_events(matched_algo_id);
+            match bank.iter().zip(&matched).position(|(a, b)| a != b) {
+                Some(pos) => events.push(std::cmp::min(bank[pos], matched[pos])),
+                None if bank.len() > matched.len() => events.push(bank[matched.len()]),
+                None if bank.len() < matched.len() => events.push(matched[bank.len()]),
+                None => (),
+            }
+        }
+        events.into_iter().min()
+    }
+
+    fn diagnose_bank(&self, imr_index: u32, expected: &TcgDigest) -> ReplayDiagnosis {
+        let entries: Vec<&ReplayTraceEntry> = self
+            .trace
+            .iter()
+            .filter(|entry| entry.imr_index == imr_index && entry.algo_id == expected.algo_id)
+            .collect();
+        let startup_locality = self
+            .startup_localities
+            .get(&imr_index)
+            .copied()
+            .unwrap_or(0);
+        let initial = get_initial_value(expected.hash.len(), startup_locality);
+        let replayed = match entries.last() {
+            Some(entry) => entry.value.clone(),
+            None => initial.clone(),
+        };
+
+        let divergence = if replayed == expected.hash {
+            ReplayDivergence::Matched
+        } else if let Some(pos) = entries
+            .iter()
+            .rposition(|entry| entry.value == expected.hash)
+        {
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: Some(entries[pos].event_index),
+                extra_events: entries[pos + 1..]
+                    .iter()
+                    .map(|entry| entry.event_index)
+                    .collect(),
+            }
+        } else if initial == expected.hash {
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: None,
+                extra_events: entries.iter().map(|entry| entry.event_index).collect(),
+            }
+        } else {
+            ReplayDivergence::AlteredOrMissingEvents {
+                first_mismatch_event: None,
             }
+        };
+
+        ReplayDiagnosis {
+            imr_index,
+            algo_id: expected.algo_id,
+            expected: expected.hash.clone(),
+            replayed,
+            divergence,
+        }
+    }
+
+    pub fn show(&self) {
+        for replay_result in &self.replay_results {
+            replay_result.show();
+        }
+        for mismatch in &self.ima_mismatches {
+            info!(
+                "IMA template hash mismatch: event[{}] IMR[{}] {} {} algorithm {}",
+                mismatch.event_index,
+                mismatch.imr_index,
+                mismatch.template_name,
+                mismatch.file_name,
+                mismatch.algo_id
+            );
+        }
+        for entry in &self.trace {
+            info!(
+                "event[{}] IMR[{}] algorithm {}: {} -> {}",
+                entry.event_index,
+                entry.imr_index,
+                entry.algo_id,
+                hex::encode(&entry.digest),
+                hex::encode(&entry.value)
+            );
         }
-        Ok(replay_results)
+    }
+}
+
+impl ReplayDiagnosis {
+    pub fn show(&self) {
+        info!(
+            "IMR[{}] algorithm {}: {:?}",
+            self.imr_index, self.algo_id, self.divergence
+        );
+        info!("    expected = {}", hex::encode(&self.expected));
+        info!("    replayed = {}", hex::encode(&self.replayed));
     }
 }
 
@@ -606,3 +1738,1146 @@ impl ReplayResult {
         }
     }
 }
+
+#[cfg(test)]
+mod test_eventlogs {
+    use super::*;
+    use crate::uefi::test_uefi_event_data::ucs2;
+
+    const BANKS: [u16; 2] = [TPM_ALG_SHA256, TPM_ALG_SHA384];
+    const TPM_BANKS: [u16; 3] = [TPM_ALG_SHA1, TPM_ALG_SHA256, TPM_ALG_SHA384];
+
+    // TCG_PCClientPCREvent carrying TCG_EfiSpecIDEventStruct declaring the algorithms of banks
+    fn get_spec_id_event(pcr_index: u32, banks: &[u16]) -> Vec<u8> {
+        let mut event = b"Spec ID Event03\0".to_vec();
+        event.extend_from_slice(&0u32.to_le_bytes());
+        event.extend_from_slice(&[0, 2, 0, 2]);
+        event.extend_from_slice(&(banks.len() as u32).to_le_bytes());
+        for algo_id in banks {
+            event.extend_from_slice(&algo_id.to_le_bytes());
+            let digest_size = TcgDigest::get_digest_size_from_algorithm_id(*algo_id) as u16;
+            event.extend_from_slice(&digest_size.to_le_bytes());
+        }
+        event.push(2);
+        event.extend_from_slice(&[0xaa, 0xbb]);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&pcr_index.to_le_bytes());
+        data.extend_from_slice(&EV_NO_ACTION.to_le_bytes());
+        data.extend_from_slice(&[0; 20]);
+        data.extend_from_slice(&(event.len() as u32).to_le_bytes());
+        data.extend_from_slice(&event);
+        data
+    }
+
+    // TCG_PCR_EVENT2 with a digest of each bank filled with seed, or measuring the event
+    // data without seed
+    fn get_event(
+        pcr_index: u32,
+        event_type: u32,
+        banks: &[u16],
+        seed: Option<u8>,
+        event: &[u8],
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&pcr_index.to_le_bytes());
+        data.extend_from_slice(&event_type.to_le_bytes());
+        data.extend_from_slice(&(banks.len() as u32).to_le_bytes());
+        for algo_id in banks {
+            data.extend_from_slice(&algo_id.to_le_bytes());
+            match seed {
+                Some(seed) => {
+                    let digest_size = TcgDigest::get_digest_size_from_algorithm_id(*algo_id);
+                    data.extend(vec![seed; digest_size as usize]);
+                }
+                None => data.extend(compute_digest(*algo_id, event).unwrap()),
+            }
+        }
+        data.extend_from_slice(&(event.len() as u32).to_le_bytes());
+        data.extend_from_slice(event);
+        data
+    }
+
+    fn get_boot_time_data() -> Vec<u8> {
+        [
+            get_spec_id_event(1, &BANKS),
+            get_event(1, EV_POST_CODE, &BANKS, Some(0x11), b"POST CODE"),
+            get_event(1, EV_SEPARATOR, &BANKS, Some(0x12), &[0; 4]),
+            get_event(
+                2,
+                EV_EFI_ACTION,
+                &BANKS,
+                Some(0x13),
+                b"Calling EFI Application",
+            ),
+            get_event(3, EV_IPL, &BANKS, Some(0x14), b""),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_to_pcclient_binary() {
+        let boot_time_data = get_boot_time_data();
+        let mut padded = boot_time_data.clone();
+        padded.extend_from_slice(&[0xff; 16]);
+
+        let ima_event =
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha1:0102 /etc/lsb-release";
+        let mut eventlogs =
+            EventLogs::new(padded, vec![ima_event.to_string()], TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+        assert_eq!(entries.len(), 6);
+        assert_eq!(eventlogs.spec_id_header_event.vendor_info, vec![0xaa, 0xbb]);
+
+        // runtime IMA event is skipped and the trailing padding is not written
+        assert_eq!(
+            eventlogs.to_pcclient_binary(&entries).unwrap(),
+            boot_time_data
+        );
+    }
+
+    #[test]
+    fn test_to_pcclient_binary_filtered() {
+        let mut eventlogs = EventLogs::new(get_boot_time_data(), Vec::new(), TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(Some(2), Some(2)).unwrap();
+        // specification ID event is rebuilt from spec_id_header_event
+        let expected = [
+            get_spec_id_event(1, &BANKS),
+            get_event(1, EV_SEPARATOR, &BANKS, Some(0x12), &[0; 4]),
+            get_event(
+                2,
+                EV_EFI_ACTION,
+                &BANKS,
+                Some(0x13),
+                b"Calling EFI Application",
+            ),
+        ]
+        .concat();
+        let data = eventlogs.to_pcclient_binary(&entries).unwrap();
+        assert_eq!(data, expected);
+
+        let mut reparsed = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        assert_eq!(reparsed.select(None, None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_to_pcclient_binary_canonical() {
+        let boot_time_data = get_boot_time_data();
+        let mut eventlogs =
+            EventLogs::new(boot_time_data.clone(), Vec::new(), TCG_CANONICAL_FORMAT);
+        let mut entries = eventlogs.select(None, None).unwrap();
+        assert_eq!(
+            eventlogs.to_pcclient_binary(&entries).unwrap(),
+            boot_time_data
+        );
+
+        // a specification ID event extended to an NV index is skipped like other NV events
+        if let EventLogEntry::TcgCanonicalEvent(event) = &mut entries[0] {
+            event.index = TcgCelIndex::NvIndex(0);
+        }
+        assert_eq!(
+            eventlogs.to_pcclient_binary(&entries).unwrap(),
+            boot_time_data
+        );
+    }
+
+    #[test]
+    fn test_parse_ima_event_log() {
+        let ima_events = vec![
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-sig sha256:0102 /usr/bin/my app 0302\n"
+                .to_string(),
+            "10 2e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha256:0304 /etc/hosts".to_string(),
+        ];
+        let mut eventlogs = EventLogs::new(get_boot_time_data(), ima_events, TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+        let event = match &entries[5] {
+            EventLogEntry::TcgImrEvent(event) => event,
+            _ => panic!("not an IMR event"),
+        };
+        assert_eq!(event.imr_index, 10);
+        assert_eq!(event.digests[0].algo_id, TPM_ALG_SHA1);
+        assert_eq!(event.event, b"sha256:0102 /usr/bin/my app 0302".to_vec());
+        let template = event.ima_template.as_ref().unwrap();
+        assert_eq!(template.template_name, "ima-sig");
+        assert_eq!(template.file_name, "/usr/bin/my app");
+        assert_eq!(template.signature, Some(vec![0x03, 0x02]));
+    }
+
+    #[test]
+    fn test_parse_unknown_ima_template() {
+        // evm-sig template, whose EVM fields are not decoded by the parser
+        let ima_event = "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 evm-sig sha256:0102 /usr/bin/my app 0302 73656375726974792e696d61 0000 0 0 0";
+        let mut eventlogs = EventLogs::new(
+            get_boot_time_data(),
+            vec![ima_event.to_string()],
+            TCG_PCCLIENT_FORMAT,
+        );
+        let entries = eventlogs.select(None, None).unwrap();
+        let event = match &entries[5] {
+            EventLogEntry::TcgImrEvent(event) => event,
+            _ => panic!("not an IMR event"),
+        };
+        assert_eq!(event.event_type, IMA_MEASUREMENT_EVENT);
+        assert!(event.ima_template.is_none());
+        assert_eq!(
+            event.event,
+            b"sha256:0102 /usr/bin/my app 0302 73656375726974792e696d61 0000 0 0 0".to_vec()
+        );
+        assert!(EventLogs::replay(entries).is_ok());
+
+        // but its template data cannot be rebuilt for the canonical format
+        let mut canonical = EventLogs::new(
+            get_boot_time_data(),
+            vec![ima_event.to_string()],
+            TCG_CANONICAL_FORMAT,
+        );
+        assert!(canonical.select(None, None).is_err());
+
+        // binary template data is kept as is
+        let mut data = get_binary_ima_data(&[0x11; 20], b"/usr/bin/my app");
+        data.splice(24..34, [&7u32.to_le_bytes()[..], b"evm-sig"].concat());
+        let mut eventlogs = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(TPM_ALG_SHA1, data.clone())],
+            TCG_CANONICAL_FORMAT,
+        );
+        let entries = eventlogs.select(Some(5), None).unwrap();
+        let event = match &entries[0] {
+            EventLogEntry::TcgCanonicalEvent(event) => event,
+            _ => panic!("not a canonical event"),
+        };
+        match &event.content {
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => {
+                assert_eq!(template_name, "evm-sig");
+                assert_eq!(template_data, &data[39..].to_vec());
+            }
+            _ => panic!("not an IMA template event"),
+        }
+        assert!(event.to_tcg_imr_event().unwrap().ima_template.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_ima_event_log() {
+        for ima_event in [
+            "x 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha256:01 /init",
+            "100 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha256:01 /init",
+            "10 1e76zz ima-ng sha256:01 /init",
+            "10 1e76 ima-ng sha256:01 /init",
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng",
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha256:01",
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-sig sha256:01 /init zz",
+        ] {
+            let mut eventlogs = EventLogs::new(
+                get_boot_time_data(),
+                vec![ima_event.to_string()],
+                TCG_PCCLIENT_FORMAT,
+            );
+            assert!(eventlogs.select(None, None).is_err(), "{}", ima_event);
+        }
+    }
+
+    // template data of an ima-ng event with the given file name and sha256 file digest 0102
+    fn get_ima_ng_template_data(file_name: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for field in [b"sha256:\0\x01\x02".to_vec(), [file_name, b"\0"].concat()] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend(field);
+        }
+        data
+    }
+
+    fn get_binary_ima_data(template_hash: &[u8], file_name: &[u8]) -> Vec<u8> {
+        let template_data = get_ima_ng_template_data(file_name);
+        let mut data = 10u32.to_le_bytes().to_vec();
+        data.extend_from_slice(template_hash);
+        data.extend_from_slice(&6u32.to_le_bytes());
+        data.extend_from_slice(b"ima-ng");
+        data.extend_from_slice(&(template_data.len() as u32).to_le_bytes());
+        data.extend(template_data);
+        data
+    }
+
+    #[test]
+    fn test_parse_ima_binary_event_logs() {
+        let mut sha1_data = get_binary_ima_data(&[0x11; 20], b"/usr/bin/my app");
+        sha1_data.extend(get_binary_ima_data(&[0x12; 20], b"/init"));
+        let mut sha256_data = get_binary_ima_data(&[0x21; 32], b"/usr/bin/my app");
+        sha256_data.extend(get_binary_ima_data(&[0x22; 32], b"/init"));
+
+        let mut eventlogs = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(TPM_ALG_SHA1, sha1_data), (TPM_ALG_SHA256, sha256_data)],
+            TCG_PCCLIENT_FORMAT,
+        );
+        let entries = eventlogs.select(None, None).unwrap();
+        assert_eq!(entries.len(), 7);
+        let event = match &entries[5] {
+            EventLogEntry::TcgImrEvent(event) => event,
+            _ => panic!("not an IMR event"),
+        };
+        assert_eq!(event.imr_index, 10);
+        assert_eq!(event.event_type, IMA_MEASUREMENT_EVENT);
+        assert_eq!(event.digests.len(), 2);
+        assert_eq!(event.digests[0].algo_id, TPM_ALG_SHA1);
+        assert_eq!(event.digests[0].hash, [0x11; 20].to_vec());
+        assert_eq!(event.digests[1].algo_id, TPM_ALG_SHA256);
+        assert_eq!(event.digests[1].hash, [0x21; 32].to_vec());
+        let template = event.ima_template.as_ref().unwrap();
+        assert_eq!(template.file_name, "/usr/bin/my app");
+        assert_eq!(template.file_digest.get_digest_str(), "sha256:0102");
+
+        // the binary template data is kept by the canonical format
+        let mut canonical = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(
+                TPM_ALG_SHA1,
+                get_binary_ima_data(&[0x11; 20], b"/usr/bin/my app"),
+            )],
+            TCG_CANONICAL_FORMAT,
+        );
+        let entries = canonical.select(Some(5), None).unwrap();
+        let event = match &entries[0] {
+            EventLogEntry::TcgCanonicalEvent(event) => event.to_tcg_imr_event().unwrap(),
+            _ => panic!("not a canonical event"),
+        };
+        assert_eq!(
+            event.ima_template.unwrap().file_name,
+            "/usr/bin/my app".to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_ima_binary_event_logs() {
+        let sha1_data = get_binary_ima_data(&[0x11; 20], b"/init");
+        for run_time_binary_data in [
+            vec![(TPM_ALG_SHA1, sha1_data[..sha1_data.len() - 1].to_vec())],
+            vec![(TPM_ALG_ERROR, sha1_data.clone())],
+            vec![
+                (TPM_ALG_SHA1, sha1_data.clone()),
+                (
+                    TPM_ALG_SHA256,
+                    get_binary_ima_data(&[0x21; 32], b"/sbin/init"),
+                ),
+            ],
+            vec![
+                (TPM_ALG_SHA1, sha1_data.clone()),
+                (TPM_ALG_SHA256, Vec::new()),
+            ],
+        ] {
+            let mut eventlogs = EventLogs::new_with_binary_ima(
+                get_boot_time_data(),
+                run_time_binary_data,
+                TCG_PCCLIENT_FORMAT,
+            );
+            assert!(eventlogs.select(None, None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_replay_verify_ima_template() {
+        let boot_aggregate = "2 67c70809bd405ea82081e8f1eb2ca16108bce307f5f139492da641e08e07ec99e2163649f29323a5f5963fe07bb06cc6 ima-ng sha384:cd01ce7f8d1a658f8fdaf33bfb18a7bf9bc3d45386f16be3caf22ef9cb32a26ec53d8b8b74c76b94b744bdf191506cb3 boot_aggregate";
+        let template_hash = hex::encode(Sha1::digest(get_ima_ng_template_data(b"/etc/hosts")));
+        let ima_events = vec![
+            boot_aggregate.to_string(),
+            format!("10 {} ima-ng sha256:0102 /etc/hosts", template_hash),
+            // the same template hash with an altered file name
+            format!("10 {} ima-ng sha256:0102 /etc/passwd", template_hash),
+            // violation
+            format!("10 {} ima-ng sha256:0102 /etc/shadow", "0".repeat(40)),
+        ];
+        let mut eventlogs = EventLogs::new(get_boot_time_data(), ima_events, TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+
+        let report = EventLogs::replay_with_options(
+            entries.clone(),
+            &ReplayOptions {
+                verify_ima_template: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.ima_mismatches.len(), 1);
+        let mismatch = &report.ima_mismatches[0];
+        assert_eq!(mismatch.event_index, 7);
+        assert_eq!(mismatch.imr_index, 10);
+        assert_eq!(mismatch.template_name, "ima-ng");
+        assert_eq!(mismatch.file_name, "/etc/passwd");
+        assert_eq!(mismatch.algo_id, TPM_ALG_SHA1);
+        assert_eq!(hex::encode(&mismatch.logged_hash), template_hash);
+        assert_eq!(
+            mismatch.computed_hash,
+            Some(Sha1::digest(get_ima_ng_template_data(b"/etc/passwd")).to_vec())
+        );
+
+        // the violation is extended as 0xFF bytes
+        let mut expected = vec![0; 20];
+        for hash in [
+            hex::decode(&template_hash).unwrap(),
+            hex::decode(&template_hash).unwrap(),
+            vec![0xff; 20],
+        ] {
+            expected = Sha1::digest([expected, hash].concat()).to_vec();
+        }
+        let result = report
+            .replay_results
+            .iter()
+            .find(|result| result.imr_index == 10)
+            .unwrap();
+        assert_eq!(result.digests[0].hash, expected);
+
+        // replay without options extends the logged template hash
+        let results = EventLogs::replay(entries).unwrap();
+        let result = results
+            .iter()
+            .find(|result| result.imr_index == 10)
+            .unwrap();
+        assert_ne!(result.digests[0].hash, expected);
+    }
+
+    #[test]
+    fn test_replay_verify_binary_ima_template() {
+        // file name not valid UTF-8 is hashed as read from the binary measurement list
+        let file_name = b"/tmp/\xff\xfe";
+        let template_hash = Sha1::digest(get_ima_ng_template_data(file_name)).to_vec();
+        let mut eventlogs = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(TPM_ALG_SHA1, get_binary_ima_data(&template_hash, file_name))],
+            TCG_PCCLIENT_FORMAT,
+        );
+        let options = ReplayOptions {
+            verify_ima_template: true,
+            ..Default::default()
+        };
+        let report =
+            EventLogs::replay_with_options(eventlogs.select(None, None).unwrap(), &options)
+                .unwrap();
+        assert!(report.ima_mismatches.is_empty());
+
+        // and kept by the canonical format
+        let mut canonical = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(TPM_ALG_SHA1, get_binary_ima_data(&template_hash, file_name))],
+            TCG_CANONICAL_FORMAT,
+        );
+        let report =
+            EventLogs::replay_with_options(canonical.select(None, None).unwrap(), &options)
+                .unwrap();
+        assert!(report.ima_mismatches.is_empty());
+
+        // altered template data
+        let mut data = get_binary_ima_data(&template_hash, file_name);
+        let len = data.len();
+        data[len - 2] = b'x';
+        let mut eventlogs = EventLogs::new_with_binary_ima(
+            get_boot_time_data(),
+            vec![(TPM_ALG_SHA1, data)],
+            TCG_PCCLIENT_FORMAT,
+        );
+        let report =
+            EventLogs::replay_with_options(eventlogs.select(None, None).unwrap(), &options)
+                .unwrap();
+        assert_eq!(report.ima_mismatches.len(), 1);
+    }
+
+    #[test]
+    fn test_replay_ima_extend_sha384() {
+        let template_data = get_ima_ng_template_data(b"/etc/hosts");
+        let template_hash = Sha1::digest(&template_data).to_vec();
+        let ima_events = vec![format!(
+            "10 {} ima-ng sha256:0102 /etc/hosts",
+            hex::encode(&template_hash)
+        )];
+        let mut eventlogs = EventLogs::new(get_boot_time_data(), ima_events, TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+
+        let mut options = ReplayOptions {
+            ima_extend_algo_id: Some(TPM_ALG_SHA384),
+            ..Default::default()
+        };
+        for (padded, hash) in [
+            (false, Sha384::digest(&template_data).to_vec()),
+            (true, [template_hash.clone(), vec![0; 28]].concat()),
+        ] {
+            options.ima_extend_padded_sha1 = padded;
+            let report = EventLogs::replay_with_options(entries.clone(), &options).unwrap();
+            let result = report
+                .replay_results
+                .iter()
+                .find(|result| result.imr_index == 10)
+                .unwrap();
+            assert_eq!(result.digests.len(), 1);
+            assert_eq!(result.digests[0].algo_id, TPM_ALG_SHA384);
+            assert_eq!(
+                result.digests[0].hash,
+                Sha384::digest([vec![0; 48], hash].concat()).to_vec()
+            );
+        }
+
+        options.ima_extend_algo_id = Some(TPM_ALG_ERROR);
+        assert!(EventLogs::replay_with_options(entries, &options).is_err());
+    }
+
+    // TcgImrEvent with a SHA384 digest
+    fn get_imr_event(imr_index: u32, byte: u8) -> EventLogEntry {
+        EventLogEntry::TcgImrEvent(TcgImrEvent {
+            imr_index,
+            event_type: EV_POST_CODE,
+            digests: vec![TcgDigest {
+                algo_id: TPM_ALG_SHA384,
+                hash: vec![byte; 48],
+            }],
+            event_size: 0,
+            event: Vec::new(),
+            ima_template: None,
+        })
+    }
+
+    fn get_expected(imr_index: u32, algo_id: u16, hash: Vec<u8>) -> ReplayResult {
+        ReplayResult {
+            imr_index,
+            digests: vec![TcgDigest { algo_id, hash }],
+        }
+    }
+
+    #[test]
+    fn test_replay_trace() {
+        let eventlogs = vec![
+            get_imr_event(0, 0x01),
+            get_imr_event(0, 0x02),
+            get_imr_event(1, 0x03),
+            get_imr_event(0, 0x04),
+        ];
+        let options = ReplayOptions {
+            trace: true,
+            ..Default::default()
+        };
+        let report = EventLogs::replay_with_options(eventlogs.clone(), &options).unwrap();
+        assert_eq!(report.trace.len(), 4);
+
+        let mut value = vec![0; 48];
+        for (entry, byte) in report
+            .trace
+            .iter()
+            .filter(|entry| entry.imr_index == 0)
+            .zip([0x01, 0x02, 0x04])
+        {
+            value = Sha384::digest([value, vec![byte; 48]].concat()).to_vec();
+            assert_eq!(entry.algo_id, TPM_ALG_SHA384);
+            assert_eq!(entry.digest, vec![byte; 48]);
+            assert_eq!(entry.value, value);
+        }
+        assert_eq!(report.trace[3].event_index, 3);
+        assert_eq!(report.replay_results[0].digests[0].hash, value);
+
+        // no trace unless requested
+        let report = EventLogs::replay_with_options(eventlogs, &ReplayOptions::default()).unwrap();
+        assert!(report.trace.is_empty());
+        assert!(report.diagnose(&[]).is_err());
+    }
+
+    #[test]
+    fn test_replay_diagnose() {
+        let eventlogs = vec![
+            get_imr_event(0, 0x01),
+            get_imr_event(0, 0x02),
+            get_imr_event(1, 0x03),
+            get_imr_event(0, 0x04),
+        ];
+        let options = ReplayOptions {
+            trace: true,
+            ..Default::default()
+        };
+        let report = EventLogs::replay_with_options(eventlogs, &options).unwrap();
+        let imr0_after_first = report.trace[0].value.clone();
+        let imr1 = report.trace[2].value.clone();
+
+        let diagnoses = report
+            .diagnose(&[
+                get_expected(0, TPM_ALG_SHA384, imr0_after_first),
+                get_expected(1, TPM_ALG_SHA384, imr1),
+                get_expected(2, TPM_ALG_SHA384, vec![0; 48]),
+                get_expected(3, TPM_ALG_SHA384, vec![0x55; 48]),
+            ])
+            .unwrap();
+        assert_eq!(diagnoses.len(), 4);
+        assert_eq!(
+            diagnoses[0].divergence,
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: Some(0),
+                extra_events: vec![1, 3],
+            }
+        );
+        assert_eq!(diagnoses[1].divergence, ReplayDivergence::Matched);
+        assert_eq!(diagnoses[2].divergence, ReplayDivergence::Matched);
+        assert_eq!(
+            diagnoses[3].divergence,
+            ReplayDivergence::AlteredOrMissingEvents {
+                first_mismatch_event: None,
+            }
+        );
+        assert_eq!(diagnoses[3].replayed, vec![0; 48]);
+
+        // nothing of IMR 1 is measured
+        let diagnoses = report
+            .diagnose(&[get_expected(1, TPM_ALG_SHA384, vec![0; 48])])
+            .unwrap();
+        assert_eq!(
+            diagnoses[0].divergence,
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: None,
+                extra_events: vec![2],
+            }
+        );
+    }
+
+    #[test]
+    fn test_replay_diagnose_diverged_bank() {
+        let report = ReplayReport {
+            replay_results: Vec::new(),
+            ima_mismatches: Vec::new(),
+            startup_localities: HashMap::new(),
+            trace: vec![
+                ReplayTraceEntry {
+                    event_index: 0,
+                    imr_index: 0,
+                    algo_id: TPM_ALG_SHA256,
+                    digest: vec![0x01; 32],
+                    value: vec![0x11; 32],
+                },
+                ReplayTraceEntry {
+                    event_index: 0,
+                    imr_index: 0,
+                    algo_id: TPM_ALG_SHA384,
+                    digest: vec![0x01; 48],
+                    value: vec![0x11; 48],
+                },
+            ],
+        };
+        let expected = ReplayResult {
+            imr_index: 0,
+            digests: vec![
+                TcgDigest {
+                    algo_id: TPM_ALG_SHA256,
+                    hash: vec![0x11; 32],
+                },
+                TcgDigest {
+                    algo_id: TPM_ALG_SHA384,
+                    hash: vec![0x22; 48],
+                },
+            ],
+        };
+        let diagnoses = report.diagnose(std::slice::from_ref(&expected)).unwrap();
+        assert_eq!(diagnoses[0].divergence, ReplayDivergence::Matched);
+        assert_eq!(diagnoses[1].algo_id, TPM_ALG_SHA384);
+        assert_eq!(
+            diagnoses[1].divergence,
+            ReplayDivergence::Diverged {
+                first_mismatch_event: None,
+            }
+        );
+
+        // event 1 is not extended to the SHA384 bank
+        let mut report = report;
+        report.trace.push(ReplayTraceEntry {
+            event_index: 1,
+            imr_index: 0,
+            algo_id: TPM_ALG_SHA256,
+            digest: vec![0x02; 32],
+            value: vec![0x11; 32],
+        });
+        let diagnoses = report.diagnose(std::slice::from_ref(&expected)).unwrap();
+        assert_eq!(
+            diagnoses[1].divergence,
+            ReplayDivergence::Diverged {
+                first_mismatch_event: Some(1),
+            }
+        );
+
+        // template hash of event 0 mismatches in the SHA384 bank
+        report.ima_mismatches.push(ImaTemplateMismatch {
+            event_index: 0,
+            imr_index: 0,
+            template_name: "ima-ng".to_string(),
+            file_name: "/init".to_string(),
+            algo_id: TPM_ALG_SHA384,
+            logged_hash: vec![0x01; 48],
+            computed_hash: Some(vec![0x03; 48]),
+        });
+        let diagnoses = report.diagnose(std::slice::from_ref(&expected)).unwrap();
+        assert_eq!(
+            diagnoses[1].divergence,
+            ReplayDivergence::Diverged {
+                first_mismatch_event: Some(0),
+            }
+        );
+
+        // no bank matches
+        let mut expected = expected;
+        expected.digests[0].hash = vec![0x22; 32];
+        let diagnoses = report.diagnose(&[expected]).unwrap();
+        assert_eq!(
+            diagnoses[0].divergence,
+            ReplayDivergence::AlteredOrMissingEvents {
+                first_mismatch_event: None,
+            }
+        );
+        assert_eq!(
+            diagnoses[1].divergence,
+            ReplayDivergence::AlteredOrMissingEvents {
+                first_mismatch_event: Some(0),
+            }
+        );
+    }
+
+    // measurements of a TPM boot as (PCR index, event type, event data)
+    fn get_tpm_measurements() -> Vec<(u32, u32, Vec<u8>)> {
+        let mut measurements = vec![
+            (0, EV_S_CRTM_VERSION, ucs2("1.0")),
+            (
+                0,
+                EV_EFI_PLATFORM_FIRMWARE_BLOB,
+                vec![
+                    0x00, 0x00, 0x82, 0xff, 0, 0, 0, 0, 0, 0, 0x0e, 0, 0, 0, 0, 0,
+                ],
+            ),
+            (7, EV_EFI_VARIABLE_DRIVER_CONFIG, b"SecureBoot".to_vec()),
+            (1, EV_EFI_VARIABLE_BOOT, b"BootOrder".to_vec()),
+            (
+                4,
+                EV_EFI_ACTION,
+                b"Calling EFI Application from Boot Option".to_vec(),
+            ),
+        ];
+        for pcr_index in 0..8 {
+            measurements.push((pcr_index, EV_SEPARATOR, vec![0; 4]));
+        }
+        measurements.push((4, EV_EFI_BOOT_SERVICES_APPLICATION, vec![0x5a; 40]));
+        measurements
+    }
+
+    // TPM event log of the measurements with SHA1, SHA256 and SHA384 banks, starting with
+    // the StartupLocality event if any
+    fn get_tpm_boot_time_data(
+        startup_locality: Option<u8>,
+        measurements: &[(u32, u32, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut data = get_spec_id_event(0, &TPM_BANKS);
+        if let Some(startup_locality) = startup_locality {
+            data.extend(get_startup_locality_event(startup_locality));
+        }
+        for (pcr_index, event_type, event) in measurements {
+            data.extend(get_event(*pcr_index, *event_type, &TPM_BANKS, None, event));
+        }
+        data
+    }
+
+    // TPM StartupLocality event at PCR 0 with zero digests
+    fn get_startup_locality_event(startup_locality: u8) -> Vec<u8> {
+        let event = [TCG_STARTUP_LOCALITY_SIGNATURE, &[startup_locality]].concat();
+        get_event(0, EV_NO_ACTION, &TPM_BANKS, Some(0), &event)
+    }
+
+    // PCR values of each bank computed independently of the replay, PCR 0 starting from
+    // the startup locality
+    fn get_tpm_pcrs(
+        algo_id: u16,
+        measurements: Vec<(u32, u32, Vec<u8>)>,
+        startup_locality: u8,
+    ) -> HashMap<u32, Vec<u8>> {
+        let digest_size = TcgDigest::get_digest_size_from_algorithm_id(algo_id) as usize;
+        let mut pcrs: HashMap<u32, Vec<u8>> = HashMap::new();
+        let mut pcr0 = vec![0; digest_size];
+        pcr0[digest_size - 1] = startup_locality;
+        pcrs.insert(0, pcr0);
+        for (pcr_index, _, event) in measurements {
+            let pcr = pcrs.entry(pcr_index).or_insert(vec![0; digest_size]);
+            let digest = compute_digest(algo_id, &event).unwrap();
+            *pcr = compute_digest(algo_id, &[pcr.clone(), digest].concat()).unwrap();
+        }
+        pcrs
+    }
+
+    // PCR 0-7 of test_data/tpm_event_log.bin, as printed by test_data/tpm_event_log.py
+    const TPM_EVENT_LOG_PCRS: [(u16, [&str; 8]); 3] = [
+        (
+            TPM_ALG_SHA1,
+            [
+                "e7e8437673fcef4875789f801662f8ec86b3d09a",
+                "ee508bc92498b0aa686e117d985ad22a1d6dc871",
+                "b2a83b0ebf2f8374299a5b2bdfc31ea955ad7236",
+                "b2a83b0ebf2f8374299a5b2bdfc31ea955ad7236",
+                "fe87699e5409ce8ffcd4deca978ab6b060e1f74b",
+                "d16d7e629fd8d08ca256f9ad3a3a1587c9e6cc1b",
+                "b2a83b0ebf2f8374299a5b2bdfc31ea955ad7236",
+                "518bd167271fbb64589c61e43d8c0165861431d8",
+            ],
+        ),
+        (
+            TPM_ALG_SHA256,
+            [
+                "7c8a0995d5eb44f781b920c4fa3091f9c31d45ca1d012b95ed599d21411daa26",
+                "ebd5ef890ece97dc5dbc31f6732f4cb68e9c419951c0c093f95ba60c0ee69632",
+                "3d458cfe55cc03ea1f443f1562beec8df51c75e14a9fcf9a7234a13f198e7969",
+                "3d458cfe55cc03ea1f443f1562beec8df51c75e14a9fcf9a7234a13f198e7969",
+                "960f2077e9282d73e653db41e56ace696da2ee29d8e0c32e31b0794c00943a6d",
+                "a5ceb755d043f32431d63e39f5161464620a3437280494b5850dc1b47cc074e0",
+                "3d458cfe55cc03ea1f443f1562beec8df51c75e14a9fcf9a7234a13f198e7969",
+                "65caf8dd1e0ea7a6347b635d2b379c93b9a1351edc2afc3ecda700e534eb3068",
+            ],
+        ),
+        (
+            TPM_ALG_SHA384,
+            [
+                "401f44c224f7531b8d7d09b86368664a8172ec5e75abd77482b8c899bb7c847d9ec061b51876b34272dd9b9ce656b75f",
+                "9ed4edd4661cb178fe0d10bdc699ca3261d750cc4bc3a552ea156e702bdc2d4d77d09ed17ec744d0b8dc4afae35a60d9",
+                "518923b0f955d08da077c96aaba522b9decede61c599cea6c41889cfbea4ae4d50529d96fe4d1afdafb65e7f95bf23c4",
+                "518923b0f955d08da077c96aaba522b9decede61c599cea6c41889cfbea4ae4d50529d96fe4d1afdafb65e7f95bf23c4",
+                "b0d3a6dec492d11cb66adcaceaae104b3799956c08a9129502ff8003fb3eb1b61038841465762c79896a2b664506678a",
+                "c50b529497c7f441ea47305587d6ce83e2e31f7b4fab6c13dc0b0c3c900e1d0caf0768321100927862df142bf0465ee4",
+                "518923b0f955d08da077c96aaba522b9decede61c599cea6c41889cfbea4ae4d50529d96fe4d1afdafb65e7f95bf23c4",
+                "98441c7f7625d10058c47683aec486ce311c633235eb555593a7ee791121e3578ae72d04ecef661f272d59058b77af35",
+            ],
+        ),
+    ];
+
+    #[test]
+    fn test_replay_multi_bank() {
+        // PCR 2, 3 and 6 only extended with the separator take the well known values
+        let data = include_bytes!("../test_data/tpm_event_log.bin").to_vec();
+        let mut eventlogs = EventLogs::new(data.clone(), Vec::new(), TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+        assert_eq!(eventlogs.imr_index_offset, 0);
+        assert_eq!(eventlogs.to_pcclient_binary(&entries).unwrap(), data);
+
+        let results = EventLogs::replay(entries).unwrap();
+        assert_eq!(results.len(), 8);
+        for result in &results {
+            let algo_ids: Vec<u16> = result.digests.iter().map(|d| d.algo_id).collect();
+            assert_eq!(algo_ids, TPM_BANKS.to_vec());
+            for (digest, (algo_id, pcrs)) in result.digests.iter().zip(TPM_EVENT_LOG_PCRS) {
+                assert_eq!(digest.algo_id, algo_id);
+                assert_eq!(hex::encode(&digest.hash), pcrs[result.imr_index as usize]);
+            }
+        }
+
+        // the canonical format replays to the same values
+        let mut canonical = EventLogs::new(data, Vec::new(), TCG_CANONICAL_FORMAT);
+        let canonical_results = EventLogs::replay(canonical.select(None, None).unwrap()).unwrap();
+        for (result, canonical_result) in results.iter().zip(canonical_results.iter()) {
+            assert_eq!(result.imr_index, canonical_result.imr_index);
+            for (digest, canonical_digest) in
+                result.digests.iter().zip(canonical_result.digests.iter())
+            {
+                assert_eq!(digest.algo_id, canonical_digest.algo_id);
+                assert_eq!(digest.hash, canonical_digest.hash);
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_host_event_log() {
+        // event log of the host checked against the PCR values read from its TPM, skipped
+        // on hosts without a TPM or without access to securityfs
+        let data = match std::fs::read("/sys/kernel/security/tpm0/binary_bios_measurements") {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        let results = EventLogs::replay(eventlogs.select(None, None).unwrap()).unwrap();
+        // PCRs from 8 on may be extended after boot by events not in this log
+        for result in results.iter().filter(|result| result.imr_index < 8) {
+            for digest in &result.digests {
+                let bank = match digest.algo_id {
+                    TPM_ALG_SHA1 => "sha1",
+                    TPM_ALG_SHA256 => "sha256",
+                    TPM_ALG_SHA384 => "sha384",
+                    _ => continue,
+                };
+                let path = format!("/sys/class/tpm/tpm0/pcr-{}/{}", bank, result.imr_index);
+                if let Ok(pcr) = std::fs::read_to_string(path) {
+                    assert_eq!(hex::encode(&digest.hash), pcr.trim().to_lowercase());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_missing_bank() {
+        let mut data = get_tpm_boot_time_data(None, &get_tpm_measurements());
+        data.extend(get_event(
+            5,
+            EV_EFI_GPT_EVENT,
+            &[TPM_ALG_SHA1, TPM_ALG_SHA384],
+            None,
+            b"GPT",
+        ));
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+        assert!(EventLogs::replay(entries.clone()).is_err());
+
+        // without the specification ID event, the banks carried by the events are replayed
+        let results = EventLogs::replay(entries[1..].to_vec()).unwrap();
+        let result = results.iter().find(|result| result.imr_index == 5).unwrap();
+        assert_eq!(result.digests.len(), 3);
+
+        // digest of a size not matching its algorithm
+        let mut entries = entries[..entries.len() - 1].to_vec();
+        if let EventLogEntry::TcgImrEvent(event) = &mut entries[1] {
+            event.digests[1].hash.pop();
+        }
+        assert!(EventLogs::replay(entries).is_err());
+    }
+
+    // check the replay results of each bank against the PCR values computed independently
+    fn check_tpm_replay(data: Vec<u8>, measurements: Vec<(u32, u32, Vec<u8>)>, locality: u8) {
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        let results = EventLogs::replay(eventlogs.select(None, None).unwrap()).unwrap();
+        for algo_id in TPM_BANKS {
+            let pcrs = get_tpm_pcrs(algo_id, measurements.clone(), locality);
+            assert_eq!(results.len(), pcrs.len());
+            for result in &results {
+                let digest = result
+                    .digests
+                    .iter()
+                    .find(|digest| digest.algo_id == algo_id)
+                    .unwrap();
+                assert_eq!(&digest.hash, pcrs.get(&result.imr_index).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_startup_locality() {
+        for startup_locality in [0, 3] {
+            let data = get_tpm_boot_time_data(Some(startup_locality), &get_tpm_measurements());
+            check_tpm_replay(data, get_tpm_measurements(), startup_locality);
+        }
+
+        // startup locality after PCR 0 is extended
+        let mut data = get_tpm_boot_time_data(None, &get_tpm_measurements());
+        data.extend(get_startup_locality_event(3));
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        assert!(EventLogs::replay(eventlogs.select(None, None).unwrap()).is_err());
+
+        // invalid startup locality
+        let data = get_tpm_boot_time_data(Some(2), &[]);
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        assert!(EventLogs::replay(eventlogs.select(None, None).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_replay_diagnose_startup_locality() {
+        let data = get_tpm_boot_time_data(Some(3), &get_tpm_measurements());
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        let options = ReplayOptions {
+            trace: true,
+            ..Default::default()
+        };
+        let report =
+            EventLogs::replay_with_options(eventlogs.select(None, None).unwrap(), &options)
+                .unwrap();
+        assert_eq!(report.startup_localities.get(&0), Some(&3));
+
+        // PCR 0 expected at its initial value has every event of PCR 0 extra
+        let mut initial = vec![0; 32];
+        initial[31] = 3;
+        let diagnoses = report
+            .diagnose(&[
+                get_expected(0, TPM_ALG_SHA256, initial.clone()),
+                get_expected(1, TPM_ALG_SHA256, vec![0; 32]),
+            ])
+            .unwrap();
+        let extra_events: Vec<usize> = report
+            .trace
+            .iter()
+            .filter(|entry| entry.imr_index == 0 && entry.algo_id == TPM_ALG_SHA256)
+            .map(|entry| entry.event_index)
+            .collect();
+        assert_eq!(
+            diagnoses[0].divergence,
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: None,
+                extra_events,
+            }
+        );
+        // other PCRs start from zero
+        assert!(matches!(
+            diagnoses[1].divergence,
+            ReplayDivergence::ExtraEvents {
+                last_matched_event: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_replay_hcrtm() {
+        let mut measurements = vec![(0, EV_EFI_HCRTM_EVENT, b"HCRTM".to_vec())];
+        measurements.extend(get_tpm_measurements());
+
+        // with or without the StartupLocality event of the H-CRTM sequence
+        let data = get_tpm_boot_time_data(Some(TCG_HCRTM_LOCALITY), &measurements);
+        check_tpm_replay(data, measurements.clone(), TCG_HCRTM_LOCALITY);
+        let data = get_tpm_boot_time_data(None, &measurements);
+        check_tpm_replay(data, measurements.clone(), TCG_HCRTM_LOCALITY);
+
+        // H-CRTM event with another startup locality
+        let data = get_tpm_boot_time_data(Some(3), &measurements);
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        assert!(EventLogs::replay(eventlogs.select(None, None).unwrap()).is_err());
+
+        // H-CRTM event after PCR 0 is extended
+        let mut data = get_tpm_boot_time_data(None, &get_tpm_measurements());
+        data.extend(get_event(0, EV_EFI_HCRTM_EVENT, &TPM_BANKS, None, b"HCRTM"));
+        let mut eventlogs = EventLogs::new(data, Vec::new(), TCG_PCCLIENT_FORMAT);
+        assert!(EventLogs::replay(eventlogs.select(None, None).unwrap()).is_err());
+    }
+
+    // CEL-TLV encoding of canonical event logs, carrying the record numbers
+    fn get_cel_tlv(entries: Vec<EventLogEntry>) -> Vec<Vec<u8>> {
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                EventLogEntry::TcgCanonicalEvent(event) => event.to_tlv().unwrap(),
+                _ => panic!("not a canonical event"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_event_log_parser() {
+        let ccel = include_bytes!("../test_data/ccel_data.bin").to_vec();
+        let tpm_event_log = include_bytes!("../test_data/tpm_event_log.bin").to_vec();
+        for data in [tpm_event_log, ccel] {
+            let mut eventlogs = EventLogs::new(data.clone(), Vec::new(), TCG_CANONICAL_FORMAT);
+            let entries = eventlogs.select(None, None).unwrap();
+            // parsed once on repeated select
+            assert_eq!(eventlogs.select(None, None).unwrap().len(), entries.len());
+
+            let mut parser = EventLogParser::new(&data, TCG_CANONICAL_FORMAT).unwrap();
+            let parsed: Vec<EventLogEntry> = (&mut parser).map(|entry| entry.unwrap()).collect();
+            assert_eq!(get_cel_tlv(parsed), get_cel_tlv(entries.clone()));
+            let position = parser.position();
+            assert_eq!(position.count, eventlogs.count);
+            assert!(position.record_numbers == eventlogs.event_logs_record_number_list);
+            assert_eq!(parser.imr_index_offset(), eventlogs.imr_index_offset);
+            assert!(parser.next().is_none());
+        }
+
+        // truncated event log
+        let data = get_tpm_boot_time_data(None, &get_tpm_measurements());
+        let mut parser = EventLogParser::new(&data[..data.len() - 1], TCG_PCCLIENT_FORMAT).unwrap();
+        let results: Vec<Result<EventLogEntry, anyhow::Error>> = (&mut parser).collect();
+        assert!(results.last().unwrap().is_err());
+        assert!(results[..results.len() - 1]
+            .iter()
+            .all(|result| result.is_ok()));
+        assert!(parser.next().is_none());
+
+        // invalid event log formats
+        for parse_format in [0, 3, u8::MAX] {
+            assert!(EventLogParser::new(&data, parse_format).is_err());
+            assert!(EventLogParser::resume(&data, parse_format, parser.position()).is_err());
+            let mut eventlogs = EventLogs::new(data.clone(), Vec::new(), parse_format);
+            assert!(eventlogs.select(None, None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_event_log_parser_resume() {
+        let data = include_bytes!("../test_data/tpm_event_log.bin").to_vec();
+        let mut eventlogs = EventLogs::new(data.clone(), Vec::new(), TCG_CANONICAL_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+
+        // resume from the position of the log parsed earlier, with events appended since
+        let appended = get_event(8, EV_IPL, &TPM_BANKS, None, b"grub_cmd linux");
+        let mut parser = EventLogParser::new(&data, TCG_CANONICAL_FORMAT).unwrap();
+        assert_eq!(parser.by_ref().count(), entries.len());
+        let position = parser.position();
+        let data_appended = [data.clone(), appended].concat();
+        let mut parser =
+            EventLogParser::resume(&data_appended, TCG_CANONICAL_FORMAT, position).unwrap();
+        let parsed: Vec<EventLogEntry> = (&mut parser).map(|entry| entry.unwrap()).collect();
+        let mut eventlogs = EventLogs::new(data_appended, Vec::new(), TCG_CANONICAL_FORMAT);
+        let entries_appended = eventlogs.select(None, None).unwrap();
+        assert_eq!(
+            get_cel_tlv(parsed),
+            get_cel_tlv(entries_appended[entries.len()..].to_vec())
+        );
+
+        // resume from a record number, forward and backward
+        let mut parser = EventLogParser::new(&data, TCG_CANONICAL_FORMAT).unwrap();
+        for count in [5, 2, 0] {
+            parser.seek_record(count).unwrap();
+            assert_eq!(parser.position().count, count);
+            let entry = parser.next().unwrap().unwrap();
+            assert_eq!(
+                get_cel_tlv(vec![entry]),
+                get_cel_tlv(vec![entries[count as usize].clone()])
+            );
+        }
+        assert!(parser.seek_record(entries.len() as u32 + 1).is_err());
+
+        // invalid positions
+        let mut position = EventLogPosition {
+            offset: data.len() + 1,
+            count: 1,
+            ..Default::default()
+        };
+        assert!(EventLogParser::resume(&data, TCG_CANONICAL_FORMAT, position.clone()).is_err());
+        position.offset = 4;
+        assert!(EventLogParser::resume(&data, TCG_CANONICAL_FORMAT, position.clone()).is_err());
+        position.count = 0;
+        assert!(EventLogParser::resume(&data, TCG_CANONICAL_FORMAT, position).is_err());
+    }
+
+    #[test]
+    fn test_replay_ccel() {
+        // CCEL and IMA event log captured in a TD, with RTMR0-2 of test_data/quote.bin
+        // generated in the same TD
+        let ccel = include_bytes!("../test_data/ccel_data.bin").to_vec();
+        let ima_events = String::from_utf8(include_bytes!("../test_data/ima_log.bin").to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let rtmrs = [
+            "c15727cac1f57d0e91106da180b3eaba72116661e17ba0553773843a9b072ecfa38cc803dfb55e0f87ec236780adb3a6",
+            "61e958c6f954ce23031fb3cae87bf4da209d697bc15a4aefcdcc92070f7b2df34720b2bb57de89c9d03c7c9aa1136569",
+            "247eed9f748b228edb65339138eb20ccf78867bc3ec68bb40faffba40ed0f87444a389001e7d1c46a2df56353c047cf2",
+        ];
+
+        let mut eventlogs = EventLogs::new(ccel, ima_events, TCG_PCCLIENT_FORMAT);
+        let entries = eventlogs.select(None, None).unwrap();
+        assert_eq!(eventlogs.imr_index_offset, 1);
+        let results = EventLogs::replay(entries).unwrap();
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert_eq!(result.digests.len(), 1);
+            assert_eq!(result.digests[0].algo_id, TPM_ALG_SHA384);
+            assert_eq!(
+                hex::encode(&result.digests[0].hash),
+                rtmrs[result.imr_index as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_pcclient_binary_invalid() {
+        let mut eventlogs = EventLogs::new(get_boot_time_data(), Vec::new(), TCG_PCCLIENT_FORMAT);
+        // not parsed yet
+        assert!(eventlogs.to_pcclient_binary(&[]).is_err());
+
+        let mut entries = eventlogs.select(None, None).unwrap();
+        if let EventLogEntry::TcgImrEvent(event) = &mut entries[1] {
+            event.digests.push(TcgDigest {
+                algo_id: TPM_ALG_SHA1,
+                hash: vec![0; 20],
+            });
+        }
+        assert!(eventlogs.to_pcclient_binary(&entries).is_err());
+    }
+}
@@ -0,0 +1,833 @@
+use crate::binary_blob::BinaryReader;
+use anyhow::anyhow;
+use log::info;
+
+/***
+    IMA template names defined at
+    https://www.kernel.org/doc/html/latest/security/IMA-templates.html
+*/
+pub const IMA_TEMPLATE_IMA: &str = "ima";
+pub const IMA_TEMPLATE_IMA_NG: &str = "ima-ng";
+pub const IMA_TEMPLATE_IMA_NGV2: &str = "ima-ngv2";
+pub const IMA_TEMPLATE_IMA_SIG: &str = "ima-sig";
+pub const IMA_TEMPLATE_IMA_SIGV2: &str = "ima-sigv2";
+pub const IMA_TEMPLATE_IMA_BUF: &str = "ima-buf";
+pub const IMA_TEMPLATE_IMA_MODSIG: &str = "ima-modsig";
+
+// IMA template field identifiers
+pub const IMA_FIELD_D: &str = "d";
+pub const IMA_FIELD_N: &str = "n";
+pub const IMA_FIELD_D_NG: &str = "d-ng";
+pub const IMA_FIELD_D_NGV2: &str = "d-ngv2";
+pub const IMA_FIELD_N_NG: &str = "n-ng";
+pub const IMA_FIELD_SIG: &str = "sig";
+pub const IMA_FIELD_BUF: &str = "buf";
+pub const IMA_FIELD_D_MODSIG: &str = "d-modsig";
+pub const IMA_FIELD_MODSIG: &str = "modsig";
+
+// maximum length of file names in the "ima" template, which are hashed padded to 256 bytes
+pub const IMA_EVENT_NAME_LEN_MAX: usize = 255;
+
+/***
+    Get the field identifiers of a template.
+    Template names not built in the kernel are taken as template format, e.g. "d-ng|n-ng|sig".
+
+    Args:
+        template_name: the template name or format
+
+    Returns:
+        the field identifiers of the template
+*/
+pub fn get_template_fields(template_name: &str) -> Result<Vec<&str>, anyhow::Error> {
+    let fields = match template_name {
+        IMA_TEMPLATE_IMA => vec![IMA_FIELD_D, IMA_FIELD_N],
+        IMA_TEMPLATE_IMA_NG => vec![IMA_FIELD_D_NG, IMA_FIELD_N_NG],
+        IMA_TEMPLATE_IMA_NGV2 => vec![IMA_FIELD_D_NGV2, IMA_FIELD_N_NG],
+        IMA_TEMPLATE_IMA_SIG => vec![IMA_FIELD_D_NG, IMA_FIELD_N_NG, IMA_FIELD_SIG],
+        IMA_TEMPLATE_IMA_SIGV2 => vec![IMA_FIELD_D_NGV2, IMA_FIELD_N_NG, IMA_FIELD_SIG],
+        IMA_TEMPLATE_IMA_BUF => vec![IMA_FIELD_D_NG, IMA_FIELD_N_NG, IMA_FIELD_BUF],
+        IMA_TEMPLATE_IMA_MODSIG => vec![
+            IMA_FIELD_D_NG,
+            IMA_FIELD_N_NG,
+            IMA_FIELD_SIG,
+            IMA_FIELD_D_MODSIG,
+            IMA_FIELD_MODSIG,
+        ],
+        format if format.contains('|') => format.split('|').collect(),
+        _ => {
+            return Err(anyhow!(
+                "[get_template_fields] unknown IMA template {}",
+                template_name
+            ))
+        }
+    };
+
+    for field in &fields {
+        if ![
+            IMA_FIELD_D,
+            IMA_FIELD_N,
+            IMA_FIELD_D_NG,
+            IMA_FIELD_D_NGV2,
+            IMA_FIELD_N_NG,
+            IMA_FIELD_SIG,
+            IMA_FIELD_BUF,
+            IMA_FIELD_D_MODSIG,
+            IMA_FIELD_MODSIG,
+        ]
+        .contains(field)
+        {
+            return Err(anyhow!(
+                "[get_template_fields] unsupported IMA template field {}",
+                field
+            ));
+        }
+    }
+    // the file digest and the file name must be the first two fields
+    match fields[..] {
+        [IMA_FIELD_D | IMA_FIELD_D_NG | IMA_FIELD_D_NGV2, IMA_FIELD_N | IMA_FIELD_N_NG, ..] => {
+            Ok(fields)
+        }
+        _ => Err(anyhow!(
+            "[get_template_fields] IMA template {} must start with digest and name",
+            template_name
+        )),
+    }
+}
+
+/***
+    Digest carried in an IMA template.
+
+    Attributes:
+        digest_type: "ima" or "verity" for the d-ngv2 field, None otherwise
+        algo: hash algorithm name used by the kernel, e.g. "sha256"
+        hash: the digest value
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImaDigest {
+    pub digest_type: Option<String>,
+    pub algo: String,
+    pub hash: Vec<u8>,
+}
+
+impl ImaDigest {
+    // Parse the ascii form of a digest field, e.g. "sha256:<hex>" for d-ng
+    fn parse_ascii(field_id: &str, value: &str) -> Result<ImaDigest, anyhow::Error> {
+        let items: Vec<&str> = value.split(':').collect();
+        let (prefix, hash) = items.split_at(items.len() - 1);
+        match hex::decode(hash[0]) {
+            Ok(hash) => ImaDigest::new(field_id, prefix, hash),
+            Err(e) => Err(anyhow!(
+                "[ImaDigest] invalid {} field: {} {:?}",
+                field_id,
+                value,
+                e
+            )),
+        }
+    }
+
+    /***
+        Parse the binary form of a digest field. The d field is the raw SHA1 digest,
+        the others are prefixed with "<algo>:" or "<type>:<algo>:" and a NUL byte.
+    */
+    fn parse_binary(field_id: &str, data: &[u8]) -> Result<ImaDigest, anyhow::Error> {
+        if field_id == IMA_FIELD_D {
+            return ImaDigest::new(field_id, &[], data.to_vec());
+        }
+        let prefix_len = match data.iter().position(|b| *b == 0) {
+            Some(prefix_len) => prefix_len,
+            None => {
+                return Err(anyhow!(
+                    "[ImaDigest] missing algorithm prefix of {} field",
+                    field_id
+                ))
+            }
+        };
+        let prefix = match std::str::from_utf8(&data[..prefix_len]) {
+            Ok(prefix) if prefix.ends_with(':') => prefix.trim_end_matches(':'),
+            _ => {
+                return Err(anyhow!(
+                    "[ImaDigest] invalid algorithm prefix of {} field: {:?}",
+                    field_id,
+                    &data[..prefix_len]
+                ))
+            }
+        };
+        let items: Vec<&str> = prefix.split(':').collect();
+        ImaDigest::new(field_id, &items, data[prefix_len + 1..].to_vec())
+    }
+
+    // Build the digest from the prefix items preceding the hash, which depend on the field
+    fn new(field_id: &str, prefix: &[&str], hash: Vec<u8>) -> Result<ImaDigest, anyhow::Error> {
+        let (digest_type, algo) = match (field_id, prefix) {
+            (IMA_FIELD_D, []) => (None, "sha1"),
+            (IMA_FIELD_D_NG | IMA_FIELD_D_MODSIG, [algo]) => (None, *algo),
+            (IMA_FIELD_D_NGV2, [digest_type, algo]) => (Some(digest_type.to_string()), *algo),
+            _ => {
+                return Err(anyhow!(
+                    "[ImaDigest] invalid {} field: {}:{}",
+                    field_id,
+                    prefix.join(":"),
+                    hex::encode(&hash)
+                ))
+            }
+        };
+        Ok(ImaDigest {
+            digest_type,
+            algo: algo.to_string(),
+            hash,
+        })
+    }
+
+    // Get the binary form of the digest field, as hashed into the template hash
+    fn to_binary(&self, field_id: &str) -> Vec<u8> {
+        let mut data = match (field_id, &self.digest_type) {
+            (IMA_FIELD_D, _) => Vec::new(),
+            (_, Some(digest_type)) => format!("{}:{}:\0", digest_type, self.algo).into_bytes(),
+            (_, None) => format!("{}:\0", self.algo).into_bytes(),
+        };
+        data.extend_from_slice(&self.hash);
+        data
+    }
+
+    // Get the ascii form of the digest, as shown in ascii_runtime_measurements
+    pub fn get_digest_str(&self) -> String {
+        match &self.digest_type {
+            Some(digest_type) => {
+                format!("{}:{}:{}", digest_type, self.algo, hex::encode(&self.hash))
+            }
+            None => format!("{}:{}", self.algo, hex::encode(&self.hash)),
+        }
+    }
+}
+
+/***
+    IMA template data decoded into fields.
+    Definition reference:
+    https://www.kernel.org/doc/html/latest/security/IMA-templates.html
+
+    Attributes:
+        template_name: the template name, e.g. "ima-ng"
+        file_digest: the file digest, from d, d-ng or d-ngv2 field
+        file_name: the file name or buffer name, from n or n-ng field
+        signature: the file signature, from sig field
+        buffer: the measured buffer, from buf field
+        modsig_digest: the digest of the file without the appended signature, from d-modsig field
+        modsig: the appended signature, from modsig field
+        template_data: the binary template data the fields are decoded from, None for
+                       templates decoded from the ascii measurement list
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImaTemplate {
+    pub template_name: String,
+    pub file_digest: ImaDigest,
+    pub file_name: String,
+    pub signature: Option<Vec<u8>>,
+    pub buffer: Option<Vec<u8>>,
+    pub modsig_digest: Option<ImaDigest>,
+    pub modsig: Option<Vec<u8>>,
+    pub template_data: Option<Vec<u8>>,
+}
+
+impl ImaTemplate {
+    /***
+        Parse the template fields of a line of ascii_runtime_measurements.
+        Fields are separated by a space and empty fields are printed as empty strings,
+        so the file name, which may contain spaces, is what lies between the digest and
+        the remaining fields. Trailing empty fields trimmed from the line are taken as empty.
+
+        Args:
+            template_name: the template name of the line
+            data: the fields following the template name, e.g.
+                  "sha256:<hex> /usr/bin/bash" for ima-ng
+
+        Returns:
+            The decoded template
+    */
+    pub fn parse_ascii(template_name: &str, data: &str) -> Result<ImaTemplate, anyhow::Error> {
+        let fields = get_template_fields(template_name)?;
+        let tokens: Vec<&str> = data.split(' ').collect();
+        if tokens.len() < 2 {
+            return Err(anyhow!(
+                "[parse_ascii] missing fields of IMA template {}: {}",
+                template_name,
+                data
+            ));
+        }
+
+        let trailing = std::cmp::min(fields.len() - 2, tokens.len() - 2);
+        let mut values = vec![
+            tokens[0].to_string(),
+            tokens[1..tokens.len() - trailing].join(" "),
+        ];
+        values.extend(
+            tokens[tokens.len() - trailing..]
+                .iter()
+                .map(|t| t.to_string()),
+        );
+        values.resize(fields.len(), String::new());
+
+        let mut template = ImaTemplate {
+            template_name: template_name.to_string(),
+            file_digest: ImaDigest::parse_ascii(fields[0], &values[0])?,
+            file_name: values[1].clone(),
+            signature: None,
+            buffer: None,
+            modsig_digest: None,
+            modsig: None,
+            template_data: None,
+        };
+        for (field_id, value) in fields.iter().zip(values.iter()).skip(2) {
+            if value.is_empty() {
+                continue;
+            }
+            match *field_id {
+                IMA_FIELD_D_MODSIG => {
+                    template.modsig_digest = Some(ImaDigest::parse_ascii(field_id, value)?)
+                }
+                _ => {
+                    let value = match hex::decode(value) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            return Err(anyhow!(
+                                "[parse_ascii] invalid {} field: {} {:?}",
+                                field_id,
+                                value,
+                                e
+                            ))
+                        }
+                    };
+                    match *field_id {
+                        IMA_FIELD_SIG => template.signature = Some(value),
+                        IMA_FIELD_BUF => template.buffer = Some(value),
+                        IMA_FIELD_MODSIG => template.modsig = Some(value),
+                        _ => {
+                            return Err(anyhow!(
+                                "[parse_ascii] unexpected {} field in IMA template {}",
+                                field_id,
+                                template_name
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        Ok(template)
+    }
+
+    /***
+        Parse the template data of an entry of binary_runtime_measurements.
+        Each field is prefixed with its length as 4 bytes little endian, except for the
+        "ima" template, where the d field is the raw 20 bytes SHA1 digest.
+
+        Args:
+            template_name: the template name of the entry
+            data: the template data of the entry
+
+        Returns:
+            The decoded template
+    */
+    pub fn parse_binary(template_name: &str, data: &[u8]) -> Result<ImaTemplate, anyhow::Error> {
+        let fields = get_template_fields(template_name)?;
+        let mut reader = BinaryReader::new(data);
+        let mut template = ImaTemplate {
+            template_name: template_name.to_string(),
+            file_digest: ImaDigest {
+                digest_type: None,
+                algo: String::new(),
+                hash: Vec::new(),
+            },
+            file_name: String::new(),
+            signature: None,
+            buffer: None,
+            modsig_digest: None,
+            modsig: None,
+            template_data: Some(data.to_vec()),
+        };
+
+        for field_id in fields {
+            let value = if template_name == IMA_TEMPLATE_IMA && field_id == IMA_FIELD_D {
+                reader.read_bytes(20)
+            } else {
+                reader
+                    .read_u32_le()
+                    .and_then(|len| reader.read_bytes(len as usize))
+            };
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[parse_binary] truncated {} field of IMA template {}: {:?}",
+                        field_id,
+                        template_name,
+                        e
+                    ))
+                }
+            };
+
+            match field_id {
+                IMA_FIELD_D | IMA_FIELD_D_NG | IMA_FIELD_D_NGV2 => {
+                    template.file_digest = ImaDigest::parse_binary(field_id, value)?
+                }
+                IMA_FIELD_N | IMA_FIELD_N_NG => {
+                    let name = match value.iter().position(|b| *b == 0) {
+                        Some(len) => &value[..len],
+                        None => value,
+                    };
+                    template.file_name = String::from_utf8_lossy(name).to_string();
+                }
+                _ if value.is_empty() => (),
+                IMA_FIELD_D_MODSIG => {
+                    template.modsig_digest = Some(ImaDigest::parse_binary(field_id, value)?)
+                }
+                IMA_FIELD_SIG => template.signature = Some(value.to_vec()),
+                IMA_FIELD_BUF => template.buffer = Some(value.to_vec()),
+                IMA_FIELD_MODSIG => template.modsig = Some(value.to_vec()),
+                _ => (),
+            }
+        }
+
+        if !reader.is_empty() {
+            return Err(anyhow!(
+                "[parse_binary] {} bytes left after the fields of IMA template {}",
+                reader.remaining(),
+                template_name
+            ));
+        }
+        Ok(template)
+    }
+
+    /***
+        Get the template data as in the binary IMA runtime measurement list, i.e. the
+        template data hashed into the template hash, except for the "ima" template, which
+        is the SHA1 digest followed by the file name with its length.
+
+        Returns:
+            The binary template data
+    */
+    pub fn get_template_data(&self) -> Result<Vec<u8>, anyhow::Error> {
+        if let Some(template_data) = &self.template_data {
+            return Ok(template_data.clone());
+        }
+        if self.template_name == IMA_TEMPLATE_IMA {
+            let mut data = self.file_digest.hash.clone();
+            data.extend_from_slice(&(self.file_name.len() as u32).to_le_bytes());
+            data.extend_from_slice(self.file_name.as_bytes());
+            return Ok(data);
+        }
+        self.get_template_hash_data()
+    }
+
+    /***
+        Get the template data hashed by the kernel into the template hash.
+        Each field is prefixed with its length as 4 bytes little endian, except for the
+        "ima" template, which is the SHA1 digest followed by the file name padded to 256 bytes.
+        File names are hashed with the terminating NUL byte.
+        The binary template data is hashed as read, with the file name of the "ima" template
+        taken from it, so the file name is not altered by decoding it. Only the template data
+        of templates decoded from the ascii measurement list is rebuilt from the fields.
+
+        Returns:
+            The data to hash for the template hash
+    */
+    pub fn get_template_hash_data(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let mut file_name = self.file_name.as_bytes().to_vec();
+        if let Some(template_data) = &self.template_data {
+            if self.template_name != IMA_TEMPLATE_IMA {
+                return Ok(template_data.clone());
+            }
+            // the SHA1 digest and the length of the file name precede the file name
+            file_name = template_data[std::cmp::min(24, template_data.len())..].to_vec();
+            if let Some(len) = file_name.iter().position(|b| *b == 0) {
+                file_name.truncate(len);
+            }
+        }
+        if self.template_name == IMA_TEMPLATE_IMA {
+            if file_name.len() > IMA_EVENT_NAME_LEN_MAX {
+                return Err(anyhow!(
+                    "[get_template_hash_data] file name longer than {} bytes",
+                    IMA_EVENT_NAME_LEN_MAX
+                ));
+            }
+            file_name.resize(IMA_EVENT_NAME_LEN_MAX + 1, 0);
+            return Ok([self.file_digest.hash.clone(), file_name].concat());
+        }
+        file_name.push(0);
+
+        let mut data = Vec::new();
+        for field_id in get_template_fields(&self.template_name)? {
+            let value = match field_id {
+                IMA_FIELD_D | IMA_FIELD_D_NG | IMA_FIELD_D_NGV2 => {
+                    self.file_digest.to_binary(field_id)
+                }
+                IMA_FIELD_N | IMA_FIELD_N_NG => file_name.clone(),
+                IMA_FIELD_D_MODSIG => match &self.modsig_digest {
+                    Some(modsig_digest) => modsig_digest.to_binary(field_id),
+                    None => Vec::new(),
+                },
+                IMA_FIELD_SIG => self.signature.clone().unwrap_or_default(),
+                IMA_FIELD_BUF => self.buffer.clone().unwrap_or_default(),
+                IMA_FIELD_MODSIG => self.modsig.clone().unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend(value);
+        }
+        Ok(data)
+    }
+
+    pub fn show(&self) {
+        info!("        Template          : {}", self.template_name);
+        info!(
+            "        FileDigest        : {}",
+            self.file_digest.get_digest_str()
+        );
+        info!("        FileName          : {}", self.file_name);
+        if let Some(signature) = &self.signature {
+            info!("        Signature         : {}", hex::encode(signature));
+        }
+        if let Some(buffer) = &self.buffer {
+            info!("        Buffer            : {}", hex::encode(buffer));
+        }
+        if let Some(modsig_digest) = &self.modsig_digest {
+            info!(
+                "        ModsigDigest      : {}",
+                modsig_digest.get_digest_str()
+            );
+        }
+        if let Some(modsig) = &self.modsig {
+            info!("        Modsig            : {}", hex::encode(modsig));
+        }
+    }
+}
+
+/***
+    Entry of the binary IMA runtime measurement list, i.e. binary_runtime_measurements
+    or the per bank binary_runtime_measurements_<algo> files.
+    Definition reference:
+    https://www.kernel.org/doc/html/latest/security/IMA-templates.html
+
+    Attributes:
+        imr_index: the PCR the entry is extended to
+        template_hash: the template digest of the bank the list belongs to
+        template_name: the template name
+        template_data: the template data, as hashed by the kernel except for the "ima" template
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImaMeasurement {
+    pub imr_index: u32,
+    pub template_hash: Vec<u8>,
+    pub template_name: String,
+    pub template_data: Vec<u8>,
+}
+
+impl ImaMeasurement {
+    /***
+        Parse the binary IMA runtime measurement list.
+        Entry Structure, integers in little endian:
+            UINT32 pcr;
+            BYTE template_hash[digest_size];
+            UINT32 template_name_len;
+            CHAR template_name[template_name_len];
+            UINT32 template_data_len; // absent for the "ima" template
+            BYTE template_data[template_data_len];
+        The "ima" template data is the 20 bytes digest followed by the file name with its length.
+
+        Args:
+            data: content of the measurement list
+            digest_size: size of the template hash of the bank the list belongs to
+
+        Returns:
+            The entries of the measurement list
+    */
+    pub fn parse_binary_list(
+        data: &[u8],
+        digest_size: usize,
+    ) -> Result<Vec<ImaMeasurement>, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let mut measurements = Vec::new();
+        while !reader.is_empty() {
+            let offset = reader.position();
+            match ImaMeasurement::read_binary(&mut reader, digest_size) {
+                Ok(measurement) => measurements.push(measurement),
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[parse_binary_list] invalid IMA measurement at offset {}: {:?}",
+                        offset,
+                        e
+                    ))
+                }
+            }
+        }
+        Ok(measurements)
+    }
+
+    fn read_binary(
+        reader: &mut BinaryReader,
+        digest_size: usize,
+    ) -> Result<ImaMeasurement, anyhow::Error> {
+        let imr_index = reader.read_u32_le()?;
+        let template_hash = reader.read_bytes(digest_size)?.to_vec();
+        let name_len = reader.read_u32_le()?;
+        let template_name = match std::str::from_utf8(reader.read_bytes(name_len as usize)?) {
+            Ok(template_name) => template_name.to_string(),
+            Err(e) => return Err(anyhow!("[read_binary] invalid template name: {:?}", e)),
+        };
+        let template_data = if template_name == IMA_TEMPLATE_IMA {
+            let mut template_data = reader.read_bytes(20)?.to_vec();
+            let file_name_len = reader.read_u32_le()?;
+            template_data.extend_from_slice(&file_name_len.to_le_bytes());
+            template_data.extend_from_slice(reader.read_bytes(file_name_len as usize)?);
+            template_data
+        } else {
+            let data_len = reader.read_u32_le()?;
+            reader.read_bytes(data_len as usize)?.to_vec()
+        };
+
+        Ok(ImaMeasurement {
+            imr_index,
+            template_hash,
+            template_name,
+            template_data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_ima_template {
+    use super::*;
+
+    #[test]
+    fn test_parse_ima() {
+        let template = ImaTemplate::parse_ascii(
+            IMA_TEMPLATE_IMA,
+            "0102030405060708090a0b0c0d0e0f1011121314 boot_aggregate",
+        )
+        .unwrap();
+        assert_eq!(template.file_digest.algo, "sha1");
+        assert_eq!(template.file_digest.hash.len(), 20);
+        assert_eq!(template.file_name, "boot_aggregate");
+    }
+
+    #[test]
+    fn test_parse_ima_ng() {
+        let template =
+            ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_NG, "sha384:0a0b /usr/lib/my file.so")
+                .unwrap();
+        assert_eq!(
+            template.file_digest,
+            ImaDigest {
+                digest_type: None,
+                algo: "sha384".to_string(),
+                hash: vec![0x0a, 0x0b],
+            }
+        );
+        // file name with spaces
+        assert_eq!(template.file_name, "/usr/lib/my file.so");
+        assert!(template.signature.is_none());
+
+        let template =
+            ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_NGV2, "verity:sha256:0c /usr/bin/ls")
+                .unwrap();
+        assert_eq!(template.file_digest.digest_type, Some("verity".to_string()));
+        assert_eq!(template.file_digest.get_digest_str(), "verity:sha256:0c");
+    }
+
+    #[test]
+    fn test_parse_ima_sig() {
+        let template =
+            ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_SIG, "sha256:01 /usr/bin/a b 030204ff")
+                .unwrap();
+        assert_eq!(template.file_name, "/usr/bin/a b");
+        assert_eq!(template.signature, Some(vec![0x03, 0x02, 0x04, 0xff]));
+
+        // empty signature printed as empty field
+        let template =
+            ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_SIG, "sha256:01 /usr/bin/a b ").unwrap();
+        assert_eq!(template.file_name, "/usr/bin/a b");
+        assert!(template.signature.is_none());
+
+        // trailing empty signature trimmed from the line
+        let template = ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_SIG, "sha256:01 /init").unwrap();
+        assert_eq!(template.file_name, "/init");
+        assert!(template.signature.is_none());
+    }
+
+    #[test]
+    fn test_parse_ima_buf_and_modsig() {
+        let template =
+            ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_BUF, "sha256:01 kexec-cmdline 726f6f74")
+                .unwrap();
+        assert_eq!(template.file_name, "kexec-cmdline");
+        assert_eq!(template.buffer, Some(b"root".to_vec()));
+
+        let template = ImaTemplate::parse_ascii(
+            IMA_TEMPLATE_IMA_MODSIG,
+            "sha256:01 /lib/modules/a.ko  sha256:02 aabb",
+        )
+        .unwrap();
+        assert!(template.signature.is_none());
+        assert_eq!(
+            template.modsig_digest.unwrap().get_digest_str(),
+            "sha256:02"
+        );
+        assert_eq!(template.modsig, Some(vec![0xaa, 0xbb]));
+
+        // custom template format
+        let template = ImaTemplate::parse_ascii("d-ng|n-ng|buf", "sha1:01 name 02").unwrap();
+        assert_eq!(template.buffer, Some(vec![0x02]));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(ImaTemplate::parse_ascii("unknown", "sha1:01 name").is_err());
+        assert!(ImaTemplate::parse_ascii("d-ng|n-ng|evmsig", "sha1:01 name ").is_err());
+        assert!(ImaTemplate::parse_ascii("n-ng|d-ng", "name sha1:01").is_err());
+        assert!(ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_NG, "sha1:01").is_err());
+        assert!(ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_NG, "sha1:0g name").is_err());
+        assert!(ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_NG, "0102 name").is_err());
+        assert!(ImaTemplate::parse_ascii(IMA_TEMPLATE_IMA_SIG, "sha1:01 name xyz").is_err());
+    }
+
+    fn get_field(value: &[u8]) -> Vec<u8> {
+        let mut field = (value.len() as u32).to_le_bytes().to_vec();
+        field.extend_from_slice(value);
+        field
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut data = get_field(b"sha256:\0\x01\x02");
+        data.extend(get_field(b"/usr/bin/my app\0"));
+        data.extend(get_field(&[0x03, 0x02]));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_SIG, &data).unwrap();
+        assert_eq!(template.file_digest.get_digest_str(), "sha256:0102");
+        assert_eq!(template.file_name, "/usr/bin/my app");
+        assert_eq!(template.signature, Some(vec![0x03, 0x02]));
+
+        let mut data = get_field(b"verity:sha384:\0\x01");
+        data.extend(get_field(b"/init\0"));
+        data.extend(get_field(b""));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_SIGV2, &data).unwrap();
+        assert_eq!(template.file_digest.get_digest_str(), "verity:sha384:01");
+        assert!(template.signature.is_none());
+
+        // the "ima" template has no length for the digest
+        let mut data = [0x11; 20].to_vec();
+        data.extend(get_field(b"boot_aggregate"));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA, &data).unwrap();
+        assert_eq!(template.file_digest.hash, [0x11; 20].to_vec());
+        assert_eq!(template.file_name, "boot_aggregate");
+    }
+
+    #[test]
+    fn test_parse_binary_invalid() {
+        let data = get_field(b"sha256:\0\x01");
+        assert!(ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_NG, &data).is_err());
+        let mut data = get_field(b"sha256\0\x01");
+        data.extend(get_field(b"/init\0"));
+        assert!(ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_NG, &data).is_err());
+        let mut data = get_field(b"sha256:\x01");
+        data.extend(get_field(b"/init\0"));
+        assert!(ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_NG, &data).is_err());
+        let mut data = get_field(b"sha256:\0\x01");
+        data.extend(get_field(b"/init\0"));
+        data.push(0);
+        assert!(ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_NG, &data).is_err());
+    }
+
+    #[test]
+    fn test_parse_binary_list() {
+        let mut template_data = get_field(b"sha1:\0\x01");
+        template_data.extend(get_field(b"/init\0"));
+        let mut data = 10u32.to_le_bytes().to_vec();
+        data.extend([0xaa; 20]);
+        data.extend(get_field(b"ima-ng"));
+        data.extend(get_field(&template_data));
+        data.extend(10u32.to_le_bytes());
+        data.extend([0xbb; 20]);
+        data.extend(get_field(b"ima"));
+        data.extend([0xcc; 20]);
+        data.extend(get_field(b"boot_aggregate"));
+
+        let measurements = ImaMeasurement::parse_binary_list(&data, 20).unwrap();
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(
+            measurements[0],
+            ImaMeasurement {
+                imr_index: 10,
+                template_hash: [0xaa; 20].to_vec(),
+                template_name: "ima-ng".to_string(),
+                template_data,
+            }
+        );
+        assert_eq!(measurements[1].template_name, "ima");
+        assert_eq!(measurements[1].template_data.len(), 20 + 4 + 14);
+
+        assert!(ImaMeasurement::parse_binary_list(&data[..data.len() - 1], 20).is_err());
+        assert!(ImaMeasurement::parse_binary_list(&data, 32).is_err());
+    }
+
+    #[test]
+    fn test_get_template_hash_data() {
+        use sha2::{Digest, Sha384};
+
+        // boot_aggregate measured by the kernel extending a sha384 template hash
+        let template = ImaTemplate::parse_ascii(
+            IMA_TEMPLATE_IMA_NG,
+            "sha384:cd01ce7f8d1a658f8fdaf33bfb18a7bf9bc3d45386f16be3caf22ef9cb32a26ec53d8b8b74c76b94b744bdf191506cb3 boot_aggregate",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(Sha384::digest(template.get_template_hash_data().unwrap())),
+            "67c70809bd405ea82081e8f1eb2ca16108bce307f5f139492da641e08e07ec99e2163649f29323a5f5963fe07bb06cc6"
+        );
+
+        // binary template data is hashed as is
+        let mut data = get_field(b"sha256:\0\x01\x02");
+        data.extend(get_field(b"/usr/bin/my app\0"));
+        data.extend(get_field(b""));
+        data.extend(get_field(b""));
+        data.extend(get_field(b""));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_MODSIG, &data).unwrap();
+        assert_eq!(template.get_template_hash_data().unwrap(), data);
+        assert_eq!(template.get_template_data().unwrap(), data);
+
+        // the "ima" template hashes the file name padded to 256 bytes
+        let template = ImaTemplate::parse_ascii(
+            IMA_TEMPLATE_IMA,
+            "0102030405060708090a0b0c0d0e0f1011121314 /init",
+        )
+        .unwrap();
+        let data = template.get_template_hash_data().unwrap();
+        assert_eq!(data.len(), 20 + 256);
+        assert_eq!(&data[20..26], b"/init\0");
+        // while the measurement list carries the file name with its length
+        let data = template.get_template_data().unwrap();
+        assert_eq!(data.len(), 20 + 4 + 5);
+        let parsed = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA, &data).unwrap();
+        assert_eq!(parsed.file_name, template.file_name);
+        assert_eq!(
+            parsed.get_template_hash_data().unwrap(),
+            template.get_template_hash_data().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_template_hash_data_non_utf8() {
+        // file names not valid UTF-8 are hashed as read from the binary template data
+        let mut data = get_field(b"sha256:\0\x01\x02");
+        data.extend(get_field(b"/tmp/\xff\xfe\0"));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA_NG, &data).unwrap();
+        assert_eq!(template.file_name, "/tmp/\u{fffd}\u{fffd}");
+        assert_eq!(template.get_template_hash_data().unwrap(), data);
+        assert_eq!(template.get_template_data().unwrap(), data);
+
+        let mut data = [0x11; 20].to_vec();
+        data.extend(get_field(b"/tmp/\xff"));
+        let template = ImaTemplate::parse_binary(IMA_TEMPLATE_IMA, &data).unwrap();
+        let hash_data = template.get_template_hash_data().unwrap();
+        assert_eq!(hash_data.len(), 20 + 256);
+        assert_eq!(&hash_data[20..27], b"/tmp/\xff\0");
+        assert_eq!(template.get_template_data().unwrap(), data);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::api_data::{ImaWatchUpdate, ReplayOptions, ReplayResult};
+use crate::eventlog::{EventLogs, Replayer};
+use anyhow::anyhow;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::thread;
+use std::time::Duration;
+
+/***
+    Watcher of the ascii IMA runtime measurement list, e.g. IMA_DATA_FILE_VM, keeping a
+    running replay of the event logs.
+    It remembers the position in the list and only parses the lines appended since the last
+    poll, which are appended to the event logs and extended to the running replay.
+
+    Attributes:
+        eventlogs: boot time event logs and the IMA events read so far
+        path: path of the ascii IMA runtime measurement list
+        offset: bytes of the list read so far, ending with a complete line
+        last_line: last complete line read, ending at the offset
+        replayer: running replay of the event logs
+        failed: whether replaying new events failed or a line failed to parse, which leaves the
+                running replay behind the event logs
+        boot_time_eventlogs: boot time event logs to read the list again from the start
+        boot_time_replayer: replay of the boot time event logs to read the list again from
+                            the start
+*/
+pub struct ImaWatcher {
+    eventlogs: EventLogs,
+    path: String,
+    offset: u64,
+    last_line: Vec<u8>,
+    replayer: Replayer,
+    failed: bool,
+    boot_time_eventlogs: EventLogs,
+    boot_time_replayer: Replayer,
+}
+
+impl ImaWatcher {
+    /***
+        Create the watcher on the boot time event logs, which are replayed at once.
+        The first poll reads the IMA events already in the runtime measurement list.
+
+        Args:
+            boot_time_data: raw data containing all boot time event logs
+            parse_format: event log format used
+            path: path of the ascii IMA runtime measurement list
+            options: options of the running replay, e.g. ima_extend_algo_id for TDX RTMRs
+
+        Returns:
+            The watcher or error if the boot time event logs fail to parse or replay
+    */
+    pub fn new(
+        boot_time_data: Vec<u8>,
+        parse_format: u8,
+        path: &str,
+        options: &ReplayOptions,
+    ) -> Result<ImaWatcher, anyhow::Error> {
+        let mut eventlogs = EventLogs::new(boot_time_data, Vec::new(), parse_format);
+        let mut replayer = Replayer::new(options);
+        for (event_index, event_log) in eventlogs.select(None, None)?.into_iter().enumerate() {
+            replayer.replay_event(event_index, event_log)?;
+        }
+
+        Ok(ImaWatcher {
+            eventlogs: eventlogs.clone(),
+            path: path.to_string(),
+            offset: 0,
+            last_line: Vec::new(),
+            replayer: replayer.clone(),
+            failed: false,
+            boot_time_eventlogs: eventlogs,
+            boot_time_replayer: replayer,
+        })
+    }
+
+    /***
+        Read the lines appended to the IMA runtime measurement list since the last poll.
+        An incomplete last line is left to the next poll. Lines failing to parse are skipped
+        and reported, and stop the running replay as the IMRs they extend can no longer be
+        replayed, same as an error replaying the events. A list no longer
+        ending the part read so far with the last line read, e.g. replaced after a reboot, is
+        read again from the start on top of the boot time replay.
+
+        Returns:
+            The new IMA events, the running replay results of the IMRs they extend, empty once
+            a line is skipped, and the skipped lines, with no event if nothing is appended
+    */
+    pub fn poll(&mut self) -> Result<ImaWatchUpdate, anyhow::Error> {
+        let file = File::open(&self.path)?;
+        self.poll_list(file)
+    }
+
+    fn poll_list<R: Read + Seek>(&mut self, mut list: R) -> Result<ImaWatchUpdate, anyhow::Error> {
+        // the list on securityfs reports a size of 0, so a shrunk or replaced list is
+        // detected by reading the last line again
+        let mut restarted = false;
+        if self.offset > 0 {
+            let mut last_line = Vec::new();
+            list.seek(SeekFrom::Start(self.offset - self.last_line.len() as u64))?;
+            list.by_ref()
+                .take(self.last_line.len() as u64)
+                .read_to_end(&mut last_line)?;
+            restarted = last_line != self.last_line;
+        }
+        if restarted {
+            self.eventlogs = self.boot_time_eventlogs.clone();
+            self.replayer = self.boot_time_replayer.clone();
+            self.offset = 0;
+            self.last_line.clear();
+            self.failed = false;
+        }
+        if self.failed {
+            return Err(anyhow!(
+                "[poll] running replay stopped at a previous error or invalid line"
+            ));
+        }
+
+        list.seek(SeekFrom::Start(self.offset))?;
+        let mut data = Vec::new();
+        list.read_to_end(&mut data)?;
+        let len = match data.iter().rposition(|byte| *byte == b'\n') {
+            Some(pos) => pos + 1,
+            None => 0,
+        };
+        let lines: Vec<String> = String::from_utf8_lossy(&data[..len])
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.to_string())
+            .collect();
+
+        let event_index = self.eventlogs.event_logs.len();
+        let mut events = Vec::new();
+        let mut invalid_lines = Vec::new();
+        for line in lines {
+            match self.eventlogs.append_run_time_data(vec![line.clone()]) {
+                Ok(new_events) => events.extend(new_events),
+                Err(_) => invalid_lines.push(line),
+            }
+        }
+        self.offset += len as u64;
+        if len > 0 {
+            let start = match data[..len - 1].iter().rposition(|byte| *byte == b'\n') {
+                Some(pos) => pos + 1,
+                None => 0,
+            };
+            self.last_line = data[start..len].to_vec();
+        }
+
+        if !invalid_lines.is_empty() {
+            self.failed = true;
+            return Ok(ImaWatchUpdate {
+                events,
+                replay_results: Vec::new(),
+                invalid_lines,
+                restarted,
+            });
+        }
+
+        let mut imr_indexes = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            match self
+                .replayer
+                .replay_event(event_index + index, event.clone())
+            {
+                Ok(Some(imr_index)) if !imr_indexes.contains(&imr_index) => {
+                    imr_indexes.push(imr_index)
+                }
+                Ok(_) => (),
+                Err(e) => {
+                    self.failed = true;
+                    return Err(anyhow!("[poll] error in running replay {:?}", e));
+                }
+            }
+        }
+
+        Ok(ImaWatchUpdate {
+            events,
+            replay_results: self
+                .replayer
+                .replay_results()
+                .iter()
+                .filter(|replay_result| imr_indexes.contains(&replay_result.imr_index))
+                .cloned()
+                .collect(),
+            invalid_lines,
+            restarted,
+        })
+    }
+
+    /***
+        Poll the IMA runtime measurement list at the interval and deliver each update with
+        new events, skipped lines or a restart to the callback, e.g. to send it over a
+        channel, until the callback returns false.
+
+        Args:
+            interval: time between polls
+            callback: receiver of the updates, returning whether to keep watching
+
+        Returns:
+            Error if polling fails
+    */
+    pub fn watch<F>(&mut self, interval: Duration, mut callback: F) -> Result<(), anyhow::Error>
+    where
+        F: FnMut(&ImaWatchUpdate) -> bool,
+    {
+        loop {
+            let update = self.poll()?;
+            let changed =
+                !update.events.is_empty() || !update.invalid_lines.is_empty() || update.restarted;
+            if changed && !callback(&update) {
+                return Ok(());
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    pub fn eventlogs(&self) -> &EventLogs {
+        &self.eventlogs
+    }
+
+    pub fn replay_results(&self) -> &[ReplayResult] {
+        self.replayer.replay_results()
+    }
+}
+
+#[cfg(test)]
+mod test_ima_watcher {
+    use super::*;
+    use crate::tcg::{TCG_PCCLIENT_FORMAT, TPM_ALG_SHA384};
+    use std::fs::{self, OpenOptions};
+    use std::io::{Cursor, Write};
+    use std::sync::mpsc;
+
+    fn get_ccel() -> Vec<u8> {
+        include_bytes!("../test_data/ccel_data.bin").to_vec()
+    }
+
+    fn get_options() -> ReplayOptions {
+        ReplayOptions {
+            ima_extend_algo_id: Some(TPM_ALG_SHA384),
+            ..Default::default()
+        }
+    }
+
+    // IMA events captured in a TD followed by events measured later
+    fn get_ima_lines() -> Vec<String> {
+        let mut lines: Vec<String> =
+            String::from_utf8(include_bytes!("../test_data/ima_log.bin").to_vec())
+                .unwrap()
+                .lines()
+                .map(|line| line.to_string())
+                .collect();
+        for (index, file_name) in ["/usr/bin/bash", "/usr/lib/libc.so.6", "/etc/hosts"]
+            .iter()
+            .enumerate()
+        {
+            lines.push(format!(
+                "2 {} ima-ng sha384:{} {}",
+                hex::encode([index as u8 + 1; 48]),
+                hex::encode([index as u8 + 0x10; 48]),
+                file_name
+            ));
+        }
+        lines
+    }
+
+    fn get_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("ima_watcher_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn append(path: &str, data: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    // list on securityfs, reporting a size of 0
+    struct SecurityFsList(Cursor<Vec<u8>>);
+
+    impl Read for SecurityFsList {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Seek for SecurityFsList {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            match pos {
+                SeekFrom::End(offset) => self.0.seek(SeekFrom::Start(offset.max(0) as u64)),
+                _ => self.0.seek(pos),
+            }
+        }
+    }
+
+    fn get_list(lines: &[String]) -> SecurityFsList {
+        SecurityFsList(Cursor::new(format!("{}\n", lines.join("\n")).into_bytes()))
+    }
+
+    // replay results of the CCEL and the IMA events replayed at once
+    fn check_replay_results(replay_results: &[ReplayResult], lines: &[String], imr_index: u32) {
+        let mut eventlogs = EventLogs::new(get_ccel(), lines.to_vec(), TCG_PCCLIENT_FORMAT);
+        let expected =
+            EventLogs::replay_with_options(eventlogs.select(None, None).unwrap(), &get_options())
+                .unwrap()
+                .replay_results;
+        let expected = expected
+            .iter()
+            .find(|result| result.imr_index == imr_index)
+            .unwrap();
+        let replay_result = replay_results
+            .iter()
+            .find(|result| result.imr_index == imr_index)
+            .unwrap();
+        assert_eq!(replay_result.digests.len(), expected.digests.len());
+        for (digest, expected_digest) in replay_result.digests.iter().zip(&expected.digests) {
+            assert_eq!(digest.algo_id, expected_digest.algo_id);
+            assert_eq!(digest.hash, expected_digest.hash);
+        }
+    }
+
+    #[test]
+    fn test_ima_watcher_poll() {
+        let path = get_path("poll");
+        let lines = get_ima_lines();
+        let (head, tail) = lines[2].split_at(10);
+        append(&path, &format!("{}\n{}\n{}", lines[0], lines[1], head));
+
+        let mut watcher =
+            ImaWatcher::new(get_ccel(), TCG_PCCLIENT_FORMAT, &path, &get_options()).unwrap();
+        let boot_time_count = watcher.eventlogs().count;
+        let update = watcher.poll().unwrap();
+        assert_eq!(update.events.len(), 2);
+        assert_eq!(update.replay_results.len(), 1);
+        check_replay_results(&update.replay_results, &lines[..2], 2);
+
+        // incomplete line
+        let update = watcher.poll().unwrap();
+        assert!(update.events.is_empty());
+        assert!(update.replay_results.is_empty());
+
+        append(&path, &format!("{}\n{}\n", tail, lines[3]));
+        let update = watcher.poll().unwrap();
+        assert_eq!(update.events.len(), 2);
+        check_replay_results(&update.replay_results, &lines, 2);
+        for imr_index in 0..3 {
+            check_replay_results(watcher.replay_results(), &lines, imr_index);
+        }
+        assert_eq!(watcher.eventlogs().count, boot_time_count + 4);
+        assert_eq!(watcher.eventlogs().run_time_data, lines);
+        assert!(!update.restarted);
+
+        // invalid event is skipped and reported, and stops the running replay
+        let mut lines = lines;
+        lines.push(format!(
+            "2 {} ima-ng sha384:{} /etc/passwd",
+            hex::encode([0x04; 48]),
+            hex::encode([0x13; 48])
+        ));
+        append(&path, &format!("2 zz ima-ng\n{}\n", lines[4]));
+        let update = watcher.poll().unwrap();
+        assert_eq!(update.events.len(), 1);
+        assert_eq!(update.invalid_lines, vec!["2 zz ima-ng".to_string()]);
+        assert!(update.replay_results.is_empty());
+        for imr_index in 0..3 {
+            check_replay_results(watcher.replay_results(), &lines[..4], imr_index);
+        }
+        assert!(watcher.poll().is_err());
+        assert_eq!(watcher.eventlogs().count, boot_time_count + 5);
+        assert_eq!(watcher.eventlogs().run_time_data, lines);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ima_watcher_restart() {
+        let path = get_path("restart");
+        let lines = get_ima_lines();
+        append(&path, &format!("{}\n", lines.join("\n")));
+
+        let mut watcher =
+            ImaWatcher::new(get_ccel(), TCG_PCCLIENT_FORMAT, &path, &get_options()).unwrap();
+        let boot_time_count = watcher.eventlogs().count;
+        let update = watcher.poll().unwrap();
+        assert_eq!(update.events.len(), lines.len());
+        assert!(!update.restarted);
+
+        // list replaced by a shorter one is read again from the start
+        fs::remove_file(&path).unwrap();
+        append(&path, &format!("{}\n", lines[..2].join("\n")));
+        let update = watcher.poll().unwrap();
+        assert!(update.restarted);
+        assert_eq!(update.events.len(), 2);
+        check_replay_results(&update.replay_results, &lines[..2], 2);
+        assert_eq!(watcher.eventlogs().count, boot_time_count + 2);
+        assert_eq!(watcher.eventlogs().run_time_data, lines[..2]);
+
+        append(&path, &format!("{}\n", lines[2]));
+        let update = watcher.poll().unwrap();
+        assert!(!update.restarted);
+        assert_eq!(update.events.len(), 1);
+        for imr_index in 0..3 {
+            check_replay_results(watcher.replay_results(), &lines[..3], imr_index);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ima_watcher_poll_size_zero() {
+        let lines = get_ima_lines();
+        let mut watcher = ImaWatcher::new(
+            get_ccel(),
+            TCG_PCCLIENT_FORMAT,
+            &get_path("size_zero"),
+            &get_options(),
+        )
+        .unwrap();
+        let boot_time_count = watcher.eventlogs().count;
+        let mut list = get_list(&lines[..2]);
+        assert_eq!(list.seek(SeekFrom::End(0)).unwrap(), 0);
+        let update = watcher.poll_list(list).unwrap();
+        assert_eq!(update.events.len(), 2);
+        assert!(!update.restarted);
+
+        let update = watcher.poll_list(get_list(&lines[..2])).unwrap();
+        assert!(update.events.is_empty());
+        assert!(!update.restarted);
+
+        let update = watcher.poll_list(get_list(&lines)).unwrap();
+        assert_eq!(update.events.len(), lines.len() - 2);
+        assert!(!update.restarted);
+        for imr_index in 0..3 {
+            check_replay_results(watcher.replay_results(), &lines, imr_index);
+        }
+
+        // list replaced by a shorter one is read again from the start
+        let update = watcher.poll_list(get_list(&lines[..3])).unwrap();
+        assert!(update.restarted);
+        assert_eq!(update.events.len(), 3);
+        assert_eq!(watcher.eventlogs().count, boot_time_count + 3);
+        for imr_index in 0..3 {
+            check_replay_results(watcher.replay_results(), &lines[..3], imr_index);
+        }
+    }
+
+    #[test]
+    fn test_ima_watcher_watch() {
+        let path = get_path("watch");
+        let lines = get_ima_lines();
+        append(&path, &format!("{}\n", lines.join("\n")));
+
+        let mut watcher =
+            ImaWatcher::new(get_ccel(), TCG_PCCLIENT_FORMAT, &path, &get_options()).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        watcher
+            .watch(Duration::from_millis(1), |update| {
+                sender.send(update.clone()).unwrap();
+                false
+            })
+            .unwrap();
+        let update = receiver.try_recv().unwrap();
+        assert_eq!(update.events.len(), lines.len());
+        check_replay_results(&update.replay_results, &lines, 2);
+        assert!(receiver.try_recv().is_err());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().is_err());
+    }
+}
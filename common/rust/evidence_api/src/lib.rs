@@ -5,7 +5,14 @@ pub mod api;
 pub mod api_data;
 pub mod binary_blob;
 pub mod cc_type;
+pub mod cca;
 pub mod eventlog;
+pub mod ima;
+pub mod ima_watcher;
+pub mod sev;
 pub mod tcg;
+pub mod tcgcel;
 pub mod tdx;
 pub mod tpm;
+pub mod uefi;
+pub mod x509;
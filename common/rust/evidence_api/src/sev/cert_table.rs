@@ -0,0 +1,225 @@
+use crate::binary_blob::*;
+use crate::sev::common::*;
+use crate::x509::load_certificates;
+use anyhow::anyhow;
+use log::info;
+use x509_cert::Certificate;
+
+// length of an entry in the certificate table
+pub const SNP_CERT_TABLE_ENTRY_LEN: usize = 24;
+
+// type of the certificate in the certificate table, decided by its GUID
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnpCertType {
+    ARK,
+    ASK,
+    VCEK,
+    VLEK,
+    UNKNOWN,
+}
+
+impl SnpCertType {
+    pub fn from_guid(guid: &[u8; 16]) -> SnpCertType {
+        match *guid {
+            SNP_CERT_GUID_ARK => SnpCertType::ARK,
+            SNP_CERT_GUID_ASK => SnpCertType::ASK,
+            SNP_CERT_GUID_VCEK => SnpCertType::VCEK,
+            SNP_CERT_GUID_VLEK => SnpCertType::VLEK,
+            _ => SnpCertType::UNKNOWN,
+        }
+    }
+}
+
+/***
+    Entry of the certificate table.
+
+    Attributes:
+        guid: GUID of the entry, encoded as EFI_GUID.
+        cert_type: the well-known certificate type of the GUID.
+        data: the certificate, DER encoded for the well-known types.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpCertTableEntry {
+    pub guid: [u8; 16],
+    pub cert_type: SnpCertType,
+    pub data: Vec<u8>,
+}
+
+impl SnpCertTableEntry {
+    // Get the GUID string, e.g. "63da758d-e664-4564-adc5-f4b93be8accd"
+    pub fn get_guid_str(&self) -> String {
+        get_guid_str(&self.guid)
+    }
+
+    pub fn get_certificate(&self) -> Result<Certificate, anyhow::Error> {
+        match load_certificates(&self.data)?.into_iter().next() {
+            Some(cert) => Ok(cert),
+            None => Err(anyhow!(
+                "[get_certificate] no certificate in entry {}",
+                self.get_guid_str()
+            )),
+        }
+    }
+}
+
+/***
+    Certificate table returned with the SEV-SNP extended attestation report, e.g.
+    the cc_aux_blob of CcReport got through configfs-tsm, or the certificate buffer
+    of SNP_GET_EXT_REPORT.
+    Definition reference:
+    https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/56421.pdf
+    Table 24. SNP Extended Guest Request Certificate Table
+    The table is an array of entries terminated by an all-zero entry:
+        Offset  Length  Name
+        0       16      GUID
+        16      4       Offset of the certificate from the start of the table
+        20      4       Length of the certificate
+
+    Attributes:
+        entries: entries in the order found in the table.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpCertTable {
+    pub entries: Vec<SnpCertTableEntry>,
+}
+
+impl SnpCertTable {
+    pub fn new(data: &[u8]) -> Result<SnpCertTable, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let mut entries = Vec::new();
+        loop {
+            let guid = match reader.read_array::<16>() {
+                Ok(guid) => guid,
+                Err(_) => {
+                    return Err(anyhow!(
+                        "[SnpCertTable] certificate table is not terminated"
+                    ))
+                }
+            };
+            let offset = reader.read_u32_le()? as usize;
+            let length = reader.read_u32_le()? as usize;
+            if guid == [0; 16] && offset == 0 && length == 0 {
+                break;
+            }
+
+            let end = match offset.checked_add(length) {
+                Some(end) if end <= data.len() => end,
+                _ => {
+                    return Err(anyhow!(
+                        "[SnpCertTable] certificate at {:#x} with length {:#x} is out of table",
+                        offset,
+                        length
+                    ))
+                }
+            };
+            entries.push(SnpCertTableEntry {
+                guid,
+                cert_type: SnpCertType::from_guid(&guid),
+                data: data[offset..end].to_vec(),
+            });
+        }
+        Ok(SnpCertTable { entries })
+    }
+
+    // Get the first entry of the given certificate type
+    pub fn get_entry(&self, cert_type: &SnpCertType) -> Option<&SnpCertTableEntry> {
+        self.entries
+            .iter()
+            .find(|entry| &entry.cert_type == cert_type)
+    }
+
+    // Get the entry of the given GUID
+    pub fn get_entry_by_guid(&self, guid: &[u8; 16]) -> Option<&SnpCertTableEntry> {
+        self.entries.iter().find(|entry| &entry.guid == guid)
+    }
+
+    pub fn show(&self) {
+        info!("show the data of SnpCertTable");
+        for (index, entry) in self.entries.iter().enumerate() {
+            info!(
+                "entry[{}] guid = {} type = {:?} length = {}",
+                index,
+                entry.get_guid_str(),
+                entry.cert_type,
+                entry.data.len()
+            );
+            match entry.get_certificate() {
+                Ok(cert) => {
+                    info!(
+                        "entry[{}] subject = {}",
+                        index, cert.tbs_certificate.subject
+                    );
+                    info!("entry[{}] issuer = {}", index, cert.tbs_certificate.issuer);
+                }
+                Err(_) => dump_data(&entry.data),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_snp_cert_table {
+    use super::*;
+
+    const UNKNOWN_GUID: [u8; 16] = [0x11; 16];
+
+    fn get_table(entries: &[([u8; 16], Vec<u8>)]) -> Vec<u8> {
+        let mut table = Vec::new();
+        let mut data = Vec::new();
+        let mut offset = (entries.len() + 1) * SNP_CERT_TABLE_ENTRY_LEN;
+        for (guid, cert) in entries.iter() {
+            table.extend_from_slice(guid);
+            table.extend_from_slice(&(offset as u32).to_le_bytes());
+            table.extend_from_slice(&(cert.len() as u32).to_le_bytes());
+            data.extend_from_slice(cert);
+            offset += cert.len();
+        }
+        table.extend_from_slice(&[0; SNP_CERT_TABLE_ENTRY_LEN]);
+        [table, data].concat()
+    }
+
+    #[test]
+    fn test_parse_cert_table() {
+        let vcek = std::fs::read("test_data/sev/vcek.der").unwrap();
+        let data = get_table(&[
+            (SNP_CERT_GUID_VCEK, vcek.clone()),
+            (UNKNOWN_GUID, vec![0x01, 0x02, 0x03]),
+            (SNP_CERT_GUID_ASK, vec![0x04; 8]),
+        ]);
+        let table = SnpCertTable::new(&data).unwrap();
+        assert_eq!(table.entries.len(), 3);
+        assert_eq!(table.entries[0].cert_type, SnpCertType::VCEK);
+        assert_eq!(table.entries[1].cert_type, SnpCertType::UNKNOWN);
+        assert_eq!(table.entries[2].cert_type, SnpCertType::ASK);
+        assert_eq!(
+            table.entries[0].get_guid_str(),
+            "63da758d-e664-4564-adc5-f4b93be8accd"
+        );
+
+        let entry = table.get_entry(&SnpCertType::VCEK).unwrap();
+        assert_eq!(entry.data, vcek);
+        assert!(entry.get_certificate().is_ok());
+        assert_eq!(
+            table.get_entry_by_guid(&UNKNOWN_GUID).unwrap().data,
+            vec![0x01, 0x02, 0x03]
+        );
+        assert!(table.get_entry(&SnpCertType::ARK).is_none());
+        assert!(table.entries[2].get_certificate().is_err());
+    }
+
+    #[test]
+    fn test_empty_cert_table() {
+        let table = SnpCertTable::new(&[0; SNP_CERT_TABLE_ENTRY_LEN]).unwrap();
+        assert!(table.entries.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_cert_table() {
+        let mut data = get_table(&[(SNP_CERT_GUID_ARK, vec![0x01; 4])]);
+        // missing terminating entry
+        assert!(SnpCertTable::new(&data[..SNP_CERT_TABLE_ENTRY_LEN]).is_err());
+        // certificate beyond the table
+        data[20] = 0xff;
+        assert!(SnpCertTable::new(&data).is_err());
+    }
+}
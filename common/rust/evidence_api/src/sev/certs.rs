@@ -0,0 +1,235 @@
+use crate::binary_blob::*;
+use crate::sev::cert_table::*;
+use crate::sev::common::*;
+use crate::x509::*;
+use anyhow::anyhow;
+use log::info;
+use std::time::SystemTime;
+use x509_cert::der::asn1::{Ia5StringRef, ObjectIdentifier, OctetString};
+use x509_cert::der::Decode;
+use x509_cert::Certificate;
+
+// OIDs of the AMD extensions in VCEK/VLEK certificates
+pub const OID_AMD_STRUCT_VERSION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.1");
+pub const OID_AMD_PRODUCT_NAME: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.2");
+pub const OID_AMD_BL_SPL: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.1");
+pub const OID_AMD_TEE_SPL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.2");
+pub const OID_AMD_SNP_SPL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.3");
+pub const OID_AMD_UCODE_SPL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.3.8");
+pub const OID_AMD_HW_ID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.3704.1.4");
+
+/***
+    AMD extensions of VCEK/VLEK certificates.
+
+    Attributes:
+        product_name: product name, e.g. "Milan-B0".
+        bl_spl / tee_spl / snp_spl / ucode_spl: security patch levels of the boot loader,
+            PSP OS, SNP firmware and microcode the key is derived from.
+        hw_id: chip ID the VCEK is derived from, not present in VLEK.
+    Definition reference:
+    https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/57230.pdf
+    Table 8. VCEK Certificate Extensions
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpTcbExtensions {
+    pub product_name: Option<String>,
+    pub bl_spl: u8,
+    pub tee_spl: u8,
+    pub snp_spl: u8,
+    pub ucode_spl: u8,
+    pub hw_id: Option<Vec<u8>>,
+}
+
+impl SnpTcbExtensions {
+    pub fn new(cert: &Certificate) -> Result<SnpTcbExtensions, anyhow::Error> {
+        let product_name = match get_extension(cert, OID_AMD_PRODUCT_NAME) {
+            Some(value) => match Ia5StringRef::from_der(value) {
+                Ok(name) => Some(name.to_string()),
+                Err(e) => return Err(anyhow!("[SnpTcbExtensions] invalid product name: {:?}", e)),
+            },
+            None => None,
+        };
+        let hw_id = match get_extension(cert, OID_AMD_HW_ID) {
+            Some(value) => match OctetString::from_der(value) {
+                Ok(hw_id) => Some(hw_id.into_bytes()),
+                Err(e) => return Err(anyhow!("[SnpTcbExtensions] invalid hwID: {:?}", e)),
+            },
+            None => None,
+        };
+
+        Ok(SnpTcbExtensions {
+            product_name,
+            bl_spl: get_spl(cert, OID_AMD_BL_SPL)?,
+            tee_spl: get_spl(cert, OID_AMD_TEE_SPL)?,
+            snp_spl: get_spl(cert, OID_AMD_SNP_SPL)?,
+            ucode_spl: get_spl(cert, OID_AMD_UCODE_SPL)?,
+            hw_id,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of SnpTcbExtensions");
+        info!("product_name = {:?}", self.product_name);
+        info!("bl_spl = {}", self.bl_spl);
+        info!("tee_spl = {}", self.tee_spl);
+        info!("snp_spl = {}", self.snp_spl);
+        info!("ucode_spl = {}", self.ucode_spl);
+        if let Some(hw_id) = &self.hw_id {
+            info!("hw_id:");
+            dump_data(hw_id);
+        }
+    }
+}
+
+// get the DER encoded value of an extension
+fn get_extension(cert: &Certificate, oid: ObjectIdentifier) -> Option<&[u8]> {
+    cert.tbs_certificate
+        .extensions
+        .as_ref()?
+        .iter()
+        .find(|extension| extension.extn_id == oid)
+        .map(|extension| extension.extn_value.as_bytes())
+}
+
+fn get_spl(cert: &Certificate, oid: ObjectIdentifier) -> Result<u8, anyhow::Error> {
+    match get_extension(cert, oid) {
+        Some(value) => match u8::from_der(value) {
+            Ok(spl) => Ok(spl),
+            Err(e) => Err(anyhow!("[get_spl] invalid SPL {}: {:?}", oid, e)),
+        },
+        None => Err(anyhow!("[get_spl] extension {} not found", oid)),
+    }
+}
+
+/***
+    Certificate chain of the key signing SEV-SNP attestation reports.
+
+    Attributes:
+        certs: X.509 certificates ordered from the VCEK or VLEK to the root, i.e.
+               VCEK -> ASK -> ARK, or VLEK -> ASVK -> ARK. The ARK may be absent.
+*/
+#[derive(Clone)]
+pub struct SnpCertChain {
+    pub certs: Vec<Certificate>,
+}
+
+impl SnpCertChain {
+    /***
+        Build the chain from the VCEK/VLEK certificate and the CA chain, i.e. the
+        cert_chain returned by the AMD Key Distribution Service (ASK followed by ARK).
+    */
+    pub fn new(vek: &[u8], ca_chain: &[u8]) -> Result<SnpCertChain, anyhow::Error> {
+        let mut certs = load_certificates(vek)?;
+        if certs.len() != 1 {
+            return Err(anyhow!(
+                "[SnpCertChain] expect one VCEK/VLEK certificate, but {} found",
+                certs.len()
+            ));
+        }
+        certs.extend(load_certificates(ca_chain)?);
+        Ok(SnpCertChain { certs })
+    }
+
+    pub fn from_files(vek_file: &str, ca_chain_file: &str) -> Result<SnpCertChain, anyhow::Error> {
+        let mut certs = load_certificate_file(vek_file)?;
+        certs.extend(load_certificate_file(ca_chain_file)?);
+        Ok(SnpCertChain { certs })
+    }
+
+    /***
+        Build the chain from the certificate table of an extended attestation report.
+
+        Args:
+            cert_table: the certificate table
+            signing_key: the key signing the report, which decides whether VCEK or
+                         VLEK is the leaf
+
+        Returns:
+            The chain found in the table
+    */
+    pub fn from_cert_table(
+        cert_table: &SnpCertTable,
+        signing_key: &SnpSigningKey,
+    ) -> Result<SnpCertChain, anyhow::Error> {
+        let vek_type = match signing_key {
+            SnpSigningKey::VCEK => SnpCertType::VCEK,
+            SnpSigningKey::VLEK => SnpCertType::VLEK,
+            SnpSigningKey::NONE => {
+                return Err(anyhow!(
+                    "[from_cert_table] report is not signed, no certificate chain to use"
+                ))
+            }
+        };
+
+        let mut certs = Vec::new();
+        for cert_type in [vek_type.clone(), SnpCertType::ASK, SnpCertType::ARK] {
+            match cert_table.get_entry(&cert_type) {
+                Some(entry) => certs.push(entry.get_certificate()?),
+                None if cert_type == vek_type => {
+                    return Err(anyhow!(
+                        "[from_cert_table] {:?} certificate not found in certificate table",
+                        vek_type
+                    ))
+                }
+                None => (),
+            }
+        }
+        Ok(SnpCertChain { certs })
+    }
+
+    pub fn get_leaf_cert(&self) -> &Certificate {
+        &self.certs[0]
+    }
+
+    // Get the AMD TCB extensions of the VCEK/VLEK certificate
+    pub fn get_tcb_extensions(&self) -> Result<SnpTcbExtensions, anyhow::Error> {
+        SnpTcbExtensions::new(self.get_leaf_cert())
+    }
+
+    /***
+        Validate the chain VCEK/VLEK -> ASK/ASVK -> ARK against a trust anchor.
+
+        Args:
+            ark_file: path of the trusted AMD Root Key certificate in PEM or DER
+            crl_files: paths of CRLs in PEM or DER, e.g. the CRL from AMD KDS
+            verify_time: time at which the chain must be valid, default is current time
+
+        Returns:
+            Error describing the first failed check
+    */
+    pub fn verify(
+        &self,
+        ark_file: &str,
+        crl_files: &[String],
+        verify_time: Option<SystemTime>,
+    ) -> Result<(), anyhow::Error> {
+        let trust_anchor = match load_certificate_file(ark_file)?.into_iter().next() {
+            Some(cert) => cert,
+            None => return Err(anyhow!("[verify] no certificate in {}", ark_file)),
+        };
+        let mut crls = Vec::new();
+        for crl_file in crl_files {
+            crls.push(load_crl_file(crl_file)?);
+        }
+
+        verify_cert_chain(
+            &self.certs,
+            &trust_anchor,
+            &crls,
+            verify_time.unwrap_or_else(SystemTime::now),
+        )
+    }
+
+    pub fn show(&self) {
+        info!("show the data of SnpCertChain");
+        for (index, cert) in self.certs.iter().enumerate() {
+            info!("cert[{}] subject = {}", index, cert.tbs_certificate.subject);
+            info!("cert[{}] issuer = {}", index, cert.tbs_certificate.issuer);
+        }
+    }
+}
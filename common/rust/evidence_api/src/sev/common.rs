@@ -0,0 +1,39 @@
+#![allow(non_camel_case_types)]
+
+// attestation report length
+pub const SNP_REPORT_LEN: usize = 0x4A0;
+// offset of the signature in attestation report, i.e. the length of the signed data
+pub const SNP_REPORT_SIGNED_LEN: usize = 0x2A0;
+// header of MSG_REPORT_RSP returned by /dev/sev-guest before the attestation report
+pub const SNP_REPORT_RSP_HEADER_LEN: usize = 0x20;
+
+// SIGNATURE_ALGO of the attestation report
+pub const SNP_SIGNATURE_ALGO_ECDSA_P384_SHA384: u32 = 1;
+
+// key used to sign the attestation report, see SIGNING_KEY of the attestation report
+#[repr(u32)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnpSigningKey {
+    VCEK = 0,
+    VLEK = 1,
+    NONE = 7,
+}
+
+// GUIDs of the certificates in the certificate table of extended report, encoded as
+// EFI_GUID, i.e. the first three fields are little-endian
+// ARK GUID "c0b406a4-a803-4952-9743-3fb6014cd0ae"
+pub const SNP_CERT_GUID_ARK: [u8; 16] = [
+    0xa4, 0x06, 0xb4, 0xc0, 0x03, 0xa8, 0x52, 0x49, 0x97, 0x43, 0x3f, 0xb6, 0x01, 0x4c, 0xd0, 0xae,
+];
+// ASK GUID "4ab7b379-bbac-4fe4-a02f-05aef327c782"
+pub const SNP_CERT_GUID_ASK: [u8; 16] = [
+    0x79, 0xb3, 0xb7, 0x4a, 0xac, 0xbb, 0xe4, 0x4f, 0xa0, 0x2f, 0x05, 0xae, 0xf3, 0x27, 0xc7, 0x82,
+];
+// VCEK GUID "63da758d-e664-4564-adc5-f4b93be8accd"
+pub const SNP_CERT_GUID_VCEK: [u8; 16] = [
+    0x8d, 0x75, 0xda, 0x63, 0x64, 0xe6, 0x64, 0x45, 0xad, 0xc5, 0xf4, 0xb9, 0x3b, 0xe8, 0xac, 0xcd,
+];
+// VLEK GUID "a8074bc2-a25a-483e-aae6-39c045a0b8a1"
+pub const SNP_CERT_GUID_VLEK: [u8; 16] = [
+    0xc2, 0x4b, 0x07, 0xa8, 0x5a, 0xa2, 0x3e, 0x48, 0xaa, 0xe6, 0x39, 0xc0, 0x45, 0xa0, 0xb8, 0xa1,
+];
@@ -0,0 +1,5 @@
+pub mod cert_table;
+pub mod certs;
+pub mod common;
+pub mod report;
+pub mod verify;
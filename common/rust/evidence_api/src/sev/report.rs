@@ -0,0 +1,564 @@
+use crate::api::ParseCcReport;
+use crate::api_data::CcReport;
+use crate::binary_blob::*;
+use crate::sev::common::*;
+use anyhow::anyhow;
+use log::info;
+
+/***
+    Guest policy of SEV-SNP defined at
+    https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/56860.pdf
+    Table 9. Structure of the Guest Policy Field
+        Bit(s)  Name                Description
+        7:0     ABI_MINOR           The minimum ABI minor version required for this guest to run.
+        15:8    ABI_MAJOR           The minimum ABI major version required for this guest to run.
+        16      SMT                 0: SMT is disallowed. 1: SMT is allowed.
+        17      -                   Reserved. Must be one.
+        18      MIGRATE_MA          0: Association with a migration agent is disallowed.
+        19      DEBUG               0: Debugging is disallowed.
+        20      SINGLE_SOCKET       1: Guest can be activated only on one socket.
+        21      CXL_ALLOW           1: CXL can be populated with devices or memory.
+        22      MEM_AES_256_XTS     1: Require AES 256 XTS for memory encryption.
+        23      RAPL_DIS            1: Require Running Average Power Limit (RAPL) disabled.
+        24      CIPHERTEXT_HIDING   1: Require ciphertext hiding to be enabled.
+        25      PAGE_SWAP_DISABLE   1: Disable Guest support for the commands
+                                    SNP_PAGE_MOVE, SNP_SWAP_OUT and SNP_SWAP_IN.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpGuestPolicy {
+    pub raw: u64,
+    pub abi_minor: u8,
+    pub abi_major: u8,
+    pub smt: bool,
+    pub migrate_ma: bool,
+    pub debug: bool,
+    pub single_socket: bool,
+    pub cxl_allow: bool,
+    pub mem_aes_256_xts: bool,
+    pub rapl_dis: bool,
+    pub ciphertext_hiding: bool,
+    pub page_swap_disable: bool,
+}
+
+impl SnpGuestPolicy {
+    pub fn new(raw: u64) -> SnpGuestPolicy {
+        SnpGuestPolicy {
+            raw,
+            abi_minor: raw as u8,
+            abi_major: (raw >> 8) as u8,
+            smt: get_bit(raw, 16),
+            migrate_ma: get_bit(raw, 18),
+            debug: get_bit(raw, 19),
+            single_socket: get_bit(raw, 20),
+            cxl_allow: get_bit(raw, 21),
+            mem_aes_256_xts: get_bit(raw, 22),
+            rapl_dis: get_bit(raw, 23),
+            ciphertext_hiding: get_bit(raw, 24),
+            page_swap_disable: get_bit(raw, 25),
+        }
+    }
+}
+
+/***
+    TCB_VERSION of SEV-SNP defined at
+    Table 3. TCB_VERSION Structure
+        Bit(s)  Name            Description
+        7:0     BOOT_LOADER     SVN of PSP bootloader.
+        15:8    TEE             SVN of PSP operating system.
+        47:16   -               Reserved.
+        55:48   SNP             SVN of SNP firmware.
+        63:56   MICROCODE       Lowest current patch level of all the cores.
+    The layout above is the one of Milan and Genoa processors, which are the ones
+    the VCEK certificates are currently issued for.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpTcbVersion {
+    pub raw: u64,
+    pub boot_loader: u8,
+    pub tee: u8,
+    pub snp: u8,
+    pub microcode: u8,
+}
+
+impl SnpTcbVersion {
+    pub fn new(raw: u64) -> SnpTcbVersion {
+        SnpTcbVersion {
+            raw,
+            boot_loader: raw as u8,
+            tee: (raw >> 8) as u8,
+            snp: (raw >> 48) as u8,
+            microcode: (raw >> 56) as u8,
+        }
+    }
+
+    pub fn show(&self, name: &str) {
+        info!(
+            "{} = {:#018x} (boot_loader: {}, tee: {}, snp: {}, microcode: {})",
+            name, self.raw, self.boot_loader, self.tee, self.snp, self.microcode
+        );
+    }
+}
+
+/***
+    PLATFORM_INFO of SEV-SNP defined at
+    Table 23. PLATFORM_INFO Field
+        Bit(s)  Name                    Description
+        0       SMT_EN                  Indicates that SMT is enabled in the system.
+        1       TSME_EN                 Indicates that TSME is enabled in the system.
+        2       ECC_EN                  Indicates that the platform is using error
+                                        correcting codes for memory.
+        3       RAPL_DIS                Indicates that the RAPL is disabled.
+        4       CIPHERTEXT_HIDING_EN    Indicates ciphertext hiding is enabled.
+        5       ALIAS_CHECK_COMPLETE    Indicates that alias detection has completed.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpPlatformInfo {
+    pub raw: u64,
+    pub smt_en: bool,
+    pub tsme_en: bool,
+    pub ecc_en: bool,
+    pub rapl_dis: bool,
+    pub ciphertext_hiding_en: bool,
+    pub alias_check_complete: bool,
+}
+
+impl SnpPlatformInfo {
+    pub fn new(raw: u64) -> SnpPlatformInfo {
+        SnpPlatformInfo {
+            raw,
+            smt_en: get_bit(raw, 0),
+            tsme_en: get_bit(raw, 1),
+            ecc_en: get_bit(raw, 2),
+            rapl_dis: get_bit(raw, 3),
+            ciphertext_hiding_en: get_bit(raw, 4),
+            alias_check_complete: get_bit(raw, 5),
+        }
+    }
+}
+
+/***
+    ECDSA signature of SEV-SNP defined at
+    Table 141. ECDSA Signature Format
+        Offset  Name    Description
+        0h      R       R component of this signature. Value is zero-extended little-endian encoded.
+        48h     S       S component of this signature. Value is zero-extended little-endian encoded.
+        90h     -       Reserved.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpSignature {
+    pub r: [u8; 72],
+    pub s: [u8; 72],
+}
+
+impl SnpSignature {
+    /***
+        Get the signature as big-endian r || s with each component truncated to the
+        given size, e.g. 48 for ECDSA P-384.
+    */
+    pub fn to_be_bytes(&self, size: usize) -> Vec<u8> {
+        let mut r = self.r[..size].to_vec();
+        let mut s = self.s[..size].to_vec();
+        r.reverse();
+        s.reverse();
+        [r, s].concat()
+    }
+}
+
+/***
+    SEV-SNP attestation report defined at
+    https://www.amd.com/content/dam/amd/en/documents/epyc-technical-docs/specifications/56860.pdf
+    Table 22. ATTESTATION_REPORT Structure
+        Offset      Name                Description
+        00h         VERSION             Version number of this attestation report.
+        04h         GUEST_SVN           The guest SVN.
+        08h         POLICY              The guest policy.
+        10h         FAMILY_ID           The family ID provided at launch.
+        20h         IMAGE_ID            The image ID provided at launch.
+        30h         VMPL                The request VMPL for the attestation report.
+        34h         SIGNATURE_ALGO      The signature algorithm used to sign this report.
+        38h         CURRENT_TCB         CurrentTcb.
+        40h         PLATFORM_INFO       Information about the platform.
+        48h         AUTHOR_KEY_EN       Bit 0: whether the digest of the author key is present
+                    MASK_CHIP_KEY       Bit 1: whether the chip key is masked
+                    SIGNING_KEY         Bits 4:2: the key used to sign this report
+        50h         REPORT_DATA         Guest-provided data.
+        90h         MEASUREMENT         The measurement calculated at launch.
+        C0h         HOST_DATA           Data provided by the hypervisor at launch.
+        E0h         ID_KEY_DIGEST       SHA-384 digest of the ID public key that signed the
+                                        ID block provided in SNP_LAUNCH_FINISH.
+        110h        AUTHOR_KEY_DIGEST   SHA-384 digest of the Author public key that
+                                        certified the ID key.
+        140h        REPORT_ID           Report ID of this guest.
+        160h        REPORT_ID_MA        Report ID of this guest's migration agent.
+        180h        REPORTED_TCB        Reported TCB version used to derive the VCEK.
+        188h        CPUID_FAM_ID        Family of the chip, since version 3.
+        189h        CPUID_MOD_ID        Model of the chip, since version 3.
+        18Ah        CPUID_STEP          Stepping of the chip, since version 3.
+        1A0h        CHIP_ID             Identifier unique to the chip.
+        1E0h        COMMITTED_TCB       CommittedTcb.
+        1E8h        CURRENT_BUILD/MINOR/MAJOR       Version of the current firmware.
+        1ECh        COMMITTED_BUILD/MINOR/MAJOR     Version of the committed firmware.
+        1F0h        LAUNCH_TCB          The CurrentTcb at the time the guest was launched.
+        2A0h        SIGNATURE           Signature of bytes 0h to 29Fh inclusive of this report.
+
+    Attributes:
+        raw: the raw attestation report, used as signed data for verification.
+        the others are the fields of attestation report, with bitfields decoded.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnpReport {
+    pub raw: Vec<u8>,
+    pub version: u32,
+    pub guest_svn: u32,
+    pub policy: SnpGuestPolicy,
+    pub family_id: [u8; 16],
+    pub image_id: [u8; 16],
+    pub vmpl: u32,
+    pub signature_algo: u32,
+    pub current_tcb: SnpTcbVersion,
+    pub platform_info: SnpPlatformInfo,
+    pub author_key_en: bool,
+    pub mask_chip_key: bool,
+    pub signing_key: u32,
+    pub report_data: [u8; 64],
+    pub measurement: [u8; 48],
+    pub host_data: [u8; 32],
+    pub id_key_digest: [u8; 48],
+    pub author_key_digest: [u8; 48],
+    pub report_id: [u8; 32],
+    pub report_id_ma: [u8; 32],
+    pub reported_tcb: SnpTcbVersion,
+    pub cpuid_fam_id: u8,
+    pub cpuid_mod_id: u8,
+    pub cpuid_step: u8,
+    pub chip_id: [u8; 64],
+    pub committed_tcb: SnpTcbVersion,
+    pub current_build: u8,
+    pub current_minor: u8,
+    pub current_major: u8,
+    pub committed_build: u8,
+    pub committed_minor: u8,
+    pub committed_major: u8,
+    pub launch_tcb: SnpTcbVersion,
+    pub signature: SnpSignature,
+}
+
+impl SnpReport {
+    /***
+        Parse the SEV-SNP attestation report.
+
+        Args:
+            report: the attestation report, optionally prefixed with the MSG_REPORT_RSP
+                    header returned by /dev/sev-guest
+
+        Returns:
+            The parsed SnpReport or error if the report is malformed
+    */
+    pub fn parse_snp_report(report: Vec<u8>) -> Result<SnpReport, anyhow::Error> {
+        let report = if report.len() == SNP_REPORT_RSP_HEADER_LEN + SNP_REPORT_LEN {
+            let status = get_u32(report[0..4].to_vec());
+            if status != 0 {
+                return Err(anyhow!(
+                    "[parse_snp_report] report request failed with status {:#x}",
+                    status
+                ));
+            }
+            report[SNP_REPORT_RSP_HEADER_LEN..].to_vec()
+        } else {
+            report
+        };
+        if report.len() != SNP_REPORT_LEN {
+            return Err(anyhow!(
+                "[parse_snp_report] attestation report needs {} bytes, but {} provided",
+                SNP_REPORT_LEN,
+                report.len()
+            ));
+        }
+
+        let mut reader = BinaryReader::new(&report);
+        let version = reader.read_u32_le()?;
+        let guest_svn = reader.read_u32_le()?;
+        let policy = SnpGuestPolicy::new(reader.read_u64_le()?);
+        let family_id = reader.read_array::<16>()?;
+        let image_id = reader.read_array::<16>()?;
+        let vmpl = reader.read_u32_le()?;
+        let signature_algo = reader.read_u32_le()?;
+        let current_tcb = SnpTcbVersion::new(reader.read_u64_le()?);
+        let platform_info = SnpPlatformInfo::new(reader.read_u64_le()?);
+        let key_info = reader.read_u32_le()?;
+        reader.read_bytes(4)?;
+        let report_data = reader.read_array::<64>()?;
+        let measurement = reader.read_array::<48>()?;
+        let host_data = reader.read_array::<32>()?;
+        let id_key_digest = reader.read_array::<48>()?;
+        let author_key_digest = reader.read_array::<48>()?;
+        let report_id = reader.read_array::<32>()?;
+        let report_id_ma = reader.read_array::<32>()?;
+        let reported_tcb = SnpTcbVersion::new(reader.read_u64_le()?);
+        let cpuid_fam_id = reader.read_u8()?;
+        let cpuid_mod_id = reader.read_u8()?;
+        let cpuid_step = reader.read_u8()?;
+        reader.read_bytes(0x1A0 - 0x18B)?;
+        let chip_id = reader.read_array::<64>()?;
+        let committed_tcb = SnpTcbVersion::new(reader.read_u64_le()?);
+        let current_build = reader.read_u8()?;
+        let current_minor = reader.read_u8()?;
+        let current_major = reader.read_u8()?;
+        reader.read_u8()?;
+        let committed_build = reader.read_u8()?;
+        let committed_minor = reader.read_u8()?;
+        let committed_major = reader.read_u8()?;
+        reader.read_u8()?;
+        let launch_tcb = SnpTcbVersion::new(reader.read_u64_le()?);
+        reader.read_bytes(SNP_REPORT_SIGNED_LEN - 0x1F8)?;
+        let signature = SnpSignature {
+            r: reader.read_array::<72>()?,
+            s: reader.read_array::<72>()?,
+        };
+
+        Ok(SnpReport {
+            version,
+            guest_svn,
+            policy,
+            family_id,
+            image_id,
+            vmpl,
+            signature_algo,
+            current_tcb,
+            platform_info,
+            author_key_en: key_info & 0x1 != 0,
+            mask_chip_key: key_info & 0x2 != 0,
+            signing_key: (key_info >> 2) & 0x7,
+            report_data,
+            measurement,
+            host_data,
+            id_key_digest,
+            author_key_digest,
+            report_id,
+            report_id_ma,
+            reported_tcb,
+            cpuid_fam_id,
+            cpuid_mod_id,
+            cpuid_step,
+            chip_id,
+            committed_tcb,
+            current_build,
+            current_minor,
+            current_major,
+            committed_build,
+            committed_minor,
+            committed_major,
+            launch_tcb,
+            signature,
+            raw: report,
+        })
+    }
+
+    // get the data covered by the report signature
+    pub fn get_signed_data(&self) -> &[u8] {
+        &self.raw[..SNP_REPORT_SIGNED_LEN]
+    }
+
+    // get the key used to sign the report
+    pub fn get_signing_key(&self) -> Result<SnpSigningKey, anyhow::Error> {
+        match self.signing_key {
+            0 => Ok(SnpSigningKey::VCEK),
+            1 => Ok(SnpSigningKey::VLEK),
+            7 => Ok(SnpSigningKey::NONE),
+            _ => Err(anyhow!(
+                "[get_signing_key] unknown signing key: {}",
+                self.signing_key
+            )),
+        }
+    }
+
+    pub fn show(&self) {
+        info!("======================================");
+        info!("show the data of SEV-SNP attestation report");
+        info!("version = {}", self.version);
+        info!("guest_svn = {}", self.guest_svn);
+        info!("policy = {:?}", self.policy);
+        info!("family_id:");
+        dump_data(&self.family_id);
+        info!("image_id:");
+        dump_data(&self.image_id);
+        info!("vmpl = {}", self.vmpl);
+        info!("signature_algo = {}", self.signature_algo);
+        self.current_tcb.show("current_tcb");
+        info!("platform_info = {:?}", self.platform_info);
+        info!("author_key_en = {}", self.author_key_en);
+        info!("mask_chip_key = {}", self.mask_chip_key);
+        info!("signing_key = {}", self.signing_key);
+        info!("report_data:");
+        dump_data(&self.report_data);
+        info!("measurement:");
+        dump_data(&self.measurement);
+        info!("host_data:");
+        dump_data(&self.host_data);
+        info!("id_key_digest:");
+        dump_data(&self.id_key_digest);
+        info!("author_key_digest:");
+        dump_data(&self.author_key_digest);
+        info!("report_id:");
+        dump_data(&self.report_id);
+        info!("report_id_ma:");
+        dump_data(&self.report_id_ma);
+        self.reported_tcb.show("reported_tcb");
+        info!(
+            "cpuid = family {:#x}, model {:#x}, stepping {:#x}",
+            self.cpuid_fam_id, self.cpuid_mod_id, self.cpuid_step
+        );
+        info!("chip_id:");
+        dump_data(&self.chip_id);
+        self.committed_tcb.show("committed_tcb");
+        info!(
+            "current firmware = {}.{}.{}",
+            self.current_major, self.current_minor, self.current_build
+        );
+        info!(
+            "committed firmware = {}.{}.{}",
+            self.committed_major, self.committed_minor, self.committed_build
+        );
+        self.launch_tcb.show("launch_tcb");
+        info!("signature r:");
+        dump_data(&self.signature.r);
+        info!("signature s:");
+        dump_data(&self.signature.s);
+    }
+}
+
+fn get_bit(value: u64, bit: u32) -> bool {
+    (value >> bit) & 1 == 1
+}
+
+// API function parses raw cc report to SnpReport struct
+impl ParseCcReport<SnpReport> for CcReport {
+    fn parse_cc_report(report: Vec<u8>) -> Result<SnpReport, anyhow::Error> {
+        match SnpReport::parse_snp_report(report) {
+            Ok(snp_report) => Ok(snp_report),
+            Err(e) => Err(anyhow!("[parse_cc_report] error parse snp report: {:?}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_parse_snp_report {
+    use super::*;
+
+    fn get_report() -> Vec<u8> {
+        let mut report = vec![0; SNP_REPORT_LEN];
+        report[0x00..0x04].copy_from_slice(&3u32.to_le_bytes());
+        report[0x04..0x08].copy_from_slice(&7u32.to_le_bytes());
+        // ABI 1.55, SMT allowed, reserved bit, debug disallowed
+        report[0x08..0x10].copy_from_slice(&0x30137u64.to_le_bytes());
+        report[0x10..0x20].copy_from_slice(&[0x01; 16]);
+        report[0x20..0x30].copy_from_slice(&[0x02; 16]);
+        report[0x30..0x34].copy_from_slice(&1u32.to_le_bytes());
+        report[0x34..0x38].copy_from_slice(&SNP_SIGNATURE_ALGO_ECDSA_P384_SHA384.to_le_bytes());
+        report[0x38..0x40].copy_from_slice(&0xdb18000000000204u64.to_le_bytes());
+        report[0x40..0x48].copy_from_slice(&0x25u64.to_le_bytes());
+        // AUTHOR_KEY_EN and VLEK
+        report[0x48..0x4C].copy_from_slice(&0x5u32.to_le_bytes());
+        report[0x50..0x90].copy_from_slice(&[0x03; 64]);
+        report[0x90..0xC0].copy_from_slice(&[0x04; 48]);
+        report[0xC0..0xE0].copy_from_slice(&[0x05; 32]);
+        report[0xE0..0x110].copy_from_slice(&[0x06; 48]);
+        report[0x110..0x140].copy_from_slice(&[0x07; 48]);
+        report[0x140..0x160].copy_from_slice(&[0x08; 32]);
+        report[0x160..0x180].copy_from_slice(&[0x09; 32]);
+        report[0x180..0x188].copy_from_slice(&0xd315000000000304u64.to_le_bytes());
+        report[0x188..0x18B].copy_from_slice(&[0x19, 0x11, 0x01]);
+        report[0x1A0..0x1E0].copy_from_slice(&[0x0a; 64]);
+        report[0x1E0..0x1E8].copy_from_slice(&0xd414000000000203u64.to_le_bytes());
+        report[0x1E8..0x1EC].copy_from_slice(&[0x15, 0x37, 0x01, 0x00]);
+        report[0x1EC..0x1F0].copy_from_slice(&[0x14, 0x36, 0x01, 0x00]);
+        report[0x1F0..0x1F8].copy_from_slice(&0xd213000000000102u64.to_le_bytes());
+        for i in 0..72 {
+            report[0x2A0 + i] = i as u8;
+            report[0x2E8 + i] = 0x80 + i as u8;
+        }
+        report
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let report = SnpReport::parse_snp_report(get_report()).unwrap();
+        assert_eq!(report.version, 3);
+        assert_eq!(report.guest_svn, 7);
+        assert_eq!(report.policy.abi_major, 1);
+        assert_eq!(report.policy.abi_minor, 55);
+        assert!(report.policy.smt);
+        assert!(!report.policy.debug);
+        assert!(!report.policy.migrate_ma);
+        assert_eq!(report.family_id, [0x01; 16]);
+        assert_eq!(report.image_id, [0x02; 16]);
+        assert_eq!(report.vmpl, 1);
+        assert_eq!(report.signature_algo, SNP_SIGNATURE_ALGO_ECDSA_P384_SHA384);
+        assert_eq!(report.current_tcb.boot_loader, 0x04);
+        assert_eq!(report.current_tcb.tee, 0x02);
+        assert_eq!(report.current_tcb.snp, 0x18);
+        assert_eq!(report.current_tcb.microcode, 0xdb);
+        assert!(report.platform_info.smt_en);
+        assert!(!report.platform_info.tsme_en);
+        assert!(report.platform_info.ecc_en);
+        assert!(report.platform_info.alias_check_complete);
+        assert!(report.author_key_en);
+        assert!(!report.mask_chip_key);
+        assert_eq!(report.get_signing_key().unwrap(), SnpSigningKey::VLEK);
+        assert_eq!(report.report_data, [0x03; 64]);
+        assert_eq!(report.measurement, [0x04; 48]);
+        assert_eq!(report.host_data, [0x05; 32]);
+        assert_eq!(report.id_key_digest, [0x06; 48]);
+        assert_eq!(report.author_key_digest, [0x07; 48]);
+        assert_eq!(report.report_id, [0x08; 32]);
+        assert_eq!(report.report_id_ma, [0x09; 32]);
+        assert_eq!(report.reported_tcb.snp, 0x15);
+        assert_eq!(report.reported_tcb.microcode, 0xd3);
+        assert_eq!(report.cpuid_fam_id, 0x19);
+        assert_eq!(report.cpuid_mod_id, 0x11);
+        assert_eq!(report.cpuid_step, 0x01);
+        assert_eq!(report.chip_id, [0x0a; 64]);
+        assert_eq!(report.committed_tcb.raw, 0xd414000000000203);
+        assert_eq!(
+            (
+                report.current_major,
+                report.current_minor,
+                report.current_build
+            ),
+            (1, 0x37, 0x15)
+        );
+        assert_eq!(
+            (
+                report.committed_major,
+                report.committed_minor,
+                report.committed_build
+            ),
+            (1, 0x36, 0x14)
+        );
+        assert_eq!(report.launch_tcb.tee, 0x01);
+        assert_eq!(report.get_signed_data(), &get_report()[..0x2A0]);
+        let sig = report.signature.to_be_bytes(48);
+        assert_eq!(sig[0], 47);
+        assert_eq!(sig[47], 0);
+        assert_eq!(sig[48], 0x80 + 47);
+    }
+
+    #[test]
+    fn test_parse_report_response() {
+        let mut response = vec![0; SNP_REPORT_RSP_HEADER_LEN];
+        response[4..8].copy_from_slice(&(SNP_REPORT_LEN as u32).to_le_bytes());
+        response.extend_from_slice(&get_report());
+        let report: SnpReport = CcReport::parse_cc_report(response.clone()).unwrap();
+        assert_eq!(report.raw, get_report());
+
+        response[0] = 0x16;
+        assert!(SnpReport::parse_snp_report(response).is_err());
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let report = get_report();
+        assert!(SnpReport::parse_snp_report(report[..SNP_REPORT_LEN - 1].to_vec()).is_err());
+        assert!(SnpReport::parse_snp_report(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::api::ParseCcReport;
+use crate::api_data::CcReport;
+use crate::sev::cert_table::*;
+use crate::sev::certs::*;
+use crate::sev::common::*;
+use crate::sev::report::*;
+use anyhow::anyhow;
+use log::info;
+use p384::ecdsa::signature::Verifier;
+use p384::ecdsa::{Signature, VerifyingKey};
+use std::time::SystemTime;
+
+/***
+    Result of the offline verification of a SEV-SNP attestation report.
+    Each attribute tells whether the corresponding check passed.
+
+    Attributes:
+        report_signature: the report signature verifies against the VCEK/VLEK.
+        reported_tcb: the TCB extensions of the VCEK/VLEK equal REPORTED_TCB.
+        chip_id: the hwID of the VCEK equals CHIP_ID. Always true for VLEK, which
+                 is not bound to a chip, and for reports with a masked chip ID.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnpReportVerifyResult {
+    pub report_signature: bool,
+    pub reported_tcb: bool,
+    pub chip_id: bool,
+}
+
+impl SnpReportVerifyResult {
+    pub fn is_valid(&self) -> bool {
+        self.report_signature && self.reported_tcb && self.chip_id
+    }
+
+    pub fn show(&self) {
+        info!("show the data of SnpReportVerifyResult");
+        info!("report_signature = {}", self.report_signature);
+        info!("reported_tcb = {}", self.reported_tcb);
+        info!("chip_id = {}", self.chip_id);
+    }
+}
+
+impl SnpReport {
+    /***
+        Verify the report against the VCEK/VLEK of the given chain.
+        The chain itself is not validated here, see ``SnpCertChain::verify``.
+
+        Args:
+            cert_chain: the chain whose leaf signed the report
+
+        Returns:
+            SnpReportVerifyResult telling which check failed, or error if the report
+            or the certificate is not supported
+    */
+    pub fn verify_signature(
+        &self,
+        cert_chain: &SnpCertChain,
+    ) -> Result<SnpReportVerifyResult, anyhow::Error> {
+        if self.signature_algo != SNP_SIGNATURE_ALGO_ECDSA_P384_SHA384 {
+            return Err(anyhow!(
+                "[verify_signature] unsupported signature algorithm: {}",
+                self.signature_algo
+            ));
+        }
+        let leaf = cert_chain.get_leaf_cert();
+        let key = match VerifyingKey::from_sec1_bytes(
+            leaf.tbs_certificate
+                .subject_public_key_info
+                .subject_public_key
+                .raw_bytes(),
+        ) {
+            Ok(key) => key,
+            Err(e) => {
+                return Err(anyhow!(
+                    "[verify_signature] invalid VCEK/VLEK public key: {:?}",
+                    e
+                ))
+            }
+        };
+        let report_signature = match Signature::from_slice(&self.signature.to_be_bytes(48)) {
+            Ok(signature) => key.verify(self.get_signed_data(), &signature).is_ok(),
+            Err(_) => false,
+        };
+
+        let extensions = cert_chain.get_tcb_extensions()?;
+        let reported_tcb = extensions.bl_spl == self.reported_tcb.boot_loader
+            && extensions.tee_spl == self.reported_tcb.tee
+            && extensions.snp_spl == self.reported_tcb.snp
+            && extensions.ucode_spl == self.reported_tcb.microcode;
+
+        let chip_id = match &extensions.hw_id {
+            Some(hw_id) => self.mask_chip_key || hw_id[..] == self.chip_id[..],
+            None => true,
+        };
+
+        Ok(SnpReportVerifyResult {
+            report_signature,
+            reported_tcb,
+            chip_id,
+        })
+    }
+
+    /***
+        Verify a SEV-SNP cc report whose cc_aux_blob carries the certificate table,
+        as returned by get_cc_report() through configfs-tsm.
+
+        Args:
+            cc_report: the cc report
+            ark_file: path of the trusted AMD Root Key certificate
+            crl_files: paths of CRLs to check the revocation status of the chain
+            verify_time: time at which the chain must be valid, default is current time
+
+        Returns:
+            SnpReportVerifyResult, or error if the report cannot be parsed or the
+            certificate chain is invalid
+    */
+    pub fn verify_cc_report(
+        cc_report: &CcReport,
+        ark_file: &str,
+        crl_files: &[String],
+        verify_time: Option<SystemTime>,
+    ) -> Result<SnpReportVerifyResult, anyhow::Error> {
+        let report: SnpReport = CcReport::parse_cc_report(cc_report.cc_report.clone())?;
+        let cert_table = match &cc_report.cc_aux_blob {
+            Some(cert_table) => cert_table,
+            None => {
+                return Err(anyhow!(
+                    "[verify_cc_report] no certificate table found in cc report"
+                ))
+            }
+        };
+        let cert_table = SnpCertTable::new(cert_table)?;
+        let cert_chain = SnpCertChain::from_cert_table(&cert_table, &report.get_signing_key()?)?;
+        cert_chain.verify(ark_file, crl_files, verify_time)?;
+        report.verify_signature(&cert_chain)
+    }
+}
+
+#[cfg(test)]
+mod test_verify_snp_report {
+    use super::*;
+    use crate::cc_type::TeeType;
+    use crate::x509::load_certificate_file;
+    use p384::ecdsa::signature::Signer;
+    use p384::ecdsa::SigningKey;
+    use p384::pkcs8::DecodePrivateKey;
+    use std::fs;
+    use x509_cert::der::Encode;
+
+    const ARK_FILE: &str = "test_data/sev/ark.pem";
+    const ASK_FILE: &str = "test_data/sev/ask.pem";
+    const VCEK_FILE: &str = "test_data/sev/vcek.der";
+    const CERT_CHAIN_FILE: &str = "test_data/sev/cert_chain.pem";
+
+    // TCB of the test VCEK: boot loader 3, tee 0, snp 8, microcode 115
+    const VCEK_TCB: u64 = 0x7308000000000003;
+
+    fn get_report(reported_tcb: u64, key_info: u32, chip_id: u8) -> Vec<u8> {
+        let mut report = vec![0; SNP_REPORT_LEN];
+        report[0x00..0x04].copy_from_slice(&2u32.to_le_bytes());
+        report[0x08..0x10].copy_from_slice(&0x30000u64.to_le_bytes());
+        report[0x34..0x38].copy_from_slice(&SNP_SIGNATURE_ALGO_ECDSA_P384_SHA384.to_le_bytes());
+        report[0x48..0x4C].copy_from_slice(&key_info.to_le_bytes());
+        report[0x50..0x90].copy_from_slice(&[0x5a; 64]);
+        report[0x180..0x188].copy_from_slice(&reported_tcb.to_le_bytes());
+        report[0x1A0..0x1E0].copy_from_slice(&[chip_id; 64]);
+
+        let pem = fs::read_to_string("test_data/sev/vcek_key.pem").unwrap();
+        let key = SigningKey::from_pkcs8_pem(&pem).unwrap();
+        let signature: Signature = key.sign(&report[..SNP_REPORT_SIGNED_LEN]);
+        let signature = signature.to_bytes();
+        for i in 0..48 {
+            report[0x2A0 + i] = signature[47 - i];
+            report[0x2E8 + i] = signature[95 - i];
+        }
+        report
+    }
+
+    fn get_cert_table() -> Vec<u8> {
+        let certs = [
+            (SNP_CERT_GUID_VCEK, fs::read(VCEK_FILE).unwrap()),
+            (
+                SNP_CERT_GUID_ASK,
+                load_certificate_file(ASK_FILE).unwrap()[0]
+                    .to_der()
+                    .unwrap(),
+            ),
+            (
+                SNP_CERT_GUID_ARK,
+                load_certificate_file(ARK_FILE).unwrap()[0]
+                    .to_der()
+                    .unwrap(),
+            ),
+        ];
+        let mut table = Vec::new();
+        let mut data = Vec::new();
+        let mut offset = (certs.len() + 1) * 24;
+        for (guid, cert) in certs.iter() {
+            table.extend_from_slice(guid);
+            table.extend_from_slice(&(offset as u32).to_le_bytes());
+            table.extend_from_slice(&(cert.len() as u32).to_le_bytes());
+            data.extend_from_slice(cert);
+            offset += cert.len();
+        }
+        table.extend_from_slice(&[0; 24]);
+        [table, data].concat()
+    }
+
+    fn get_cert_chain() -> SnpCertChain {
+        SnpCertChain::from_files(VCEK_FILE, CERT_CHAIN_FILE).unwrap()
+    }
+
+    #[test]
+    fn test_verify_cc_report() {
+        let cc_report = CcReport {
+            cc_report: get_report(VCEK_TCB, 0, 0x0a),
+            cc_type: TeeType::SEV,
+            cc_aux_blob: Some(get_cert_table()),
+            cc_report_generation: None,
+            cc_provider: None,
+        };
+        let result = SnpReport::verify_cc_report(&cc_report, ARK_FILE, &[], None).unwrap();
+        assert!(result.is_valid());
+
+        // ASK is not a trusted root
+        assert!(SnpReport::verify_cc_report(&cc_report, ASK_FILE, &[], None).is_err());
+    }
+
+    #[test]
+    fn test_verify_cert_chain_files() {
+        let cert_chain = get_cert_chain();
+        assert_eq!(cert_chain.certs.len(), 3);
+        cert_chain.verify(ARK_FILE, &[], None).unwrap();
+
+        let extensions = cert_chain.get_tcb_extensions().unwrap();
+        assert_eq!(extensions.product_name, Some("Milan-B0".to_string()));
+        assert_eq!(
+            (
+                extensions.bl_spl,
+                extensions.tee_spl,
+                extensions.snp_spl,
+                extensions.ucode_spl
+            ),
+            (3, 0, 8, 115)
+        );
+        assert_eq!(extensions.hw_id, Some(vec![0x0a; 64]));
+    }
+
+    #[test]
+    fn test_tampered_report() {
+        let mut raw = get_report(VCEK_TCB, 0, 0x0a);
+        raw[0x50] ^= 0xff;
+        let report = SnpReport::parse_snp_report(raw).unwrap();
+        let result = report.verify_signature(&get_cert_chain()).unwrap();
+        assert!(!result.report_signature);
+        assert!(result.reported_tcb);
+        assert!(result.chip_id);
+    }
+
+    #[test]
+    fn test_reported_tcb_mismatch() {
+        let report = SnpReport::parse_snp_report(get_report(VCEK_TCB + 1, 0, 0x0a)).unwrap();
+        let result = report.verify_signature(&get_cert_chain()).unwrap();
+        assert!(result.report_signature);
+        assert!(!result.reported_tcb);
+    }
+
+    #[test]
+    fn test_chip_id() {
+        let report = SnpReport::parse_snp_report(get_report(VCEK_TCB, 0, 0x0b)).unwrap();
+        let result = report.verify_signature(&get_cert_chain()).unwrap();
+        assert!(result.report_signature);
+        assert!(!result.chip_id);
+
+        // MASK_CHIP_KEY set
+        let report = SnpReport::parse_snp_report(get_report(VCEK_TCB, 0x2, 0)).unwrap();
+        assert!(report
+            .verify_signature(&get_cert_chain())
+            .unwrap()
+            .is_valid());
+    }
+
+    #[test]
+    fn test_missing_vlek() {
+        let cert_table = SnpCertTable::new(&get_cert_table()).unwrap();
+        assert!(SnpCertChain::from_cert_table(&cert_table, &SnpSigningKey::VLEK).is_err());
+        assert!(SnpCertChain::from_cert_table(&cert_table, &SnpSigningKey::NONE).is_err());
+        assert!(SnpCertChain::from_cert_table(&cert_table, &SnpSigningKey::VCEK).is_ok());
+    }
+}
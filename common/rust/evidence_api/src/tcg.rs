@@ -1,14 +1,25 @@
-use crate::binary_blob::dump_data;
+use crate::binary_blob::{dump_data, BinaryReader};
+use crate::ima::ImaTemplate;
+pub use crate::tcgcel::TcgCanonicalEvent;
+use anyhow::anyhow;
 use hashbrown::HashMap;
 use log::info;
 
 pub const TPM_ALG_ERROR: u16 = 0x0;
 pub const TPM_ALG_RSA: u16 = 0x1;
 pub const TPM_ALG_SHA1: u16 = 0x4;
+pub const TPM_ALG_HMAC: u16 = 0x5;
 pub const TPM_ALG_SHA256: u16 = 0xB;
 pub const TPM_ALG_SHA384: u16 = 0xC;
 pub const TPM_ALG_SHA512: u16 = 0xD;
+pub const TPM_ALG_NULL: u16 = 0x10;
+pub const TPM_ALG_RSASSA: u16 = 0x14;
+pub const TPM_ALG_RSAPSS: u16 = 0x16;
 pub const TPM_ALG_ECDSA: u16 = 0x18;
+pub const TPM_ALG_ECDAA: u16 = 0x1A;
+pub const TPM_ALG_SM2: u16 = 0x1B;
+pub const TPM_ALG_ECSCHNORR: u16 = 0x1C;
+pub const TPM_ALG_ECC: u16 = 0x23;
 
 pub const TCG_PCCLIENT_FORMAT: u8 = 1;
 pub const TCG_CANONICAL_FORMAT: u8 = 2;
@@ -20,10 +31,18 @@ lazy_static! {
         map.insert(TPM_ALG_ERROR, "TPM_ALG_ERROR".to_string());
         map.insert(TPM_ALG_RSA, "TPM_ALG_RSA".to_string());
         map.insert(TPM_ALG_SHA1, "TPM_ALG_SHA1".to_string());
+        map.insert(TPM_ALG_HMAC, "TPM_ALG_HMAC".to_string());
         map.insert(TPM_ALG_SHA256, "TPM_ALG_SHA256".to_string());
         map.insert(TPM_ALG_SHA384, "TPM_ALG_SHA384".to_string());
         map.insert(TPM_ALG_SHA512, "TPM_ALG_SHA512".to_string());
+        map.insert(TPM_ALG_NULL, "TPM_ALG_NULL".to_string());
+        map.insert(TPM_ALG_RSASSA, "TPM_ALG_RSASSA".to_string());
+        map.insert(TPM_ALG_RSAPSS, "TPM_ALG_RSAPSS".to_string());
         map.insert(TPM_ALG_ECDSA, "TPM_ALG_ECDSA".to_string());
+        map.insert(TPM_ALG_ECDAA, "TPM_ALG_ECDAA".to_string());
+        map.insert(TPM_ALG_SM2, "TPM_ALG_SM2".to_string());
+        map.insert(TPM_ALG_ECSCHNORR, "TPM_ALG_ECSCHNORR".to_string());
+        map.insert(TPM_ALG_ECC, "TPM_ALG_ECC".to_string());
         map
     };
 }
@@ -269,6 +288,7 @@ impl TcgEventType {
         UINT32 eventSize;
         BYTE event[eventSize];
     } TCG_PCR_EVENT2;
+    ima_template holds the decoded template of IMA measurement events.
 */
 #[derive(Clone)]
 pub struct TcgImrEvent {
@@ -277,6 +297,7 @@ pub struct TcgImrEvent {
     pub digests: Vec<TcgDigest>,
     pub event_size: u32,
     pub event: Vec<u8>,
+    pub ima_template: Option<Box<ImaTemplate>>,
 }
 
 /***
@@ -300,6 +321,9 @@ pub struct TcgPcClientImrEvent {
     pub event: Vec<u8>,
 }
 
+// signature of the TCG_EfiSpecIDEventStruct of crypto agile logs
+pub const TCG_SPEC_ID_EVENT_SIGNATURE: &[u8] = b"Spec ID Event03\0";
+
 /***
     TCG TCG_EfiSpecIDEventStruct defined at
     https://trustedcomputinggroup.org/wp-content/uploads/EFI-Protocol-Specification-rev13-160330final.pdf.
@@ -352,6 +376,77 @@ impl TcgEfiSpecIdEvent {
             vendor_info: Vec::new(),
         }
     }
+
+    /***
+        Parse the event data of the specification ID event.
+
+        Args:
+            data: TCG_EfiSpecIDEventStruct in bytes
+
+        Returns:
+            The parsed TcgEfiSpecIdEvent or error if the data is truncated
+    */
+    pub fn parse(data: &[u8]) -> Result<TcgEfiSpecIdEvent, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let signature = reader.read_array()?;
+        let platform_class = reader.read_u32_le()?;
+        let spec_version_minor = reader.read_u8()?;
+        let spec_version_major = reader.read_u8()?;
+        let spec_errata = reader.read_u8()?;
+        let uintn_ize = reader.read_u8()?;
+        let number_of_algorithms = reader.read_u32_le()?;
+        let mut digest_sizes = Vec::new();
+        for _ in 0..number_of_algorithms {
+            let algo_id = reader.read_u16_le()?;
+            let digest_size = reader.read_u16_le()?;
+            digest_sizes.push(TcgEfiSpecIdEventAlgorithmSize {
+                algo_id,
+                digest_size: digest_size.into(),
+            });
+        }
+        let vendor_info_size = reader.read_u8()?;
+        let vendor_info = reader.read_bytes(vendor_info_size as usize)?.to_vec();
+
+        Ok(TcgEfiSpecIdEvent {
+            signature,
+            platform_class,
+            spec_version_minor,
+            spec_version_major,
+            spec_errata,
+            uintn_ize,
+            number_of_algorithms,
+            digest_sizes,
+            vendor_info_size,
+            vendor_info,
+        })
+    }
+
+    // Encode the struct as the event data of the specification ID event
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.signature);
+        data.extend_from_slice(&self.platform_class.to_le_bytes());
+        data.push(self.spec_version_minor);
+        data.push(self.spec_version_major);
+        data.push(self.spec_errata);
+        data.push(self.uintn_ize);
+        data.extend_from_slice(&self.number_of_algorithms.to_le_bytes());
+        for digest_size in &self.digest_sizes {
+            data.extend_from_slice(&digest_size.algo_id.to_le_bytes());
+            data.extend_from_slice(&(digest_size.digest_size as u16).to_le_bytes());
+        }
+        data.push(self.vendor_info_size);
+        data.extend_from_slice(&self.vendor_info);
+        data
+    }
+
+    // Get the digest size of the algorithm declared in the event
+    pub fn get_digest_size(&self, algo_id: u16) -> Option<u32> {
+        self.digest_sizes
+            .iter()
+            .find(|digest_size| digest_size.algo_id == algo_id)
+            .map(|digest_size| digest_size.digest_size)
+    }
 }
 
 /***
@@ -369,8 +464,64 @@ pub struct TcgEfiSpecIdEventAlgorithmSize {
     pub digest_size: u32,
 }
 
+// signature of the TCG_EfiStartupLocalityEvent logged as EV_NO_ACTION in PCR 0
+pub const TCG_STARTUP_LOCALITY_SIGNATURE: &[u8] = b"StartupLocality\0";
+
+// startup locality logged when PCR 0 is initialized by an H-CRTM sequence
+pub const TCG_HCRTM_LOCALITY: u8 = 4;
+
+/***
+    TCG TCG_EfiStartupLocalityEvent defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClientSpecPlat_TPM_2p0_1p04_pub.pdf.
+    Definition:
+    typedef struct tdTCG_EfiStartupLocalityEvent {
+        BYTE[16] signature;
+        UINT8 StartupLocality;
+    } TCG_EfiStartupLocalityEvent;
+    The startup locality is 0 or 3, the locality TPM2_Startup is issued from, or 4 if PCR 0
+    is initialized by an H-CRTM sequence. PCR 0 starts from the startup locality in its last
+    byte instead of zero.
+*/
 #[derive(Clone)]
-pub struct TcgCanonicalEvent {}
+pub struct TcgEfiStartupLocalityEvent {
+    pub signature: [u8; 16],
+    pub startup_locality: u8,
+}
+
+impl TcgEfiStartupLocalityEvent {
+    /***
+        Parse the event data of the startup locality event.
+
+        Args:
+            data: TCG_EfiStartupLocalityEvent in bytes
+
+        Returns:
+            The parsed TcgEfiStartupLocalityEvent or error if the signature does not match,
+            the data is truncated or the locality is invalid
+    */
+    pub fn parse(data: &[u8]) -> Result<TcgEfiStartupLocalityEvent, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let signature: [u8; 16] = reader.read_array()?;
+        if signature != TCG_STARTUP_LOCALITY_SIGNATURE {
+            return Err(anyhow!(
+                "[TcgEfiStartupLocalityEvent::parse] invalid signature: {:02X?}",
+                signature
+            ));
+        }
+        let startup_locality = reader.read_u8()?;
+        if ![0, 3, TCG_HCRTM_LOCALITY].contains(&startup_locality) {
+            return Err(anyhow!(
+                "[TcgEfiStartupLocalityEvent::parse] invalid startup locality: {}",
+                startup_locality
+            ));
+        }
+
+        Ok(TcgEfiStartupLocalityEvent {
+            signature,
+            startup_locality,
+        })
+    }
+}
 
 #[derive(Clone)]
 pub enum EventLogEntry {
@@ -386,7 +537,7 @@ impl EventLogEntry {
             EventLogEntry::TcgPcClientImrEvent(tcg_pc_client_imr_event) => {
                 &tcg_pc_client_imr_event.show()
             }
-            EventLogEntry::TcgCanonicalEvent(_) => todo!(),
+            EventLogEntry::TcgCanonicalEvent(tcg_canonical_event) => &tcg_canonical_event.show(),
         };
     }
 }
@@ -417,7 +568,14 @@ impl TcgImrEvent {
             dump_data(&self.digests[digest_index].hash);
         }
         info!("        Event:");
-        dump_data(&self.event);
+        if let Some(ima_template) = &self.ima_template {
+            ima_template.show();
+            return;
+        }
+        match self.get_event_data() {
+            Ok(Some(event_data)) => event_data.show(),
+            _ => dump_data(&self.event),
+        }
     }
 }
 
@@ -433,7 +591,7 @@ impl TcgPcClientImrEvent {
             &TcgEventType::get_event_type_string(self.event_type)
         );
         info!("        Digest:");
-        dump_data(&self.digest.to_vec());
+        dump_data(&self.digest);
         info!("        Event:");
         dump_data(&self.event);
     }
@@ -0,0 +1,970 @@
+use crate::binary_blob::*;
+use crate::ima::ImaTemplate;
+use crate::tcg::*;
+use anyhow::anyhow;
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/***
+    TCG Canonical Event Log types defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_IWG_CEL_v1_r0p41_pub.pdf
+*/
+// top level types of a CEL record
+pub const CEL_SEQNUM: u8 = 0x0;
+pub const CEL_PCR: u8 = 0x1;
+pub const CEL_NV_INDEX: u8 = 0x2;
+pub const CEL_DIGESTS: u8 = 0x3;
+pub const CEL_MGT: u8 = 0x4;
+pub const CEL_PCCLIENT_STD: u8 = 0x5;
+pub const CEL_IMA_TEMPLATE: u8 = 0x7;
+pub const CEL_IMA_TLV: u8 = 0x8;
+
+// CEL_MGT content types
+pub const CEL_MGT_CEL_VERSION: u8 = 1;
+pub const CEL_MGT_CEL_VERSION_MAJOR: u8 = 0;
+pub const CEL_MGT_CEL_VERSION_MINOR: u8 = 1;
+pub const CEL_MGT_FIRMWARE_END: u8 = 2;
+pub const CEL_MGT_CEL_TIMESTAMP: u8 = 80;
+pub const CEL_MGT_STATE_TRANS: u8 = 81;
+pub const CEL_MGT_STATE_TRANS_SUSPEND: u32 = 0;
+pub const CEL_MGT_STATE_TRANS_HIBERNATE: u32 = 1;
+pub const CEL_MGT_STATE_TRANS_KEXEC: u32 = 2;
+
+// PCCLIENT_STD content types
+pub const PCCLIENT_STD_TYPE: u8 = 0;
+pub const PCCLIENT_STD_CONTENT: u8 = 1;
+
+// IMA_TEMPLATE content types
+pub const IMA_TEMPLATE_NAME: u8 = 0;
+pub const IMA_TEMPLATE_DATA: u8 = 1;
+
+// length of the type and length fields of a TLV
+const CEL_TLV_HEADER_LEN: usize = 5;
+
+// encodings of the canonical event log
+#[derive(Clone, Debug, PartialEq)]
+pub enum TcgCelEncoding {
+    TLV,
+    JSON,
+}
+
+// the register a CEL record is extended to
+#[derive(Clone, Debug, PartialEq)]
+pub enum TcgCelIndex {
+    Pcr(u32),
+    NvIndex(u32),
+}
+
+/***
+    Content of a CEL management event.
+    Definition reference: TCG_IWG_CEL, section 5.2 CEL_MGT event content
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum TcgCelMgt {
+    CelVersion { major: u32, minor: u32 },
+    FirmwareEnd,
+    CelTimestamp(u64),
+    StateTrans(u32),
+}
+
+// content of a CEL record, selected by the content type
+#[derive(Clone, Debug, PartialEq)]
+pub enum TcgCelContent {
+    PcClientStd {
+        event_type: u32,
+        event_data: Vec<u8>,
+    },
+    ImaTemplate {
+        template_name: String,
+        template_data: Vec<u8>,
+    },
+    CelMgt(TcgCelMgt),
+}
+
+impl TcgCelContent {
+    pub fn get_content_type(&self) -> u8 {
+        match self {
+            TcgCelContent::PcClientStd { .. } => CEL_PCCLIENT_STD,
+            TcgCelContent::ImaTemplate { .. } => CEL_IMA_TEMPLATE,
+            TcgCelContent::CelMgt(_) => CEL_MGT,
+        }
+    }
+
+    pub fn get_content_type_str(&self) -> String {
+        match self {
+            TcgCelContent::PcClientStd { .. } => "pcclient_std".to_string(),
+            TcgCelContent::ImaTemplate { .. } => "ima_template".to_string(),
+            TcgCelContent::CelMgt(_) => "cel".to_string(),
+        }
+    }
+}
+
+/***
+    TCG TPMS_CEL_EVENT defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_IWG_CEL_v1_r0p41_pub.pdf
+    Definition:
+    typedef struct tdTPMS_CEL_EVENT {
+        TPMI_CEL_RECNUM recnum;
+        TPMU_INDEX index;
+        TPML_DIGEST_VALUES digests;
+        TPMI_CEL_CONTENT_TYPE content_type;
+        TPMU_EVENT_CONTENT content;
+    } TPMS_CEL_EVENT;
+
+    Attributes:
+        rec_num: the record number of the event
+        index: the PCR or NV index the event is extended to
+        digests: digests of the event content
+        content: the event content
+*/
+#[derive(Clone)]
+pub struct TcgCanonicalEvent {
+    pub rec_num: u32,
+    pub index: TcgCelIndex,
+    pub digests: Vec<TcgDigest>,
+    pub content: TcgCelContent,
+}
+
+// CEL-JSON representation of a digest
+#[derive(Serialize, Deserialize)]
+struct CelJsonDigest {
+    #[serde(rename = "hashAlg")]
+    hash_alg: String,
+    digest: String,
+}
+
+// CEL-JSON representation of a record
+#[derive(Serialize, Deserialize)]
+struct CelJsonRecord {
+    recnum: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pcr: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nv_index: Option<u32>,
+    digests: Vec<CelJsonDigest>,
+    content_type: String,
+    content: Value,
+}
+
+impl TcgCanonicalEvent {
+    /***
+        Convert the event into TcgImrEvent, so that it can be replayed.
+
+        Returns:
+            TcgImrEvent for PCClient and IMA template events extended to a PCR,
+            None for CEL management events and events extended to an NV index
+    */
+    pub fn to_tcg_imr_event(&self) -> Option<TcgImrEvent> {
+        let imr_index = match self.index {
+            TcgCelIndex::Pcr(imr_index) => imr_index,
+            TcgCelIndex::NvIndex(_) => return None,
+        };
+        let (event_type, event, ima_template) = match &self.content {
+            TcgCelContent::PcClientStd {
+                event_type,
+                event_data,
+            } => (*event_type, event_data.clone(), None),
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => (
+                IMA_MEASUREMENT_EVENT,
+                template_data.clone(),
+                ImaTemplate::parse_binary(template_name, template_data)
+                    .ok()
+                    .map(Box::new),
+            ),
+            TcgCelContent::CelMgt(_) => return None,
+        };
+
+        Some(TcgImrEvent {
+            imr_index,
+            event_type,
+            digests: self.digests.clone(),
+            event_size: event.len() as u32,
+            event,
+            ima_template,
+        })
+    }
+
+    /***
+        Encode the event as CEL-TLV.
+        Each field is a TLV with 1 byte type, 4 bytes length in big endian and the value.
+        Integers are encoded in big endian.
+    */
+    pub fn to_tlv(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let index = match self.index {
+            TcgCelIndex::Pcr(index) => encode_tlv(CEL_PCR, &index.to_be_bytes()),
+            TcgCelIndex::NvIndex(index) => encode_tlv(CEL_NV_INDEX, &index.to_be_bytes()),
+        };
+
+        let mut digests = Vec::new();
+        for digest in &self.digests {
+            let algo_id: u8 = match digest.algo_id.try_into() {
+                Ok(algo_id) => algo_id,
+                Err(_) => {
+                    return Err(anyhow!(
+                        "[to_tlv] algorithm {:#x} cannot be encoded as TLV type",
+                        digest.algo_id
+                    ))
+                }
+            };
+            digests.extend(encode_tlv(algo_id, &digest.hash));
+        }
+
+        let content = match &self.content {
+            TcgCelContent::PcClientStd {
+                event_type,
+                event_data,
+            } => [
+                encode_tlv(PCCLIENT_STD_TYPE, &event_type.to_be_bytes()),
+                encode_tlv(PCCLIENT_STD_CONTENT, event_data),
+            ]
+            .concat(),
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => [
+                encode_tlv(IMA_TEMPLATE_NAME, template_name.as_bytes()),
+                encode_tlv(IMA_TEMPLATE_DATA, template_data),
+            ]
+            .concat(),
+            TcgCelContent::CelMgt(TcgCelMgt::CelVersion { major, minor }) => encode_tlv(
+                CEL_MGT_CEL_VERSION,
+                &[
+                    encode_tlv(CEL_MGT_CEL_VERSION_MAJOR, &major.to_be_bytes()),
+                    encode_tlv(CEL_MGT_CEL_VERSION_MINOR, &minor.to_be_bytes()),
+                ]
+                .concat(),
+            ),
+            TcgCelContent::CelMgt(TcgCelMgt::FirmwareEnd) => encode_tlv(CEL_MGT_FIRMWARE_END, &[]),
+            TcgCelContent::CelMgt(TcgCelMgt::CelTimestamp(timestamp)) => {
+                encode_tlv(CEL_MGT_CEL_TIMESTAMP, &timestamp.to_be_bytes())
+            }
+            TcgCelContent::CelMgt(TcgCelMgt::StateTrans(state)) => {
+                encode_tlv(CEL_MGT_STATE_TRANS, &state.to_be_bytes())
+            }
+        };
+
+        Ok([
+            encode_tlv(CEL_SEQNUM, &self.rec_num.to_be_bytes()),
+            index,
+            encode_tlv(CEL_DIGESTS, &digests),
+            encode_tlv(self.content.get_content_type(), &content),
+        ]
+        .concat())
+    }
+
+    /***
+        Encode the event as CEL-JSON record, e.g.
+        {"recnum":1,"pcr":0,"digests":[{"hashAlg":"sha256","digest":"..."}],
+         "content_type":"pcclient_std","content":{"event_type":1,"event_data":"<base64>"}}
+    */
+    pub fn to_json(&self) -> Result<Value, anyhow::Error> {
+        let mut digests = Vec::new();
+        for digest in &self.digests {
+            digests.push(CelJsonDigest {
+                hash_alg: get_cel_hash_alg_str(digest.algo_id)?,
+                digest: hex::encode(&digest.hash),
+            });
+        }
+
+        let content = match &self.content {
+            TcgCelContent::PcClientStd {
+                event_type,
+                event_data,
+            } => json!({
+                "event_type": event_type,
+                "event_data": base64::encode(event_data),
+            }),
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => json!({
+                "template_name": template_name,
+                "template_data": base64::encode(template_data),
+            }),
+            TcgCelContent::CelMgt(TcgCelMgt::CelVersion { major, minor }) => {
+                json!({"cel_version": {"major": major, "minor": minor}})
+            }
+            TcgCelContent::CelMgt(TcgCelMgt::FirmwareEnd) => json!({ "firmware_end": null }),
+            TcgCelContent::CelMgt(TcgCelMgt::CelTimestamp(timestamp)) => {
+                json!({ "cel_timestamp": timestamp })
+            }
+            TcgCelContent::CelMgt(TcgCelMgt::StateTrans(state)) => {
+                let state = match *state {
+                    CEL_MGT_STATE_TRANS_SUSPEND => "suspend",
+                    CEL_MGT_STATE_TRANS_HIBERNATE => "hibernate",
+                    CEL_MGT_STATE_TRANS_KEXEC => "kexec",
+                    _ => return Err(anyhow!("[to_json] invalid state transition: {}", state)),
+                };
+                json!({ "state_trans": state })
+            }
+        };
+
+        let (pcr, nv_index) = match self.index {
+            TcgCelIndex::Pcr(index) => (Some(index), None),
+            TcgCelIndex::NvIndex(index) => (None, Some(index)),
+        };
+        let record = CelJsonRecord {
+            recnum: self.rec_num,
+            pcr,
+            nv_index,
+            digests,
+            content_type: self.content.get_content_type_str(),
+            content,
+        };
+        match serde_json::to_value(record) {
+            Ok(value) => Ok(value),
+            Err(e) => Err(anyhow!("[to_json] encoding error: {:?}", e)),
+        }
+    }
+
+    // Parse one CEL-TLV record from the reader
+    fn parse_tlv_record(reader: &mut BinaryReader) -> Result<TcgCanonicalEvent, anyhow::Error> {
+        let rec_num = match read_tlv(reader)? {
+            (CEL_SEQNUM, value) => decode_uint(value)? as u32,
+            (t, _) => {
+                return Err(anyhow!(
+                    "[parse_tlv_record] expect record number, got type {}",
+                    t
+                ))
+            }
+        };
+        let index = match read_tlv(reader)? {
+            (CEL_PCR, value) => TcgCelIndex::Pcr(decode_uint(value)? as u32),
+            (CEL_NV_INDEX, value) => TcgCelIndex::NvIndex(decode_uint(value)? as u32),
+            (t, _) => {
+                return Err(anyhow!(
+                    "[parse_tlv_record] expect PCR or NV index, got type {}",
+                    t
+                ))
+            }
+        };
+
+        let digests_data = match read_tlv(reader)? {
+            (CEL_DIGESTS, value) => value,
+            (t, _) => return Err(anyhow!("[parse_tlv_record] expect digests, got type {}", t)),
+        };
+        let mut digests = Vec::new();
+        let mut digests_reader = BinaryReader::new(digests_data);
+        while !digests_reader.is_empty() {
+            let (algo_id, hash) = read_tlv(&mut digests_reader)?;
+            digests.push(TcgDigest {
+                algo_id: algo_id.into(),
+                hash: hash.to_vec(),
+            });
+        }
+
+        let (content_type, content_data) = read_tlv(reader)?;
+        let mut content_reader = BinaryReader::new(content_data);
+        let content = match content_type {
+            CEL_PCCLIENT_STD => {
+                let event_type = read_tlv_of(&mut content_reader, PCCLIENT_STD_TYPE)?;
+                let event_data = read_tlv_of(&mut content_reader, PCCLIENT_STD_CONTENT)?;
+                TcgCelContent::PcClientStd {
+                    event_type: decode_uint(event_type)? as u32,
+                    event_data: event_data.to_vec(),
+                }
+            }
+            CEL_IMA_TEMPLATE => {
+                let template_name = read_tlv_of(&mut content_reader, IMA_TEMPLATE_NAME)?;
+                let template_data = read_tlv_of(&mut content_reader, IMA_TEMPLATE_DATA)?;
+                TcgCelContent::ImaTemplate {
+                    template_name: String::from_utf8_lossy(template_name).to_string(),
+                    template_data: template_data.to_vec(),
+                }
+            }
+            CEL_MGT => {
+                let mgt = match read_tlv(&mut content_reader)? {
+                    (CEL_MGT_CEL_VERSION, value) => {
+                        let mut version_reader = BinaryReader::new(value);
+                        let major = read_tlv_of(&mut version_reader, CEL_MGT_CEL_VERSION_MAJOR)?;
+                        let minor = read_tlv_of(&mut version_reader, CEL_MGT_CEL_VERSION_MINOR)?;
+                        TcgCelMgt::CelVersion {
+                            major: decode_uint(major)? as u32,
+                            minor: decode_uint(minor)? as u32,
+                        }
+                    }
+                    (CEL_MGT_FIRMWARE_END, _) => TcgCelMgt::FirmwareEnd,
+                    (CEL_MGT_CEL_TIMESTAMP, value) => TcgCelMgt::CelTimestamp(decode_uint(value)?),
+                    (CEL_MGT_STATE_TRANS, value) => {
+                        TcgCelMgt::StateTrans(decode_uint(value)? as u32)
+                    }
+                    (t, _) => {
+                        return Err(anyhow!(
+                            "[parse_tlv_record] unknown CEL management type {}",
+                            t
+                        ))
+                    }
+                };
+                TcgCelContent::CelMgt(mgt)
+            }
+            _ => {
+                return Err(anyhow!(
+                    "[parse_tlv_record] unsupported content type {}",
+                    content_type
+                ))
+            }
+        };
+
+        Ok(TcgCanonicalEvent {
+            rec_num,
+            index,
+            digests,
+            content,
+        })
+    }
+
+    // Parse one CEL-JSON record
+    fn parse_json_record(record: CelJsonRecord) -> Result<TcgCanonicalEvent, anyhow::Error> {
+        let index = match (record.pcr, record.nv_index) {
+            (Some(pcr), None) => TcgCelIndex::Pcr(pcr),
+            (None, Some(nv_index)) => TcgCelIndex::NvIndex(nv_index),
+            _ => {
+                return Err(anyhow!(
+                    "[parse_json_record] record {} must have either pcr or nv_index",
+                    record.recnum
+                ))
+            }
+        };
+
+        let mut digests = Vec::new();
+        for digest in record.digests {
+            let hash = match hex::decode(&digest.digest) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[parse_json_record] invalid digest {}: {:?}",
+                        digest.digest,
+                        e
+                    ))
+                }
+            };
+            digests.push(TcgDigest {
+                algo_id: get_cel_hash_alg_id(&digest.hash_alg)?,
+                hash,
+            });
+        }
+
+        let content = &record.content;
+        let content = match record.content_type.as_str() {
+            "pcclient_std" => TcgCelContent::PcClientStd {
+                event_type: match content["event_type"].as_u64() {
+                    Some(event_type) => event_type as u32,
+                    None => return Err(anyhow!("[parse_json_record] invalid event_type")),
+                },
+                event_data: get_json_base64(content, "event_data")?,
+            },
+            "ima_template" => TcgCelContent::ImaTemplate {
+                template_name: match content["template_name"].as_str() {
+                    Some(template_name) => template_name.to_string(),
+                    None => return Err(anyhow!("[parse_json_record] invalid template_name")),
+                },
+                template_data: get_json_base64(content, "template_data")?,
+            },
+            "cel" => TcgCelContent::CelMgt(get_json_cel_mgt(content)?),
+            content_type => {
+                return Err(anyhow!(
+                    "[parse_json_record] unsupported content type {}",
+                    content_type
+                ))
+            }
+        };
+
+        Ok(TcgCanonicalEvent {
+            rec_num: record.recnum,
+            index,
+            digests,
+            content,
+        })
+    }
+
+    // Parse a canonical event log encoded in CEL-TLV, i.e. concatenated records
+    pub fn parse_cel_tlv(data: &[u8]) -> Result<Vec<TcgCanonicalEvent>, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let mut events = Vec::new();
+        while !reader.is_empty() {
+            events.push(TcgCanonicalEvent::parse_tlv_record(&mut reader)?);
+        }
+        Ok(events)
+    }
+
+    // Parse a canonical event log encoded in CEL-JSON, i.e. an array of records
+    pub fn parse_cel_json(data: &str) -> Result<Vec<TcgCanonicalEvent>, anyhow::Error> {
+        let records: Vec<CelJsonRecord> = match serde_json::from_str(data) {
+            Ok(records) => records,
+            Err(e) => return Err(anyhow!("[parse_cel_json] invalid CEL-JSON: {:?}", e)),
+        };
+        records
+            .into_iter()
+            .map(TcgCanonicalEvent::parse_json_record)
+            .collect()
+    }
+
+    // Encode the events as canonical event log in the given encoding
+    pub fn encode_cel(
+        events: &[TcgCanonicalEvent],
+        encoding: TcgCelEncoding,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        match encoding {
+            TcgCelEncoding::TLV => {
+                let mut data = Vec::new();
+                for event in events {
+                    data.extend(event.to_tlv()?);
+                }
+                Ok(data)
+            }
+            TcgCelEncoding::JSON => {
+                let records = events
+                    .iter()
+                    .map(|event| event.to_json())
+                    .collect::<Result<Vec<Value>, anyhow::Error>>()?;
+                match serde_json::to_vec(&records) {
+                    Ok(data) => Ok(data),
+                    Err(e) => Err(anyhow!("[encode_cel] encoding error: {:?}", e)),
+                }
+            }
+        }
+    }
+
+    // Parse a canonical event log in the given encoding
+    pub fn parse_cel(
+        data: &[u8],
+        encoding: TcgCelEncoding,
+    ) -> Result<Vec<TcgCanonicalEvent>, anyhow::Error> {
+        match encoding {
+            TcgCelEncoding::TLV => TcgCanonicalEvent::parse_cel_tlv(data),
+            TcgCelEncoding::JSON => match std::str::from_utf8(data) {
+                Ok(data) => TcgCanonicalEvent::parse_cel_json(data),
+                Err(e) => Err(anyhow!("[parse_cel] CEL-JSON is not UTF-8: {:?}", e)),
+            },
+        }
+    }
+
+    pub fn show(&self) {
+        info!(
+            "        -----------------------------Canonical Event Log Entry----------------------------"
+        );
+        info!("        Rec Num           : {}", self.rec_num);
+        match self.index {
+            TcgCelIndex::Pcr(index) => info!("        IMR               : {}", index),
+            TcgCelIndex::NvIndex(index) => info!("        NV Index          : {:#x}", index),
+        }
+        info!(
+            "        Content Type      : {} ({})",
+            self.content.get_content_type(),
+            self.content.get_content_type_str()
+        );
+        for (digest_index, digest) in self.digests.iter().enumerate() {
+            info!(
+                "        Algorithm_id[{}]   : {} {}",
+                digest_index,
+                digest.algo_id,
+                match ALGO_NAME_MAP.get(&digest.algo_id) {
+                    Some(name) => name.to_owned(),
+                    None => "UNKNOWN".to_string(),
+                }
+            );
+            info!("        Digest[{}]:", digest_index);
+            dump_data(&digest.hash);
+        }
+        info!("        Content:");
+        match &self.content {
+            TcgCelContent::PcClientStd {
+                event_type,
+                event_data,
+            } => {
+                info!(
+                    "        Type              : {:02X?} ({})",
+                    event_type,
+                    &TcgEventType::get_event_type_string(*event_type)
+                );
+                dump_data(event_data);
+            }
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => {
+                info!("        Template Name     : {}", template_name);
+                dump_data(template_data);
+            }
+            TcgCelContent::CelMgt(mgt) => info!("        {:?}", mgt),
+        }
+    }
+}
+
+fn encode_tlv(tlv_type: u8, value: &[u8]) -> Vec<u8> {
+    [&[tlv_type][..], &(value.len() as u32).to_be_bytes(), value].concat()
+}
+
+fn read_tlv<'a>(reader: &mut BinaryReader<'a>) -> Result<(u8, &'a [u8]), anyhow::Error> {
+    if reader.remaining() < CEL_TLV_HEADER_LEN {
+        return Err(anyhow!(
+            "[read_tlv] truncated TLV at offset {}",
+            reader.position()
+        ));
+    }
+    let tlv_type = reader.read_u8()?;
+    let length = reader.read_u32_be()? as usize;
+    Ok((tlv_type, reader.read_bytes(length)?))
+}
+
+// read a TLV of the expected type
+fn read_tlv_of<'a>(
+    reader: &mut BinaryReader<'a>,
+    expected_type: u8,
+) -> Result<&'a [u8], anyhow::Error> {
+    match read_tlv(reader)? {
+        (tlv_type, value) if tlv_type == expected_type => Ok(value),
+        (tlv_type, _) => Err(anyhow!(
+            "[read_tlv_of] expect TLV type {}, got {}",
+            expected_type,
+            tlv_type
+        )),
+    }
+}
+
+// decode a big endian unsigned integer of at most 8 bytes
+fn decode_uint(value: &[u8]) -> Result<u64, anyhow::Error> {
+    if value.len() > 8 {
+        return Err(anyhow!(
+            "[decode_uint] integer of {} bytes is too long",
+            value.len()
+        ));
+    }
+    Ok(value.iter().fold(0, |acc, b| (acc << 8) | *b as u64))
+}
+
+// hash algorithm names used by CEL-JSON
+fn get_cel_hash_alg_str(algo_id: u16) -> Result<String, anyhow::Error> {
+    match algo_id {
+        TPM_ALG_SHA1 => Ok("sha1".to_string()),
+        TPM_ALG_SHA256 => Ok("sha256".to_string()),
+        TPM_ALG_SHA384 => Ok("sha384".to_string()),
+        TPM_ALG_SHA512 => Ok("sha512".to_string()),
+        _ => Err(anyhow!(
+            "[get_cel_hash_alg_str] unsupported hash algorithm {:#x}",
+            algo_id
+        )),
+    }
+}
+
+fn get_cel_hash_alg_id(hash_alg: &str) -> Result<u16, anyhow::Error> {
+    match hash_alg {
+        "sha1" => Ok(TPM_ALG_SHA1),
+        "sha256" => Ok(TPM_ALG_SHA256),
+        "sha384" => Ok(TPM_ALG_SHA384),
+        "sha512" => Ok(TPM_ALG_SHA512),
+        _ => Err(anyhow!(
+            "[get_cel_hash_alg_id] unsupported hash algorithm {}",
+            hash_alg
+        )),
+    }
+}
+
+fn get_json_base64(content: &Value, key: &str) -> Result<Vec<u8>, anyhow::Error> {
+    match content[key].as_str().map(base64::decode) {
+        Some(Ok(data)) => Ok(data),
+        _ => Err(anyhow!("[get_json_base64] invalid base64 value of {}", key)),
+    }
+}
+
+fn get_json_cel_mgt(content: &Value) -> Result<TcgCelMgt, anyhow::Error> {
+    let content = match content.as_object() {
+        Some(content) if content.len() == 1 => content,
+        _ => {
+            return Err(anyhow!(
+                "[get_json_cel_mgt] CEL management content must have one item"
+            ))
+        }
+    };
+    let (mgt_type, value) = content.iter().next().unwrap();
+    match (mgt_type.as_str(), value) {
+        ("cel_version", version) => match (version["major"].as_u64(), version["minor"].as_u64()) {
+            (Some(major), Some(minor)) => Ok(TcgCelMgt::CelVersion {
+                major: major as u32,
+                minor: minor as u32,
+            }),
+            _ => Err(anyhow!("[get_json_cel_mgt] invalid cel_version")),
+        },
+        ("firmware_end", _) => Ok(TcgCelMgt::FirmwareEnd),
+        ("cel_timestamp", timestamp) => match timestamp.as_u64() {
+            Some(timestamp) => Ok(TcgCelMgt::CelTimestamp(timestamp)),
+            None => Err(anyhow!("[get_json_cel_mgt] invalid cel_timestamp")),
+        },
+        ("state_trans", state) => match state.as_str() {
+            Some("suspend") => Ok(TcgCelMgt::StateTrans(CEL_MGT_STATE_TRANS_SUSPEND)),
+            Some("hibernate") => Ok(TcgCelMgt::StateTrans(CEL_MGT_STATE_TRANS_HIBERNATE)),
+            Some("kexec") => Ok(TcgCelMgt::StateTrans(CEL_MGT_STATE_TRANS_KEXEC)),
+            _ => Err(anyhow!("[get_json_cel_mgt] invalid state_trans")),
+        },
+        (mgt_type, _) => Err(anyhow!(
+            "[get_json_cel_mgt] unknown CEL management type {}",
+            mgt_type
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test_tcg_cel {
+    use super::*;
+    use crate::eventlog::EventLogs;
+
+    fn get_events() -> Vec<TcgCanonicalEvent> {
+        vec![
+            TcgCanonicalEvent {
+                rec_num: 0,
+                index: TcgCelIndex::Pcr(0),
+                digests: Vec::new(),
+                content: TcgCelContent::CelMgt(TcgCelMgt::CelVersion { major: 1, minor: 0 }),
+            },
+            TcgCanonicalEvent {
+                rec_num: 1,
+                index: TcgCelIndex::Pcr(0),
+                digests: vec![
+                    TcgDigest {
+                        algo_id: TPM_ALG_SHA256,
+                        hash: vec![0x11; 32],
+                    },
+                    TcgDigest {
+                        algo_id: TPM_ALG_SHA384,
+                        hash: vec![0x12; 48],
+                    },
+                ],
+                content: TcgCelContent::PcClientStd {
+                    event_type: EV_POST_CODE,
+                    event_data: b"POST CODE".to_vec(),
+                },
+            },
+            TcgCanonicalEvent {
+                rec_num: 2,
+                index: TcgCelIndex::Pcr(10),
+                digests: vec![TcgDigest {
+                    algo_id: TPM_ALG_SHA1,
+                    hash: vec![0x13; 20],
+                }],
+                content: TcgCelContent::ImaTemplate {
+                    template_name: "ima-ng".to_string(),
+                    template_data: vec![0x01, 0x02, 0x03],
+                },
+            },
+            TcgCanonicalEvent {
+                rec_num: 3,
+                index: TcgCelIndex::NvIndex(0x1500000),
+                digests: vec![TcgDigest {
+                    algo_id: TPM_ALG_SHA256,
+                    hash: vec![0x14; 32],
+                }],
+                content: TcgCelContent::PcClientStd {
+                    event_type: EV_EVENT_TAG,
+                    event_data: Vec::new(),
+                },
+            },
+            TcgCanonicalEvent {
+                rec_num: 4,
+                index: TcgCelIndex::Pcr(0),
+                digests: Vec::new(),
+                content: TcgCelContent::CelMgt(TcgCelMgt::StateTrans(CEL_MGT_STATE_TRANS_KEXEC)),
+            },
+        ]
+    }
+
+    fn assert_events_eq(actual: &[TcgCanonicalEvent], expected: &[TcgCanonicalEvent]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_eq!(a.rec_num, e.rec_num);
+            assert_eq!(a.index, e.index);
+            assert_eq!(a.content, e.content);
+            assert_eq!(a.digests.len(), e.digests.len());
+            for (ad, ed) in a.digests.iter().zip(e.digests.iter()) {
+                assert_eq!(ad.algo_id, ed.algo_id);
+                assert_eq!(ad.hash, ed.hash);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cel_tlv() {
+        let events = get_events();
+        let data = TcgCanonicalEvent::encode_cel(&events, TcgCelEncoding::TLV).unwrap();
+        // recnum TLV of the first record
+        assert_eq!(data[..9], [CEL_SEQNUM, 0, 0, 0, 4, 0, 0, 0, 0]);
+        let parsed = TcgCanonicalEvent::parse_cel(&data, TcgCelEncoding::TLV).unwrap();
+        assert_events_eq(&parsed, &events);
+
+        // truncated record
+        assert!(TcgCanonicalEvent::parse_cel_tlv(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_cel_json() {
+        let events = get_events();
+        let data = TcgCanonicalEvent::encode_cel(&events, TcgCelEncoding::JSON).unwrap();
+        let parsed = TcgCanonicalEvent::parse_cel(&data, TcgCelEncoding::JSON).unwrap();
+        assert_events_eq(&parsed, &events);
+
+        let record = events[1].to_json().unwrap();
+        assert_eq!(record["pcr"], 0);
+        assert!(record.get("nv_index").is_none());
+        assert_eq!(record["content_type"], "pcclient_std");
+        assert_eq!(record["digests"][0]["hashAlg"], "sha256");
+        assert_eq!(
+            record["content"]["event_data"],
+            base64::encode(b"POST CODE")
+        );
+        assert_eq!(
+            events[4].to_json().unwrap()["content"]["state_trans"],
+            "kexec"
+        );
+    }
+
+    #[test]
+    fn test_parse_cel_json_sample() {
+        let data = r#"[
+            {"recnum":0,"pcr":0,"digests":[],"content_type":"cel",
+             "content":{"cel_timestamp":1700000000}},
+            {"recnum":1,"pcr":0,"digests":[],"content_type":"cel",
+             "content":{"firmware_end":null}},
+            {"recnum":2,"pcr":10,"digests":[{"hashAlg":"sha1","digest":"0102"}],
+             "content_type":"ima_template",
+             "content":{"template_name":"ima-ng","template_data":"AQID"}}
+        ]"#;
+        let events = TcgCanonicalEvent::parse_cel_json(data).unwrap();
+        assert_eq!(
+            events[0].content,
+            TcgCelContent::CelMgt(TcgCelMgt::CelTimestamp(1700000000))
+        );
+        assert_eq!(
+            events[1].content,
+            TcgCelContent::CelMgt(TcgCelMgt::FirmwareEnd)
+        );
+        assert_eq!(events[2].digests[0].algo_id, TPM_ALG_SHA1);
+        assert_eq!(events[2].digests[0].hash, vec![0x01, 0x02]);
+
+        // both pcr and nv_index
+        let data = r#"[{"recnum":0,"pcr":0,"nv_index":1,"digests":[],"content_type":"cel",
+                        "content":{"firmware_end":null}}]"#;
+        assert!(TcgCanonicalEvent::parse_cel_json(data).is_err());
+        // unknown hash algorithm
+        let data = r#"[{"recnum":0,"pcr":0,"digests":[{"hashAlg":"md5","digest":"00"}],
+                        "content_type":"cel","content":{"firmware_end":null}}]"#;
+        assert!(TcgCanonicalEvent::parse_cel_json(data).is_err());
+    }
+
+    #[test]
+    fn test_replay_cel() {
+        let entries: Vec<EventLogEntry> = get_events()
+            .into_iter()
+            .map(EventLogEntry::TcgCanonicalEvent)
+            .collect();
+        let imr_events: Vec<EventLogEntry> = get_events()
+            .iter()
+            .filter_map(|event| event.to_tcg_imr_event())
+            .map(EventLogEntry::TcgImrEvent)
+            .collect();
+        // management events and NV index events are not replayed
+        assert_eq!(imr_events.len(), 2);
+
+        let cel_results = EventLogs::replay(entries).unwrap();
+        let imr_results = EventLogs::replay(imr_events).unwrap();
+        assert_eq!(cel_results.len(), 2);
+        assert_eq!(cel_results.len(), imr_results.len());
+        for (c, i) in cel_results.iter().zip(imr_results.iter()) {
+            assert_eq!(c.imr_index, i.imr_index);
+            assert_eq!(c.digests[0].hash, i.digests[0].hash);
+        }
+    }
+
+    #[test]
+    fn test_select_canonical_format() {
+        let mut boot_time_data = Vec::new();
+        // TCG_PCClientPCREvent carrying TCG_EfiSpecIDEventStruct with SHA256 only
+        let mut spec_id_event = b"Spec ID Event03\0".to_vec();
+        spec_id_event.extend_from_slice(&0u32.to_le_bytes());
+        spec_id_event.extend_from_slice(&[0, 2, 0, 2]);
+        spec_id_event.extend_from_slice(&1u32.to_le_bytes());
+        spec_id_event.extend_from_slice(&TPM_ALG_SHA256.to_le_bytes());
+        spec_id_event.extend_from_slice(&32u16.to_le_bytes());
+        spec_id_event.push(0);
+        boot_time_data.extend_from_slice(&1u32.to_le_bytes());
+        boot_time_data.extend_from_slice(&EV_NO_ACTION.to_le_bytes());
+        boot_time_data.extend_from_slice(&[0; 20]);
+        boot_time_data.extend_from_slice(&(spec_id_event.len() as u32).to_le_bytes());
+        boot_time_data.extend_from_slice(&spec_id_event);
+        // TCG_PCR_EVENT2 extended to IMR 0
+        boot_time_data.extend_from_slice(&1u32.to_le_bytes());
+        boot_time_data.extend_from_slice(&EV_SEPARATOR.to_le_bytes());
+        boot_time_data.extend_from_slice(&1u32.to_le_bytes());
+        boot_time_data.extend_from_slice(&TPM_ALG_SHA256.to_le_bytes());
+        boot_time_data.extend_from_slice(&[0x21; 32]);
+        boot_time_data.extend_from_slice(&4u32.to_le_bytes());
+        boot_time_data.extend_from_slice(&[0; 4]);
+        let ima_event =
+            "10 1e762ca412a3ef388ddcab416e2eb382d9d1e356 ima-ng sha1:0102 /etc/lsb-release";
+
+        let mut pcclient = EventLogs::new(
+            boot_time_data.clone(),
+            vec![ima_event.to_string()],
+            TCG_PCCLIENT_FORMAT,
+        );
+        let mut canonical = EventLogs::new(
+            boot_time_data,
+            vec![ima_event.to_string()],
+            TCG_CANONICAL_FORMAT,
+        );
+        let pcclient_events = pcclient.select(None, None).unwrap();
+        let canonical_events = canonical.select(None, None).unwrap();
+        assert_eq!(canonical_events.len(), 3);
+
+        let cel_events: Vec<TcgCanonicalEvent> = canonical_events
+            .iter()
+            .map(|event| match event {
+                EventLogEntry::TcgCanonicalEvent(event) => event.clone(),
+                _ => panic!("not a canonical event"),
+            })
+            .collect();
+        // the specification ID event is not extended
+        assert!(cel_events[0].digests.is_empty());
+        assert_eq!(cel_events[1].index, TcgCelIndex::Pcr(0));
+        assert_eq!(cel_events[1].rec_num, 1);
+        // the ascii IMA event is carried as binary template data
+        match &cel_events[2].content {
+            TcgCelContent::ImaTemplate {
+                template_name,
+                template_data,
+            } => {
+                assert_eq!(template_name, "ima-ng");
+                assert_eq!(
+                    template_data,
+                    &[
+                        &8u32.to_le_bytes()[..],
+                        b"sha1:\0\x01\x02",
+                        &17u32.to_le_bytes(),
+                        b"/etc/lsb-release\0",
+                    ]
+                    .concat()
+                );
+            }
+            _ => panic!("not an IMA template event"),
+        }
+        let ima_template = cel_events[2].to_tcg_imr_event().unwrap().ima_template;
+        assert_eq!(ima_template.unwrap().file_name, "/etc/lsb-release");
+
+        // the log survives both encodings
+        for encoding in [TcgCelEncoding::TLV, TcgCelEncoding::JSON] {
+            let data = TcgCanonicalEvent::encode_cel(&cel_events, encoding.clone()).unwrap();
+            let parsed = TcgCanonicalEvent::parse_cel(&data, encoding).unwrap();
+            assert_events_eq(&parsed, &cel_events);
+        }
+
+        let pcclient_results = EventLogs::replay(pcclient_events).unwrap();
+        let canonical_results = EventLogs::replay(canonical_events).unwrap();
+        assert_eq!(pcclient_results.len(), canonical_results.len());
+        for (p, c) in pcclient_results.iter().zip(canonical_results.iter()) {
+            assert_eq!(p.imr_index, c.imr_index);
+            assert_eq!(p.digests[0].hash, c.digests[0].hash);
+        }
+    }
+}
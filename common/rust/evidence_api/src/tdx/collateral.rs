@@ -0,0 +1,1017 @@
+use crate::tdx::pck::*;
+use crate::tdx::quote::*;
+use crate::x509::*;
+use anyhow::anyhow;
+use log::info;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::fs;
+use std::str::FromStr;
+use std::time::SystemTime;
+use x509_cert::der::DateTime;
+
+pub const TCB_INFO_ID_TDX: &str = "TDX";
+pub const QE_IDENTITY_ID_TD_QE: &str = "TD_QE";
+
+/***
+    TCB status defined by Intel PCS collateral.
+    Definition reference:
+    https://api.portal.trustedservices.intel.com/content/documentation.html
+    Intel® Trusted Services API Management Developer Portal, TCB Info V3
+*/
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub enum TcbStatus {
+    UpToDate,
+    SWHardeningNeeded,
+    ConfigurationNeeded,
+    ConfigurationAndSWHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+    // no TCB level in the collateral matches the platform
+    #[serde(skip)]
+    NotSupported,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TcbComponent {
+    pub svn: u8,
+    pub category: Option<String>,
+    #[serde(rename = "type")]
+    pub comp_type: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Tcb {
+    pub sgxtcbcomponents: Vec<TcbComponent>,
+    pub pcesvn: u16,
+    #[serde(default)]
+    pub tdxtcbcomponents: Vec<TcbComponent>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbLevel {
+    pub tcb: Tcb,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(rename = "advisoryIDs", default)]
+    pub advisory_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModule {
+    pub mrsigner: String,
+    pub attributes: String,
+    pub attributes_mask: String,
+}
+
+// TCB level identified by ISV SVN, used by TDX module identities and QE identity
+#[derive(Clone, Debug, Deserialize)]
+pub struct IsvTcb {
+    pub isvsvn: u16,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsvTcbLevel {
+    pub tcb: IsvTcb,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(rename = "advisoryIDs", default)]
+    pub advisory_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModuleIdentity {
+    pub id: String,
+    pub mrsigner: String,
+    pub attributes: String,
+    pub attributes_mask: String,
+    pub tcb_levels: Vec<IsvTcbLevel>,
+}
+
+/***
+    TCB Info collateral body, i.e. the "tcbInfo" object of the document served by
+    Intel PCS at /tdx/certification/v4/tcb.
+*/
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbInfoBody {
+    pub id: String,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub fmspc: String,
+    pub pce_id: String,
+    pub tcb_type: u32,
+    pub tcb_evaluation_data_number: u32,
+    pub tdx_module: Option<TdxModule>,
+    pub tdx_module_identities: Option<Vec<TdxModuleIdentity>>,
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedCollateral<'a> {
+    #[serde(borrow)]
+    tcb_info: Option<&'a RawValue>,
+    #[serde(borrow)]
+    enclave_identity: Option<&'a RawValue>,
+    signature: String,
+}
+
+/***
+    Signed collateral document: the raw JSON body and its signature.
+    The signature is an ECDSA P-256 signature (r || s) over the exact bytes of the body.
+*/
+#[derive(Clone)]
+pub struct SignedCollateralBody {
+    pub raw_body: String,
+    pub signature: Vec<u8>,
+}
+
+impl SignedCollateralBody {
+    /***
+        Parse a signed collateral JSON document.
+
+        Args:
+            json: the collateral document
+            body_name: name of the signed body, "tcbInfo" or "enclaveIdentity"
+    */
+    pub fn new(json: &str, body_name: &str) -> Result<SignedCollateralBody, anyhow::Error> {
+        let collateral: SignedCollateral = match serde_json::from_str(json) {
+            Ok(collateral) => collateral,
+            Err(e) => return Err(anyhow!("[SignedCollateralBody] invalid JSON: {:?}", e)),
+        };
+        let raw_body = match body_name {
+            "tcbInfo" => collateral.tcb_info,
+            "enclaveIdentity" => collateral.enclave_identity,
+            _ => None,
+        };
+        let raw_body = match raw_body {
+            Some(raw_body) => raw_body.get().to_string(),
+            None => return Err(anyhow!("[SignedCollateralBody] {} not found", body_name)),
+        };
+        let signature = match hex::decode(&collateral.signature) {
+            Ok(signature) => signature,
+            Err(e) => return Err(anyhow!("[SignedCollateralBody] invalid signature: {:?}", e)),
+        };
+
+        Ok(SignedCollateralBody {
+            raw_body,
+            signature,
+        })
+    }
+
+    /***
+        Verify the collateral signature with the signing certificate chain.
+
+        Args:
+            issuer_chain_file: PEM file of the signing certificate chain, e.g. the content of
+                               the TCB-Info-Issuer-Chain header, leaf certificate first
+            root_ca_file: path of the trusted Intel SGX Root CA certificate in PEM or DER
+            verify_time: time at which the chain must be valid
+    */
+    pub fn verify(
+        &self,
+        issuer_chain_file: &str,
+        root_ca_file: &str,
+        verify_time: SystemTime,
+    ) -> Result<(), anyhow::Error> {
+        let chain = load_certificate_file(issuer_chain_file)?;
+        let trust_anchor = match load_certificate_file(root_ca_file)?.into_iter().next() {
+            Some(cert) => cert,
+            None => return Err(anyhow!("[verify] no certificate in {}", root_ca_file)),
+        };
+        verify_cert_chain(&chain, &trust_anchor, &[], verify_time)?;
+
+        let key = match VerifyingKey::from_sec1_bytes(
+            chain[0]
+                .tbs_certificate
+                .subject_public_key_info
+                .subject_public_key
+                .raw_bytes(),
+        ) {
+            Ok(key) => key,
+            Err(e) => return Err(anyhow!("[verify] invalid signing key: {:?}", e)),
+        };
+        verify_collateral_signature(&key, self.raw_body.as_bytes(), &self.signature)
+    }
+}
+
+pub(crate) fn verify_collateral_signature(
+    key: &VerifyingKey,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), anyhow::Error> {
+    let signature = match Signature::from_slice(signature) {
+        Ok(signature) => signature,
+        Err(e) => {
+            return Err(anyhow!(
+                "[verify_collateral_signature] invalid signature: {:?}",
+                e
+            ))
+        }
+    };
+    match key.verify(data, &signature) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(anyhow!(
+            "[verify_collateral_signature] signature verification failed"
+        )),
+    }
+}
+
+pub(crate) fn parse_date_time(date_time: &str) -> Result<SystemTime, anyhow::Error> {
+    match DateTime::from_str(date_time) {
+        Ok(date_time) => Ok(date_time.to_system_time()),
+        Err(e) => Err(anyhow!(
+            "[parse_date_time] invalid date time {}: {:?}",
+            date_time,
+            e
+        )),
+    }
+}
+
+/***
+    TCB Info collateral.
+
+    Attributes:
+        tcb_info: the parsed TCB Info body.
+        signed_body: the raw TCB Info body and its signature.
+*/
+#[derive(Clone)]
+pub struct TcbInfo {
+    pub tcb_info: TcbInfoBody,
+    pub signed_body: SignedCollateralBody,
+}
+
+impl TcbInfo {
+    // Parse a TCB Info JSON document without verifying it
+    pub fn new(json: &str) -> Result<TcbInfo, anyhow::Error> {
+        let signed_body = SignedCollateralBody::new(json, "tcbInfo")?;
+        let tcb_info: TcbInfoBody = match serde_json::from_str(&signed_body.raw_body) {
+            Ok(tcb_info) => tcb_info,
+            Err(e) => return Err(anyhow!("[TcbInfo] invalid tcbInfo: {:?}", e)),
+        };
+        Ok(TcbInfo {
+            tcb_info,
+            signed_body,
+        })
+    }
+
+    /***
+        Load TCB Info from a JSON file and verify it.
+
+        Args:
+            path: path of the TCB Info JSON document
+            issuer_chain_file: PEM file of the TCB signing certificate chain
+            root_ca_file: path of the trusted Intel SGX Root CA certificate in PEM or DER
+            verify_time: time at which the collateral must be valid, default is current time
+
+        Returns:
+            The verified TcbInfo, or error if signature, certificate chain or validity
+            period check fails
+    */
+    pub fn from_file(
+        path: &str,
+        issuer_chain_file: &str,
+        root_ca_file: &str,
+        verify_time: Option<SystemTime>,
+    ) -> Result<TcbInfo, anyhow::Error> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) => return Err(anyhow!("[from_file] error reading {}: {:?}", path, e)),
+        };
+        let tcb_info = TcbInfo::new(&json)?;
+        tcb_info.verify(
+            issuer_chain_file,
+            root_ca_file,
+            verify_time.unwrap_or_else(SystemTime::now),
+        )?;
+        Ok(tcb_info)
+    }
+
+    // Verify the signature and validity period of the TCB Info
+    pub fn verify(
+        &self,
+        issuer_chain_file: &str,
+        root_ca_file: &str,
+        verify_time: SystemTime,
+    ) -> Result<(), anyhow::Error> {
+        self.signed_body
+            .verify(issuer_chain_file, root_ca_file, verify_time)?;
+        if verify_time < parse_date_time(&self.tcb_info.issue_date)?
+            || verify_time > parse_date_time(&self.tcb_info.next_update)?
+        {
+            return Err(anyhow!(
+                "[verify] TCB Info is not valid at verification time, valid from {} to {}",
+                self.tcb_info.issue_date,
+                self.tcb_info.next_update
+            ));
+        }
+        Ok(())
+    }
+}
+
+/***
+    QE Identity collateral body, i.e. the "enclaveIdentity" object of the document served
+    by Intel PCS at /tdx/certification/v4/qe/identity.
+*/
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QeIdentityBody {
+    pub id: String,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub tcb_evaluation_data_number: u32,
+    pub miscselect: String,
+    pub miscselect_mask: String,
+    pub attributes: String,
+    pub attributes_mask: String,
+    pub mrsigner: String,
+    pub isvprodid: u16,
+    pub tcb_levels: Vec<IsvTcbLevel>,
+}
+
+/***
+    QE Identity collateral.
+
+    Attributes:
+        enclave_identity: the parsed QE Identity body.
+        signed_body: the raw QE Identity body and its signature.
+*/
+#[derive(Clone)]
+pub struct QeIdentity {
+    pub enclave_identity: QeIdentityBody,
+    pub signed_body: SignedCollateralBody,
+}
+
+impl QeIdentity {
+    // Parse a QE Identity JSON document without verifying it
+    pub fn new(json: &str) -> Result<QeIdentity, anyhow::Error> {
+        let signed_body = SignedCollateralBody::new(json, "enclaveIdentity")?;
+        let enclave_identity: QeIdentityBody = match serde_json::from_str(&signed_body.raw_body) {
+            Ok(enclave_identity) => enclave_identity,
+            Err(e) => return Err(anyhow!("[QeIdentity] invalid enclaveIdentity: {:?}", e)),
+        };
+        Ok(QeIdentity {
+            enclave_identity,
+            signed_body,
+        })
+    }
+
+    /***
+        Load QE Identity from a JSON file and verify it.
+
+        Args:
+            path: path of the QE Identity JSON document
+            issuer_chain_file: PEM file of the signing certificate chain, i.e. the content
+                               of the SGX-Enclave-Identity-Issuer-Chain header
+            root_ca_file: path of the trusted Intel SGX Root CA certificate in PEM or DER
+            verify_time: time at which the collateral must be valid, default is current time
+
+        Returns:
+            The verified QeIdentity, or error if signature, certificate chain or validity
+            period check fails
+    */
+    pub fn from_file(
+        path: &str,
+        issuer_chain_file: &str,
+        root_ca_file: &str,
+        verify_time: Option<SystemTime>,
+    ) -> Result<QeIdentity, anyhow::Error> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) => return Err(anyhow!("[from_file] error reading {}: {:?}", path, e)),
+        };
+        let qe_identity = QeIdentity::new(&json)?;
+        qe_identity.verify(
+            issuer_chain_file,
+            root_ca_file,
+            verify_time.unwrap_or_else(SystemTime::now),
+        )?;
+        Ok(qe_identity)
+    }
+
+    // Verify the signature and validity period of the QE Identity
+    pub fn verify(
+        &self,
+        issuer_chain_file: &str,
+        root_ca_file: &str,
+        verify_time: SystemTime,
+    ) -> Result<(), anyhow::Error> {
+        self.signed_body
+            .verify(issuer_chain_file, root_ca_file, verify_time)?;
+        if verify_time < parse_date_time(&self.enclave_identity.issue_date)?
+            || verify_time > parse_date_time(&self.enclave_identity.next_update)?
+        {
+            return Err(anyhow!(
+                "[verify] QE Identity is not valid at verification time, valid from {} to {}",
+                self.enclave_identity.issue_date,
+                self.enclave_identity.next_update
+            ));
+        }
+        Ok(())
+    }
+}
+
+/***
+    Result of the QE Identity evaluation.
+
+    Attributes:
+        tcb_status: TCB status of the matching QE TCB level.
+        tcb_date: date of the matching QE TCB level.
+        advisory_ids: advisories of the matching QE TCB level.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct QeIdentityResult {
+    pub tcb_status: TcbStatus,
+    pub tcb_date: Option<String>,
+    pub advisory_ids: Vec<String>,
+}
+
+impl QeIdentityResult {
+    pub fn show(&self) {
+        info!("show the data of QeIdentityResult");
+        info!("tcb_status = {:?}", self.tcb_status);
+        info!("tcb_date = {:?}", self.tcb_date);
+        info!("advisory_ids = {:?}", self.advisory_ids);
+    }
+}
+
+impl TdxEnclaveReportBody {
+    /***
+        Evaluate the QE report against QE Identity collateral of the TD QE.
+
+        MISCSELECT and ATTRIBUTES are compared after applying the masks of the collateral,
+        MRSIGNER and ISVPRODID must be equal. Then the first TCB level with ISVSVN lower
+        or equal to the report ISVSVN gives the TCB status.
+
+        Returns:
+            QeIdentityResult with the resolved TCB status, or error if the report does not
+            match the identity
+    */
+    pub fn evaluate_qe_identity(
+        &self,
+        qe_identity: &QeIdentity,
+    ) -> Result<QeIdentityResult, anyhow::Error> {
+        let identity = &qe_identity.enclave_identity;
+        if identity.id != QE_IDENTITY_ID_TD_QE {
+            return Err(anyhow!(
+                "[evaluate_qe_identity] QE Identity id {} is not TD_QE",
+                identity.id
+            ));
+        }
+        let decode = |name: &str, value: &str| match hex::decode(value) {
+            Ok(value) => Ok(value),
+            Err(e) => Err(anyhow!(
+                "[evaluate_qe_identity] invalid {} {}: {:?}",
+                name,
+                value,
+                e
+            )),
+        };
+
+        let miscselect = decode("miscselect", &identity.miscselect)?;
+        let miscselect_mask = decode("miscselectMask", &identity.miscselect_mask)?;
+        if !masked_equal(&self.miscselect, &miscselect_mask, &miscselect) {
+            return Err(anyhow!("[evaluate_qe_identity] MISCSELECT mismatch"));
+        }
+        let attributes = decode("attributes", &identity.attributes)?;
+        let attributes_mask = decode("attributesMask", &identity.attributes_mask)?;
+        if !masked_equal(&self.attributes, &attributes_mask, &attributes) {
+            return Err(anyhow!("[evaluate_qe_identity] ATTRIBUTES mismatch"));
+        }
+        if decode("mrsigner", &identity.mrsigner)? != self.mrsigner {
+            return Err(anyhow!("[evaluate_qe_identity] MRSIGNER mismatch"));
+        }
+        if self.isv_prodid as u16 != identity.isvprodid {
+            return Err(anyhow!(
+                "[evaluate_qe_identity] ISVPRODID mismatch, report {} vs identity {}",
+                self.isv_prodid,
+                identity.isvprodid
+            ));
+        }
+
+        let isv_svn = self.isv_svn as u16;
+        match identity
+            .tcb_levels
+            .iter()
+            .find(|level| level.tcb.isvsvn <= isv_svn)
+        {
+            Some(level) => Ok(QeIdentityResult {
+                tcb_status: level.tcb_status.clone(),
+                tcb_date: Some(level.tcb_date.clone()),
+                advisory_ids: level.advisory_ids.clone(),
+            }),
+            None => Ok(QeIdentityResult {
+                tcb_status: TcbStatus::NotSupported,
+                tcb_date: None,
+                advisory_ids: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl TdxQuote {
+    // Evaluate the QE report carried in the quote against QE Identity collateral
+    pub fn evaluate_qe_identity(
+        &self,
+        qe_identity: &QeIdentity,
+    ) -> Result<QeIdentityResult, anyhow::Error> {
+        let qe_report_cert = match &self.tdx_quote_ecdsa256_sigature {
+            Some(signature) => &signature.qe_cert.cert_data_struct,
+            None => {
+                return Err(anyhow!(
+                    "[evaluate_qe_identity] only ECDSA-256-with-P-256 quote signature is supported"
+                ))
+            }
+        };
+        match qe_report_cert {
+            Some(qe_report_cert) => qe_report_cert.qe_report.evaluate_qe_identity(qe_identity),
+            None => Err(anyhow!(
+                "[evaluate_qe_identity] QE report certification data not found in quote"
+            )),
+        }
+    }
+}
+
+// compare value & mask with expected, all slices must have the same length
+fn masked_equal(value: &[u8], mask: &[u8], expected: &[u8]) -> bool {
+    value.len() == mask.len()
+        && value.len() == expected.len()
+        && (0..value.len()).all(|i| value[i] & mask[i] == expected[i])
+}
+
+/***
+    Result of the TCB status evaluation.
+
+    Attributes:
+        tcb_status: the final TCB status, converged from platform and TDX module status.
+        platform_tcb_status: TCB status of the matching platform TCB level.
+        tdx_module_tcb_status: TCB status of the matching TDX module identity TCB level,
+                               only when TDX module identities are evaluated.
+        tcb_date: date of the matching platform TCB level.
+        advisory_ids: advisories of the matching TCB levels.
+        tcb_evaluation_data_number: TCB evaluation data number of the collateral.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TcbEvaluationResult {
+    pub tcb_status: TcbStatus,
+    pub platform_tcb_status: TcbStatus,
+    pub tdx_module_tcb_status: Option<TcbStatus>,
+    pub tcb_date: Option<String>,
+    pub advisory_ids: Vec<String>,
+    pub tcb_evaluation_data_number: u32,
+}
+
+impl TcbEvaluationResult {
+    pub fn show(&self) {
+        info!("show the data of TcbEvaluationResult");
+        info!("tcb_status = {:?}", self.tcb_status);
+        info!("platform_tcb_status = {:?}", self.platform_tcb_status);
+        info!("tdx_module_tcb_status = {:?}", self.tdx_module_tcb_status);
+        info!("tcb_date = {:?}", self.tcb_date);
+        info!("advisory_ids = {:?}", self.advisory_ids);
+        info!(
+            "tcb_evaluation_data_number = {}",
+            self.tcb_evaluation_data_number
+        );
+    }
+}
+
+impl TdxQuote {
+    /***
+        Evaluate the TCB status of the quote against TCB Info collateral.
+
+        The PCK extensions are taken from the PCK certificate chain in the quote. The
+        caller is expected to verify the TCB Info, e.g. by loading it with
+        ``TcbInfo::from_file``, and the quote signatures and PCK chain beforehand.
+
+        Algorithm:
+        1. FMSPC and PCE-ID of the PCK certificate must match the TCB Info.
+        2. MRSIGNERSEAM and SEAMATTRIBUTES must match the TDX module of the TCB Info.
+        3. The first TCB level with all SGX TCB components and PCESVN lower or equal to
+           the PCK certificate values, and all TDX TCB components lower or equal to
+           TEE_TCB_SVN gives the platform TCB status. When TEE_TCB_SVN[1] is not zero,
+           the first two TDX TCB components are evaluated with the TDX module identity
+           "TDX_<TEE_TCB_SVN[1]>" against TEE_TCB_SVN[0] instead.
+        4. The platform and TDX module TCB status are converged into the final status.
+    */
+    pub fn evaluate_tcb_status(
+        &self,
+        tcb_info: &TcbInfo,
+    ) -> Result<TcbEvaluationResult, anyhow::Error> {
+        let pck_extensions = self.get_pck_cert_chain()?.get_pck_extensions()?;
+        evaluate_tcb_status(&self.body, &pck_extensions, &tcb_info.tcb_info)
+    }
+}
+
+pub fn evaluate_tcb_status(
+    body: &TdxQuoteBody,
+    pck_extensions: &PckExtensions,
+    tcb_info: &TcbInfoBody,
+) -> Result<TcbEvaluationResult, anyhow::Error> {
+    if tcb_info.id != TCB_INFO_ID_TDX {
+        return Err(anyhow!(
+            "[evaluate_tcb_status] TCB Info id {} is not TDX",
+            tcb_info.id
+        ));
+    }
+    if !tcb_info
+        .fmspc
+        .eq_ignore_ascii_case(&hex::encode(pck_extensions.fmspc))
+    {
+        return Err(anyhow!(
+            "[evaluate_tcb_status] FMSPC mismatch, TCB Info {} vs PCK {}",
+            tcb_info.fmspc,
+            hex::encode(pck_extensions.fmspc)
+        ));
+    }
+    if !tcb_info
+        .pce_id
+        .eq_ignore_ascii_case(&hex::encode(pck_extensions.pce_id))
+    {
+        return Err(anyhow!(
+            "[evaluate_tcb_status] PCE-ID mismatch, TCB Info {} vs PCK {}",
+            tcb_info.pce_id,
+            hex::encode(pck_extensions.pce_id)
+        ));
+    }
+
+    if let Some(tdx_module) = &tcb_info.tdx_module {
+        check_tdx_module(
+            body,
+            &tdx_module.mrsigner,
+            &tdx_module.attributes,
+            &tdx_module.attributes_mask,
+        )?;
+    }
+
+    let tee_tcb_svn = body.tee_tcb_svn;
+    let mut advisory_ids = Vec::new();
+    let mut tdx_module_tcb_status = None;
+    let tdx_component_start = if tee_tcb_svn[1] > 0 {
+        let level = evaluate_tdx_module_identity(body, tcb_info)?;
+        match level {
+            Some(level) => {
+                tdx_module_tcb_status = Some(level.tcb_status.clone());
+                advisory_ids.extend(level.advisory_ids.iter().cloned());
+            }
+            None => tdx_module_tcb_status = Some(TcbStatus::NotSupported),
+        }
+        2
+    } else {
+        0
+    };
+
+    let platform_level = tcb_info.tcb_levels.iter().find(|level| {
+        level.tcb.sgxtcbcomponents.len() == 16
+            && level.tcb.tdxtcbcomponents.len() == 16
+            && (0..16)
+                .all(|i| level.tcb.sgxtcbcomponents[i].svn <= pck_extensions.cpu_svn_components[i])
+            && level.tcb.pcesvn <= pck_extensions.pce_svn
+            && (tdx_component_start..16)
+                .all(|i| level.tcb.tdxtcbcomponents[i].svn <= tee_tcb_svn[i])
+    });
+
+    let (platform_tcb_status, tcb_date) = match platform_level {
+        Some(level) => {
+            advisory_ids.extend(level.advisory_ids.iter().cloned());
+            (level.tcb_status.clone(), Some(level.tcb_date.clone()))
+        }
+        None => (TcbStatus::NotSupported, None),
+    };
+    advisory_ids.sort();
+    advisory_ids.dedup();
+
+    Ok(TcbEvaluationResult {
+        tcb_status: converge_tcb_status(&platform_tcb_status, &tdx_module_tcb_status),
+        platform_tcb_status,
+        tdx_module_tcb_status,
+        tcb_date,
+        advisory_ids,
+        tcb_evaluation_data_number: tcb_info.tcb_evaluation_data_number,
+    })
+}
+
+// check MRSIGNERSEAM and SEAMATTRIBUTES against the TDX module in collateral
+fn check_tdx_module(
+    body: &TdxQuoteBody,
+    mrsigner: &str,
+    attributes: &str,
+    attributes_mask: &str,
+) -> Result<(), anyhow::Error> {
+    let decode = |value: &str| match hex::decode(value) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(anyhow!("[check_tdx_module] invalid hex {}: {:?}", value, e)),
+    };
+    if decode(mrsigner)? != body.mrseam_signer {
+        return Err(anyhow!(
+            "[check_tdx_module] MRSIGNERSEAM does not match TDX module mrsigner"
+        ));
+    }
+    if !masked_equal(
+        &body.seam_attributes,
+        &decode(attributes_mask)?,
+        &decode(attributes)?,
+    ) {
+        return Err(anyhow!(
+            "[check_tdx_module] SEAMATTRIBUTES do not match TDX module attributes"
+        ));
+    }
+    Ok(())
+}
+
+fn evaluate_tdx_module_identity<'a>(
+    body: &TdxQuoteBody,
+    tcb_info: &'a TcbInfoBody,
+) -> Result<Option<&'a IsvTcbLevel>, anyhow::Error> {
+    let id = format!("TDX_{:02X}", body.tee_tcb_svn[1]);
+    let identity = match &tcb_info.tdx_module_identities {
+        Some(identities) => identities.iter().find(|identity| identity.id == id),
+        None => None,
+    };
+    let identity = match identity {
+        Some(identity) => identity,
+        None => {
+            return Err(anyhow!(
+                "[evaluate_tdx_module_identity] TDX module identity {} not found",
+                id
+            ))
+        }
+    };
+    check_tdx_module(
+        body,
+        &identity.mrsigner,
+        &identity.attributes,
+        &identity.attributes_mask,
+    )?;
+
+    Ok(identity
+        .tcb_levels
+        .iter()
+        .find(|level| level.tcb.isvsvn <= body.tee_tcb_svn[0] as u16))
+}
+
+// converge platform TCB status with TDX module TCB status
+fn converge_tcb_status(platform: &TcbStatus, tdx_module: &Option<TcbStatus>) -> TcbStatus {
+    match tdx_module {
+        Some(TcbStatus::OutOfDate) => match platform {
+            TcbStatus::UpToDate | TcbStatus::SWHardeningNeeded => TcbStatus::OutOfDate,
+            TcbStatus::ConfigurationNeeded | TcbStatus::ConfigurationAndSWHardeningNeeded => {
+                TcbStatus::OutOfDateConfigurationNeeded
+            }
+            _ => platform.clone(),
+        },
+        Some(TcbStatus::Revoked) => TcbStatus::Revoked,
+        Some(TcbStatus::NotSupported) => TcbStatus::NotSupported,
+        _ => platform.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test_evaluate_tcb_status {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use p256::pkcs8::DecodePrivateKey;
+
+    const QUOTE_V4: &[u8] = include_bytes!("../../test_data/quote.bin");
+
+    fn components(svns: &[u8]) -> String {
+        let components: Vec<String> = svns
+            .iter()
+            .map(|svn| format!("{{\"svn\":{}}}", svn))
+            .collect();
+        format!("[{}]", components.join(","))
+    }
+
+    // build a TCB Info document matching the test quote, tdx_svn_2 is the 3rd TDX component
+    fn build_tcb_info(quote: &TdxQuote, fmspc: &str, tdx_svn_2: u8, module_status: &str) -> String {
+        let pck = quote
+            .get_pck_cert_chain()
+            .unwrap()
+            .get_pck_extensions()
+            .unwrap();
+        let mut tdx_svns = quote.body.tee_tcb_svn;
+        tdx_svns[2] = tdx_svn_2;
+        format!(
+            r#"{{"tcbInfo":{{"id":"TDX","version":3,"issueDate":"2024-01-01T00:00:00Z","nextUpdate":"2024-02-01T00:00:00Z","fmspc":"{}","pceId":"{}","tcbType":0,"tcbEvaluationDataNumber":16,
+            "tdxModule":{{"mrsigner":"{}","attributes":"0000000000000000","attributesMask":"FFFFFFFFFFFFFFFF"}},
+            "tdxModuleIdentities":[{{"id":"TDX_{:02X}","mrsigner":"{}","attributes":"0000000000000000","attributesMask":"FFFFFFFFFFFFFFFF",
+                "tcbLevels":[{{"tcb":{{"isvsvn":{}}},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"{}","advisoryIDs":["INTEL-SA-00001"]}}]}}],
+            "tcbLevels":[
+                {{"tcb":{{"sgxtcbcomponents":{},"pcesvn":{},"tdxtcbcomponents":{}}},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"UpToDate"}},
+                {{"tcb":{{"sgxtcbcomponents":{},"pcesvn":0,"tdxtcbcomponents":{}}},"tcbDate":"2018-01-04T00:00:00Z","tcbStatus":"OutOfDate","advisoryIDs":["INTEL-SA-00002","INTEL-SA-00003"]}}]
+            }},"signature":"00"}}"#,
+            fmspc,
+            hex::encode(pck.pce_id),
+            hex::encode(quote.body.mrseam_signer),
+            quote.body.tee_tcb_svn[1],
+            hex::encode(quote.body.mrseam_signer),
+            quote.body.tee_tcb_svn[0],
+            module_status,
+            components(&pck.cpu_svn_components),
+            pck.pce_svn,
+            components(&tdx_svns),
+            components(&[0; 16]),
+            components(&[0; 16]),
+        )
+    }
+
+    // sign the TCB Info document with the test TCB signing key in test_data/tdx
+    fn sign_tcb_info(json: &str) -> String {
+        let body = SignedCollateralBody::new(json, "tcbInfo").unwrap();
+        let signing_key =
+            SigningKey::from_pkcs8_pem(include_str!("../../test_data/tdx/tcb_signing_key.pem"))
+                .unwrap();
+        let signature: Signature = signing_key.sign(body.raw_body.as_bytes());
+        json.replace(
+            r#""signature":"00""#,
+            &format!(r#""signature":"{}""#, hex::encode(signature.to_bytes())),
+        )
+    }
+
+    fn get_fmspc(quote: &TdxQuote) -> String {
+        let pck = quote
+            .get_pck_cert_chain()
+            .unwrap()
+            .get_pck_extensions()
+            .unwrap();
+        hex::encode_upper(pck.fmspc)
+    }
+
+    #[test]
+    fn test_evaluate_tcb_status_up_to_date() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let json = build_tcb_info(&quote, &get_fmspc(&quote), 0, "UpToDate");
+        let tcb_info = TcbInfo::new(&json).unwrap();
+        let result = quote.evaluate_tcb_status(&tcb_info).unwrap();
+        assert_eq!(result.tcb_status, TcbStatus::UpToDate);
+        assert_eq!(result.tdx_module_tcb_status, Some(TcbStatus::UpToDate));
+        assert_eq!(result.advisory_ids, vec!["INTEL-SA-00001".to_string()]);
+        assert_eq!(result.tcb_evaluation_data_number, 16);
+    }
+
+    #[test]
+    fn test_verify_and_evaluate_tcb_info() {
+        let mut quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        // TDX module major version 0x10 is identified by TDX_10
+        quote.body.tee_tcb_svn[1] = 0x10;
+        let json = sign_tcb_info(&build_tcb_info(&quote, &get_fmspc(&quote), 0, "OutOfDate"));
+        assert!(json.contains(r#""id":"TDX_10""#));
+
+        let tcb_info = TcbInfo::new(&json).unwrap();
+        let chain = format!(
+            "{}/test_data/tdx/tcb_signing.pem",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let root_ca = format!("{}/test_data/tdx/root_ca.pem", env!("CARGO_MANIFEST_DIR"));
+        let verify_time = parse_date_time("2024-01-15T00:00:00Z").unwrap();
+        tcb_info.verify(&chain, &root_ca, verify_time).unwrap();
+        let result = quote.evaluate_tcb_status(&tcb_info).unwrap();
+        assert_eq!(result.platform_tcb_status, TcbStatus::UpToDate);
+        assert_eq!(result.tdx_module_tcb_status, Some(TcbStatus::OutOfDate));
+        assert_eq!(result.tcb_status, TcbStatus::OutOfDate);
+
+        // outside the validity period
+        let verify_time = parse_date_time("2024-03-01T00:00:00Z").unwrap();
+        assert!(tcb_info.verify(&chain, &root_ca, verify_time).is_err());
+
+        // tampered TCB Info body
+        let tampered = json.replacen(
+            r#""tcbEvaluationDataNumber":16"#,
+            r#""tcbEvaluationDataNumber":17"#,
+            1,
+        );
+        let verify_time = parse_date_time("2024-01-15T00:00:00Z").unwrap();
+        assert!(TcbInfo::new(&tampered)
+            .unwrap()
+            .verify(&chain, &root_ca, verify_time)
+            .is_err());
+        // signing certificate not issued by the root CA
+        assert!(tcb_info.verify(&chain, &chain, verify_time).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_tcb_status_out_of_date() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        // first level requires a higher TDX TCB component than the quote
+        let json = build_tcb_info(&quote, &get_fmspc(&quote), 0xff, "UpToDate");
+        let result = quote
+            .evaluate_tcb_status(&TcbInfo::new(&json).unwrap())
+            .unwrap();
+        assert_eq!(result.platform_tcb_status, TcbStatus::OutOfDate);
+        assert_eq!(result.tcb_status, TcbStatus::OutOfDate);
+        assert_eq!(result.advisory_ids.len(), 3);
+
+        // out of date TDX module makes an up to date platform out of date
+        let json = build_tcb_info(&quote, &get_fmspc(&quote), 0, "OutOfDate");
+        let result = quote
+            .evaluate_tcb_status(&TcbInfo::new(&json).unwrap())
+            .unwrap();
+        assert_eq!(result.platform_tcb_status, TcbStatus::UpToDate);
+        assert_eq!(result.tcb_status, TcbStatus::OutOfDate);
+    }
+
+    #[test]
+    fn test_evaluate_tcb_status_fmspc_mismatch() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let json = build_tcb_info(&quote, "000000000000", 0, "UpToDate");
+        assert!(quote
+            .evaluate_tcb_status(&TcbInfo::new(&json).unwrap())
+            .is_err());
+    }
+
+    fn build_qe_identity(
+        report: &TdxEnclaveReportBody,
+        id: &str,
+        mrsigner: &str,
+        isvsvn: u16,
+    ) -> String {
+        format!(
+            r#"{{"enclaveIdentity":{{"id":"{}","version":2,"issueDate":"2024-01-01T00:00:00Z","nextUpdate":"2024-02-01T00:00:00Z","tcbEvaluationDataNumber":16,
+            "miscselect":"00000000","miscselectMask":"00000000","attributes":"{}","attributesMask":"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF","mrsigner":"{}","isvprodid":{},
+            "tcbLevels":[{{"tcb":{{"isvsvn":{}}},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"UpToDate"}},
+                {{"tcb":{{"isvsvn":0}},"tcbDate":"2021-11-10T00:00:00Z","tcbStatus":"OutOfDate","advisoryIDs":["INTEL-SA-00615"]}}]
+            }},"signature":"00"}}"#,
+            id,
+            hex::encode(report.attributes),
+            mrsigner,
+            report.isv_prodid,
+            isvsvn
+        )
+    }
+
+    #[test]
+    fn test_evaluate_qe_identity() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let signature = quote.tdx_quote_ecdsa256_sigature.as_ref().unwrap();
+        let report = &signature
+            .qe_cert
+            .cert_data_struct
+            .as_ref()
+            .unwrap()
+            .qe_report;
+        let mrsigner = hex::encode(report.mrsigner);
+
+        let json = build_qe_identity(report, "TD_QE", &mrsigner, report.isv_svn as u16);
+        let result = quote
+            .evaluate_qe_identity(&QeIdentity::new(&json).unwrap())
+            .unwrap();
+        assert_eq!(result.tcb_status, TcbStatus::UpToDate);
+
+        let json = build_qe_identity(report, "TD_QE", &mrsigner, report.isv_svn as u16 + 1);
+        let result = quote
+            .evaluate_qe_identity(&QeIdentity::new(&json).unwrap())
+            .unwrap();
+        assert_eq!(result.tcb_status, TcbStatus::OutOfDate);
+        assert_eq!(result.advisory_ids, vec!["INTEL-SA-00615".to_string()]);
+
+        // ISVSVN above 255 is valid in the collateral
+        let json = build_qe_identity(report, "TD_QE", &mrsigner, 0x100);
+        let result = quote
+            .evaluate_qe_identity(&QeIdentity::new(&json).unwrap())
+            .unwrap();
+        assert_eq!(result.tcb_status, TcbStatus::OutOfDate);
+
+        let json = build_qe_identity(report, "TD_QE", &hex::encode([0; 32]), 0);
+        assert!(quote
+            .evaluate_qe_identity(&QeIdentity::new(&json).unwrap())
+            .is_err());
+
+        // identity of the SGX QE
+        let json = build_qe_identity(report, "QE", &mrsigner, 0);
+        assert!(quote
+            .evaluate_qe_identity(&QeIdentity::new(&json).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_collateral_signature() {
+        let json = r#"{"tcbInfo":{"id":"TDX", "version":3},"signature":"00"}"#;
+        let body = SignedCollateralBody::new(json, "tcbInfo").unwrap();
+        assert_eq!(body.raw_body, r#"{"id":"TDX", "version":3}"#);
+
+        let signing_key = SigningKey::from_slice(&[0x5a; 32]).unwrap();
+        let signature: Signature = signing_key.sign(body.raw_body.as_bytes());
+        let key = VerifyingKey::from(&signing_key);
+        assert!(
+            verify_collateral_signature(&key, body.raw_body.as_bytes(), &signature.to_bytes())
+                .is_ok()
+        );
+        assert!(verify_collateral_signature(
+            &key,
+            br#"{"id":"TDX","version":3}"#,
+            &signature.to_bytes()
+        )
+        .is_err());
+    }
+}
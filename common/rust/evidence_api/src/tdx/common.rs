@@ -74,12 +74,35 @@ pub enum QeCertDataType {
 pub const TDX_QUOTE_VERSION_4: u16 = 4;
 pub const TDX_QUOTE_VERSION_5: u16 = 5;
 
+#[derive(Clone, PartialEq, Debug)]
+#[repr(u16)]
+pub enum TdxQuoteBodyType {
+    /*** TD Quote Body Type used in the Version 5 TD Quote Body Descriptor.
+    Definition reference:
+    https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf
+    A.4.2. TD Quote Body Descriptor
+    */
+    SGX_ENCLAVE = 1, // Future SGX support, currently not supported
+    TD_1_0 = 2,
+    TD_1_5 = 3,
+}
+
+// quote structure sizes in bytes
+pub const TDX_QUOTE_HEADER_LEN: usize = 48;
+pub const TDX_QUOTE_BODY_DESCRIPTOR_LEN: usize = 6;
+pub const TDX_QUOTE_BODY_1_0_LEN: usize = 584;
+pub const TDX_QUOTE_BODY_1_5_LEN: usize = 648;
+
 pub const ACPI_TABLE_FILE_VM: &str = "/sys/firmware/acpi/tables/CCEL";
 pub const ACPI_TABLE_DATA_FILE_VM: &str = "/sys/firmware/acpi/tables/data/CCEL";
 pub const IMA_DATA_FILE_VM: &str = "/sys/kernel/security/integrity/ima/ascii_runtime_measurements";
+pub const IMA_BINARY_DATA_FILE_VM: &str =
+    "/sys/kernel/security/integrity/ima/binary_runtime_measurements";
 
 pub const ACPI_TABLE_FILE_CONTAINER: &str = "/run/firmware/acpi/tables/CCEL";
 pub const ACPI_TABLE_DATA_FILE_CONTAINER: &str = "/run/firmware/acpi/tables/data/CCEL";
 pub const IMA_DATA_FILE_CONTAINER: &str =
     "/run/kernel/security/integrity/ima/ascii_runtime_measurements";
+pub const IMA_BINARY_DATA_FILE_CONTAINER: &str =
+    "/run/kernel/security/integrity/ima/binary_runtime_measurements";
 pub const ATTEST_CFG_FILE_PATH: &str = "/etc/tdx-attest.conf";
@@ -1,4 +1,7 @@
+pub mod collateral;
 pub mod common;
+pub mod pck;
 pub mod quote;
 pub mod report;
 pub mod rtmr;
+pub mod verify;
@@ -0,0 +1,427 @@
+use crate::tdx::common::*;
+use crate::tdx::quote::*;
+use crate::x509::*;
+use anyhow::anyhow;
+use log::info;
+use std::time::SystemTime;
+use x509_cert::der::asn1::{Any, ObjectIdentifier, OctetString};
+use x509_cert::der::{Decode, Tag, Tagged};
+use x509_cert::Certificate;
+
+// OID of the SGX extensions in PCK certificates and of the contained fields
+pub const OID_SGX_EXTENSIONS: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1");
+pub const OID_SGX_PPID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.1");
+pub const OID_SGX_TCB: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2");
+pub const OID_SGX_PCESVN: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.17");
+pub const OID_SGX_CPUSVN: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.18");
+pub const OID_SGX_PCEID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.3");
+pub const OID_SGX_FMSPC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.4");
+pub const OID_SGX_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.5");
+pub const OID_SGX_PLATFORM_INSTANCE_ID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.6");
+pub const OID_SGX_CONFIGURATION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.7");
+pub const OID_SGX_DYNAMIC_PLATFORM: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.7.1");
+pub const OID_SGX_CACHED_KEYS: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.7.2");
+pub const OID_SGX_SMT_ENABLED: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.7.3");
+
+/***
+    PCK certificate chain carried in the QE Certification Data of a TDX quote.
+
+    Attributes:
+        certs: X.509 certificates in the order found in the quote, i.e. the PCK leaf
+               certificate first, followed by the Intel SGX PCK Platform/Processor CA
+               and the Intel SGX Root CA.
+    Definition reference:
+    https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf
+    A.3.9. QE Certification Data - Version 4, Certification Data Type 5
+*/
+#[derive(Clone)]
+pub struct PckCertChain {
+    pub certs: Vec<Certificate>,
+}
+
+impl PckCertChain {
+    pub fn new(pem: &[u8]) -> Result<PckCertChain, anyhow::Error> {
+        let certs = match load_certificates(pem) {
+            Ok(certs) => certs,
+            Err(e) => {
+                return Err(anyhow!(
+                    "[PckCertChain] error parsing PCK cert chain: {:?}",
+                    e
+                ))
+            }
+        };
+        if certs.is_empty() {
+            return Err(anyhow!("[PckCertChain] empty PCK cert chain"));
+        }
+        Ok(PckCertChain { certs })
+    }
+
+    pub fn get_leaf_cert(&self) -> &Certificate {
+        &self.certs[0]
+    }
+
+    // Get the SGX/TDX platform extensions of the PCK leaf certificate
+    pub fn get_pck_extensions(&self) -> Result<PckExtensions, anyhow::Error> {
+        PckExtensions::new(self.get_leaf_cert())
+    }
+
+    /***
+        Validate the chain leaf -> PCK Platform/Processor CA -> root against a trust anchor.
+
+        Args:
+            root_ca_file: path of the trusted Intel SGX Root CA certificate in PEM or DER
+            crl_files: paths of CRLs in PEM or DER, e.g. the PCK CRL and Root CA CRL
+            verify_time: time at which the chain must be valid, default is current time
+
+        Returns:
+            Error describing the first failed check
+    */
+    pub fn verify(
+        &self,
+        root_ca_file: &str,
+        crl_files: &[String],
+        verify_time: Option<SystemTime>,
+    ) -> Result<(), anyhow::Error> {
+        let trust_anchor = match load_certificate_file(root_ca_file)?.into_iter().next() {
+            Some(cert) => cert,
+            None => return Err(anyhow!("[verify] no certificate in {}", root_ca_file)),
+        };
+        let mut crls = Vec::new();
+        for crl_file in crl_files {
+            crls.push(load_crl_file(crl_file)?);
+        }
+
+        verify_cert_chain(
+            &self.certs,
+            &trust_anchor,
+            &crls,
+            verify_time.unwrap_or_else(SystemTime::now),
+        )
+    }
+
+    pub fn show(&self) {
+        info!("show the data of PckCertChain");
+        for (index, cert) in self.certs.iter().enumerate() {
+            info!("cert[{}] subject = {}", index, cert.tbs_certificate.subject);
+            info!("cert[{}] issuer = {}", index, cert.tbs_certificate.issuer);
+        }
+    }
+}
+
+// SGX Type of the platform
+#[derive(Clone, Debug, PartialEq)]
+pub enum SgxType {
+    Standard = 0,
+    Scalable = 1,
+    ScalableWithIntegrity = 2,
+}
+
+/***
+    Platform configuration of multi-package platforms, only present in PCK certificates
+    issued by the Intel SGX PCK Platform CA.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PckPlatformConfiguration {
+    pub dynamic_platform: Option<bool>,
+    pub cached_keys: Option<bool>,
+    pub smt_enabled: Option<bool>,
+}
+
+/***
+    SGX/TDX platform extensions of the PCK leaf certificate.
+
+    Attributes:
+        ppid: Platform Provisioning ID of the processor package or platform instance.
+        cpu_svn_components: the 16 SGX TCB Comp SVNs of the TCB level.
+        pce_svn: PCE Security Version Number of the TCB level.
+        cpu_svn: raw CPUSVN of the TCB level.
+        pce_id: identifier of the Provisioning Certification Enclave.
+        fmspc: Family-Model-Stepping-Platform-CustomSKU of the platform.
+        sgx_type: SGX Type of the platform.
+        platform_instance_id: identifier of the platform instance, only for multi-package
+                              platforms.
+        configuration: configuration of the platform instance, only for multi-package
+                       platforms.
+    Definition reference:
+    https://api.trustedservices.intel.com/documents/Intel_SGX_PCK_Certificate_CRL_Spec-1.5.pdf
+    1.3.5 Intel SGX PCK Certificate, SGX Extensions (OID 1.2.840.113741.1.13.1)
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PckExtensions {
+    pub ppid: Vec<u8>,
+    pub cpu_svn_components: [u8; 16],
+    pub pce_svn: u16,
+    pub cpu_svn: [u8; 16],
+    pub pce_id: [u8; 2],
+    pub fmspc: [u8; 6],
+    pub sgx_type: Option<SgxType>,
+    pub platform_instance_id: Option<Vec<u8>>,
+    pub configuration: Option<PckPlatformConfiguration>,
+}
+
+impl PckExtensions {
+    // Parse the SGX extensions of a PCK certificate
+    pub fn new(cert: &Certificate) -> Result<PckExtensions, anyhow::Error> {
+        let extension = match &cert.tbs_certificate.extensions {
+            Some(extensions) => extensions
+                .iter()
+                .find(|extension| extension.extn_id == OID_SGX_EXTENSIONS),
+            None => None,
+        };
+        let extension = match extension {
+            Some(extension) => extension,
+            None => return Err(anyhow!("[PckExtensions] SGX extensions not found")),
+        };
+
+        let mut pck_extensions = PckExtensions::default();
+        let mut found = Vec::new();
+        let sgx_extensions = match Any::from_der(extension.extn_value.as_bytes()) {
+            Ok(sgx_extensions) => sgx_extensions,
+            Err(e) => return Err(anyhow!("[PckExtensions] invalid SGX extensions: {:?}", e)),
+        };
+        for (oid, value) in decode_sgx_entries(&sgx_extensions)? {
+            found.push(oid);
+            match oid {
+                OID_SGX_PPID => pck_extensions.ppid = decode_octets(&value)?,
+                OID_SGX_TCB => {
+                    for (oid, value) in decode_sgx_entries(&value)? {
+                        let arc = oid.arcs().last().unwrap_or(0) as usize;
+                        if oid == OID_SGX_PCESVN {
+                            pck_extensions.pce_svn = value
+                                .decode_as::<u16>()
+                                .map_err(|e| anyhow!("[PckExtensions] invalid PCESVN: {:?}", e))?;
+                        } else if oid == OID_SGX_CPUSVN {
+                            pck_extensions.cpu_svn = decode_fixed_octets(&value)?;
+                        } else if (1..=16).contains(&arc) {
+                            pck_extensions.cpu_svn_components[arc - 1] =
+                                value.decode_as::<u8>().map_err(|e| {
+                                    anyhow!("[PckExtensions] invalid SGX TCB Comp SVN: {:?}", e)
+                                })?;
+                        }
+                    }
+                }
+                OID_SGX_PCEID => pck_extensions.pce_id = decode_fixed_octets(&value)?,
+                OID_SGX_FMSPC => pck_extensions.fmspc = decode_fixed_octets(&value)?,
+                OID_SGX_TYPE => {
+                    if value.tag() != Tag::Enumerated {
+                        return Err(anyhow!("[PckExtensions] invalid SGX Type tag"));
+                    }
+                    pck_extensions.sgx_type = match value.value() {
+                        [0] => Some(SgxType::Standard),
+                        [1] => Some(SgxType::Scalable),
+                        [2] => Some(SgxType::ScalableWithIntegrity),
+                        v => return Err(anyhow!("[PckExtensions] unknown SGX Type {:?}", v)),
+                    };
+                }
+                OID_SGX_PLATFORM_INSTANCE_ID => {
+                    pck_extensions.platform_instance_id = Some(decode_octets(&value)?)
+                }
+                OID_SGX_CONFIGURATION => {
+                    let mut configuration = PckPlatformConfiguration::default();
+                    for (oid, value) in decode_sgx_entries(&value)? {
+                        let flag = value.decode_as::<bool>().ok();
+                        match oid {
+                            OID_SGX_DYNAMIC_PLATFORM => configuration.dynamic_platform = flag,
+                            OID_SGX_CACHED_KEYS => configuration.cached_keys = flag,
+                            OID_SGX_SMT_ENABLED => configuration.smt_enabled = flag,
+                            _ => (),
+                        }
+                    }
+                    pck_extensions.configuration = Some(configuration);
+                }
+                _ => (),
+            }
+        }
+
+        for oid in [OID_SGX_PPID, OID_SGX_TCB, OID_SGX_PCEID, OID_SGX_FMSPC] {
+            if !found.contains(&oid) {
+                return Err(anyhow!("[PckExtensions] missing mandatory field {}", oid));
+            }
+        }
+
+        Ok(pck_extensions)
+    }
+
+    pub fn show(&self) {
+        info!("show the data of PckExtensions");
+        info!("ppid = {:02X?}", self.ppid);
+        info!("cpu_svn_components = {:?}", self.cpu_svn_components);
+        info!("pce_svn = {}", self.pce_svn);
+        info!("cpu_svn = {:02X?}", self.cpu_svn);
+        info!("pce_id = {:02X?}", self.pce_id);
+        info!("fmspc = {:02X?}", self.fmspc);
+        info!("sgx_type = {:?}", self.sgx_type);
+        info!("platform_instance_id = {:02X?}", self.platform_instance_id);
+        info!("configuration = {:?}", self.configuration);
+    }
+}
+
+// decode SEQUENCE OF SEQUENCE { OID, value } used by the SGX extensions
+fn decode_sgx_entries(sequence: &Any) -> Result<Vec<(ObjectIdentifier, Any)>, anyhow::Error> {
+    let entries = match sequence.decode_as::<Vec<Any>>() {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(anyhow!(
+                "[decode_sgx_entries] invalid SGX extension: {:?}",
+                e
+            ))
+        }
+    };
+
+    let mut result = Vec::new();
+    for entry in entries {
+        let fields = match entry.decode_as::<Vec<Any>>() {
+            Ok(fields) if fields.len() == 2 => fields,
+            _ => return Err(anyhow!("[decode_sgx_entries] invalid SGX extension entry")),
+        };
+        let oid = match fields[0].decode_as::<ObjectIdentifier>() {
+            Ok(oid) => oid,
+            Err(e) => return Err(anyhow!("[decode_sgx_entries] invalid OID: {:?}", e)),
+        };
+        result.push((oid, fields[1].clone()));
+    }
+    Ok(result)
+}
+
+fn decode_octets(value: &Any) -> Result<Vec<u8>, anyhow::Error> {
+    match value.decode_as::<OctetString>() {
+        Ok(octets) => Ok(octets.into_bytes()),
+        Err(e) => Err(anyhow!("[decode_octets] invalid OCTET STRING: {:?}", e)),
+    }
+}
+
+fn decode_fixed_octets<const N: usize>(value: &Any) -> Result<[u8; N], anyhow::Error> {
+    let octets = decode_octets(value)?;
+    match octets.try_into() {
+        Ok(octets) => Ok(octets),
+        Err(octets) => Err(anyhow!(
+            "[decode_fixed_octets] expect {} bytes, got {}",
+            N,
+            octets.len()
+        )),
+    }
+}
+
+impl TdxQuoteQeCert {
+    /***
+        Get the PCK certificate chain from QE Certification Data.
+        The chain is either carried directly with Certification Data Type 5, or nested in
+        the QE Report Certification Data with Certification Data Type 6.
+    */
+    pub fn get_pck_cert_chain(&self) -> Result<PckCertChain, anyhow::Error> {
+        match (&self.cert_type, &self.cert_data_struct, &self.cert_data_vec) {
+            (QeCertDataType::PCK_CERT_CHAIN, _, Some(pem)) => PckCertChain::new(pem),
+            (QeCertDataType::QE_REPORT_CERT, Some(qe_report_cert), _) => {
+                qe_report_cert.qe_auth_cert.get_pck_cert_chain()
+            }
+            _ => Err(anyhow!(
+                "[get_pck_cert_chain] PCK cert chain not found, cert type: {:?}",
+                self.cert_type
+            )),
+        }
+    }
+}
+
+impl TdxQuote {
+    // Get the PCK certificate chain carried in the quote signature data
+    pub fn get_pck_cert_chain(&self) -> Result<PckCertChain, anyhow::Error> {
+        match &self.tdx_quote_ecdsa256_sigature {
+            Some(signature) => signature.qe_cert.get_pck_cert_chain(),
+            None => Err(anyhow!(
+                "[get_pck_cert_chain] only ECDSA-256-with-P-256 quote signature is supported"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_pck_cert_chain {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+    use x509_cert::der::Encode;
+
+    const QUOTE_V4: &[u8] = include_bytes!("../../test_data/quote.bin");
+
+    fn write_cert(name: &str, cert: &Certificate) -> String {
+        let path = env::temp_dir().join(name);
+        fs::write(&path, cert.to_der().unwrap()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn get_verify_time(chain: &PckCertChain) -> SystemTime {
+        let not_before = chain.get_leaf_cert().tbs_certificate.validity.not_before;
+        not_before.to_system_time() + Duration::from_secs(24 * 3600)
+    }
+
+    #[test]
+    fn test_get_pck_cert_chain() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let chain = quote.get_pck_cert_chain().unwrap();
+        assert_eq!(chain.certs.len(), 3);
+        assert_eq!(
+            chain.get_leaf_cert().tbs_certificate.issuer,
+            chain.certs[1].tbs_certificate.subject
+        );
+    }
+
+    #[test]
+    fn test_get_pck_extensions() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let chain = quote.get_pck_cert_chain().unwrap();
+        let extensions = chain.get_pck_extensions().unwrap();
+        assert_eq!(extensions.ppid.len(), 16);
+        assert_eq!(extensions.cpu_svn_components, extensions.cpu_svn);
+        assert_ne!(extensions.fmspc, [0; 6]);
+        assert!(extensions.sgx_type.is_some());
+
+        // the intermediate CA does not carry SGX extensions
+        assert!(PckExtensions::new(&chain.certs[1]).is_err());
+    }
+
+    #[test]
+    fn test_verify_pck_cert_chain() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let chain = quote.get_pck_cert_chain().unwrap();
+        let root_ca_file = write_cert("evidence_api_test_root_ca.der", &chain.certs[2]);
+        let verify_time = get_verify_time(&chain);
+
+        assert!(chain.verify(&root_ca_file, &[], Some(verify_time)).is_ok());
+
+        // the trust anchor is optional in the chain
+        let partial_chain = PckCertChain {
+            certs: chain.certs[0..2].to_vec(),
+        };
+        assert!(partial_chain
+            .verify(&root_ca_file, &[], Some(verify_time))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_verify_pck_cert_chain_fail() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let chain = quote.get_pck_cert_chain().unwrap();
+        let verify_time = get_verify_time(&chain);
+
+        // wrong trust anchor
+        let wrong_ca_file = write_cert("evidence_api_test_wrong_ca.der", &chain.certs[1]);
+        assert!(chain
+            .verify(&wrong_ca_file, &[], Some(verify_time))
+            .is_err());
+
+        // expired at verification time
+        let root_ca_file = write_cert("evidence_api_test_root_ca_2.der", &chain.certs[2]);
+        let not_after = chain.get_leaf_cert().tbs_certificate.validity.not_after;
+        let expired = not_after.to_system_time() + Duration::from_secs(1);
+        assert!(chain.verify(&root_ca_file, &[], Some(expired)).is_err());
+    }
+}
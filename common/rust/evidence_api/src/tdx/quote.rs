@@ -8,6 +8,7 @@ use log::*;
 
 use crate::api::ParseCcReport;
 use crate::api_data::CcReport;
+use crate::binary_blob::BinaryReader;
 use crate::tdx::common::*;
 
 #[repr(C)]
@@ -159,11 +160,11 @@ pub struct TdxQuoteBody {
     Quote Format Version        Architecture    Class Usage Comment
     4                           TDX 1.0         TdxQuoteBody
     4                           TDX 1.5         TdxQuoteBody
-    5                           TDX 1.0         TODO: should use TdxQuoteBody
-    5                           TDX 1.5         TODO: should define a sub class with 2 more fields
+    5                           TDX 1.0         TdxQuoteBody
+    5                           TDX 1.5         TdxQuoteBody15, TdxQuoteBody with 2 more fields
                                                     TEE_TCB_SVN_2
                                                     MRSERVICETD
-    5                           SGX             TODO: should define a new independent class
+    5                           SGX             Not supported
     Atrributes:
         data: A bytearray fo the raw data.
         tee_tcb_svn: describing the TCB of TDX.
@@ -272,6 +273,103 @@ impl TdxQuoteBody {
     }
 }
 
+#[derive(Clone)]
+pub struct TdxQuoteBodyDescriptor {
+    /*** TD Quote Body Descriptor, only present in Version 5 Quote Format.
+    Atrributes:
+        body_type: A ``TdxQuoteBodyType`` indicating the type of the TD Quote Body.
+        size: Size of the TD Quote Body in bytes.
+    Definition reference:
+    https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf
+    A.4.2. TD Quote Body Descriptor
+    Name            Size (bytes)    Type        Description
+    TD Quote Body   2               Integer     Type of the TD Quote Body. Supported values:
+    Type                                            1 (Future SGX support)
+                                                    2 (TD Quote Body for TDX 1.0)
+                                                    3 (TD Quote Body for TDX 1.5)
+    Size            4               Integer     Size of the TD Quote Body:
+                                                    584 for TDX 1.0
+                                                    648 for TDX 1.5
+    */
+    pub body_type: TdxQuoteBodyType,
+    pub size: u32,
+}
+
+impl TdxQuoteBodyDescriptor {
+    pub fn new(data: &[u8]) -> Result<TdxQuoteBodyDescriptor, anyhow::Error> {
+        if data.len() < TDX_QUOTE_BODY_DESCRIPTOR_LEN {
+            return Err(anyhow!(
+                "[TdxQuoteBodyDescriptor] need {} bytes, but only {} provided",
+                TDX_QUOTE_BODY_DESCRIPTOR_LEN,
+                data.len()
+            ));
+        }
+        let body_type = match u16::from_le_bytes(data[0..2].try_into().unwrap()) {
+            1 => TdxQuoteBodyType::SGX_ENCLAVE,
+            2 => TdxQuoteBodyType::TD_1_0,
+            3 => TdxQuoteBodyType::TD_1_5,
+            v => {
+                return Err(anyhow!(
+                    "[TdxQuoteBodyDescriptor] unknown TD Quote Body type: {}",
+                    v
+                ))
+            }
+        };
+        let size = u32::from_le_bytes(data[2..6].try_into().unwrap());
+
+        Ok(TdxQuoteBodyDescriptor { body_type, size })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TdxQuoteBodyDescriptor");
+        info!("body_type = {:?}", self.body_type);
+        info!("size = {}", self.size);
+    }
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct TdxQuoteBody15 {
+    /*** TD Quote Body for TDX 1.5 in Version 5 Quote Format.
+    Atrributes:
+        body: A ``TdxQuoteBody`` storing the fields shared with the TDX 1.0 TD Quote Body.
+        tee_tcb_svn2: describing the current TCB of TDX.
+        mrservicetd: A bytearray storing the Measurement of the initial contents of the
+                     Migration TD.
+    Definition reference:
+    https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_TDX_DCAP_Quoting_Library_API.pdf
+    A.4.4. TD Quote Body for TDX 1.5
+    */
+    pub body: TdxQuoteBody,
+    pub tee_tcb_svn2: [u8; 16], // Array of current TEE TCB SVNs
+    pub mrservicetd: [u8; 48],  // Measurement of the Migration TD (SHA384 hash)
+}
+
+impl TdxQuoteBody15 {
+    pub fn show(&self) {
+        info!("show the data of TdxQuoteBody15");
+        self.body.show();
+        info!("tee_tcb_svn2 = {:02X?}", self.tee_tcb_svn2);
+        info!("mrservicetd = {:02X?}", self.mrservicetd);
+    }
+}
+
+// TD Quote Body of a Version 5 Quote, typed according to the TD Quote Body Descriptor
+#[derive(Clone)]
+pub enum TdxQuoteV5Body {
+    TD_1_0(TdxQuoteBody),
+    TD_1_5(TdxQuoteBody15),
+}
+
+impl TdxQuoteV5Body {
+    pub fn show(&self) {
+        match self {
+            TdxQuoteV5Body::TD_1_0(body) => body.show(),
+            TdxQuoteV5Body::TD_1_5(body) => body.show(),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct TdxEnclaveReportBody {
@@ -290,6 +388,11 @@ pub struct TdxEnclaveReportBody {
 }
 
 impl TdxEnclaveReportBody {
+    // raw bytes of the enclave report, as covered by the QE report signature
+    pub fn to_bytes(&self) -> [u8; 384] {
+        unsafe { transmute::<TdxEnclaveReportBody, [u8; 384]>(self.clone()) }
+    }
+
     pub fn show(&self) {
         info!("show the data of TdxEnclaveReportBody");
         info!("cpu_svn = {:02X?}", self.cpu_svn);
@@ -325,35 +428,21 @@ pub struct TdxQuoteQeReportCert {
 }
 
 impl TdxQuoteQeReportCert {
-    pub fn new(data: Vec<u8>) -> TdxQuoteQeReportCert {
-        let tdx_enclave_report_body: TdxEnclaveReportBody = unsafe {
-            transmute::<[u8; 384], TdxEnclaveReportBody>(
-                data[0..384]
-                    .try_into()
-                    .expect("slice with incorrect length"),
-            )
-        };
-        let qe_report_sig = data[384..448].try_into().unwrap();
-        let auth_data_size = unsafe {
-            transmute::<[u8; 2], u16>(
-                data[448..450]
-                    .try_into()
-                    .expect("slice with incorrect length"),
-            )
-        }
-        .to_le();
-        let auth_data_end = 450 + auth_data_size;
-        let mut qe_auth_data = Vec::new();
-        if auth_data_size > 0 {
-            qe_auth_data = data[450..auth_data_end as usize].to_vec();
-        }
-
-        TdxQuoteQeReportCert {
+    pub fn new(data: &[u8]) -> Result<TdxQuoteQeReportCert, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let tdx_enclave_report_body: TdxEnclaveReportBody =
+            unsafe { transmute::<[u8; 384], TdxEnclaveReportBody>(reader.read_array()?) };
+        let qe_report_sig = reader.read_array()?;
+        let auth_data_size = reader.read_u16_le()?;
+        let qe_auth_data = reader.read_bytes(auth_data_size as usize)?.to_vec();
+        let qe_auth_cert = TdxQuoteQeCert::new(&data[reader.position()..])?;
+
+        Ok(TdxQuoteQeReportCert {
             qe_report: tdx_enclave_report_body,
             qe_report_sig,
             qe_auth_data,
-            qe_auth_cert: Box::new(TdxQuoteQeCert::new(data[auth_data_end as usize..].to_vec())),
-        }
+            qe_auth_cert: Box::new(qe_auth_cert),
+        })
     }
 
     pub fn show(&self) {
@@ -384,32 +473,38 @@ pub struct TdxQuoteQeCert {
 }
 
 impl TdxQuoteQeCert {
-    pub fn new(data: Vec<u8>) -> TdxQuoteQeCert {
-        let cert_type: QeCertDataType = unsafe {
-            transmute::<[u8; 2], QeCertDataType>(
-                data[0..2].try_into().expect("slice with incorrect length"),
-            )
+    pub fn new(data: &[u8]) -> Result<TdxQuoteQeCert, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let cert_type = match reader.read_u16_le()? {
+            1 => QeCertDataType::PCK_ID_PLAIN,
+            2 => QeCertDataType::PCK_ID_RSA_2048_OAEP,
+            3 => QeCertDataType::PCK_ID_RSA_3072_OAEP,
+            4 => QeCertDataType::PCK_LEAF_CERT_PLAIN,
+            5 => QeCertDataType::PCK_CERT_CHAIN,
+            6 => QeCertDataType::QE_REPORT_CERT,
+            7 => QeCertDataType::PLATFORM_MANIFEST,
+            v => {
+                return Err(anyhow!(
+                    "[TdxQuoteQeCert] unknown QE certification data type: {}",
+                    v
+                ))
+            }
         };
-        let cert_size = unsafe {
-            transmute::<[u8; 4], u32>(data[2..6].try_into().expect("slice with incorrect length"))
-        }
-        .to_le();
-        let cert_data_end = 6 + cert_size;
+        let cert_size = reader.read_u32_le()?;
+        let cert_data = reader.read_bytes(cert_size as usize)?;
 
         if cert_type == QeCertDataType::QE_REPORT_CERT {
-            let cert_data = TdxQuoteQeReportCert::new(data[6..cert_data_end as usize].to_vec());
-            TdxQuoteQeCert {
-                cert_type: cert_type as QeCertDataType,
-                cert_data_struct: Some(Box::new(cert_data)),
+            Ok(TdxQuoteQeCert {
+                cert_type,
+                cert_data_struct: Some(Box::new(TdxQuoteQeReportCert::new(cert_data)?)),
                 cert_data_vec: None,
-            }
+            })
         } else {
-            let cert_data = data[6..cert_data_end as usize].to_vec();
-            TdxQuoteQeCert {
-                cert_type: cert_type as QeCertDataType,
+            Ok(TdxQuoteQeCert {
+                cert_type,
                 cert_data_struct: None,
-                cert_data_vec: Some(cert_data),
-            }
+                cert_data_vec: Some(cert_data.to_vec()),
+            })
         }
     }
 
@@ -448,12 +543,13 @@ pub struct TdxQuoteEcdsa256Sigature {
 }
 
 impl TdxQuoteEcdsa256Sigature {
-    pub fn new(data: Vec<u8>) -> TdxQuoteEcdsa256Sigature {
-        let sig = data[0..64].try_into().unwrap();
-        let ak = data[64..128].try_into().unwrap();
-        let qe_cert = TdxQuoteQeCert::new(data[128..data.len()].to_vec());
+    pub fn new(data: &[u8]) -> Result<TdxQuoteEcdsa256Sigature, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let sig = reader.read_array()?;
+        let ak = reader.read_array()?;
+        let qe_cert = TdxQuoteQeCert::new(&data[reader.position()..])?;
 
-        TdxQuoteEcdsa256Sigature { sig, ak, qe_cert }
+        Ok(TdxQuoteEcdsa256Sigature { sig, ak, qe_cert })
     }
 
     pub fn show(&self) {
@@ -496,17 +592,38 @@ pub struct TdxQuote {
     Quote Signature Variable Signature      Variable-length data containing the signature and
     Data                     Dependent      supporting data. For instance, an ECDSA P-256
                                             Signature
-    For Version 5
-    TODO: implement version 5 according to A.4. Version 5 Quote Format.
+    For Version 5, defined at A.4. Version 5 Quote Format:
+    Name            Size    Type            Description
+    Quote Header    48      TD Quote Header Header of Quote data structure. Version is 5.
+    TD Quote Body   6       TD Quote Body   Type and size of the TD Quote Body that follows.
+    Descriptor              Descriptor
+    TD Quote Body   584 or  TD Quote Body   TD Quote Body for TDX 1.0 (584 bytes) or
+                    648                     TDX 1.5 (648 bytes).
+    Quote Signature 4       Integer         Size of the Quote Signature Data structure
+    Data Len
+    Quote Signature Variable Signature      Same as Version 4.
+    Data                     Dependent
+    The ``body`` attribute always holds the fields shared by all body types, so the
+    RTMRs and other TDX 1.0 fields are accessible regardless of the quote version.
+    ``body_descriptor`` and ``body_v5`` are only set for Version 5 quotes.
     */
     pub header: TdxQuoteHeader,
     pub body: TdxQuoteBody,
+    pub body_descriptor: Option<TdxQuoteBodyDescriptor>, // for quote version 5
+    pub body_v5: Option<TdxQuoteV5Body>,                 // for quote version 5
     pub tdx_quote_ecdsa256_sigature: Option<TdxQuoteEcdsa256Sigature>, // for AttestationKeyType.ECDSA_P256
     pub tdx_quote_signature: Option<TdxQuoteSignature>, // for AttestationKeyType.ECDSA_P384
 }
 
 impl TdxQuote {
     pub fn parse_tdx_quote(quote: Vec<u8>) -> Result<TdxQuote, anyhow::Error> {
+        if quote.len() < TDX_QUOTE_HEADER_LEN {
+            return Err(anyhow!(
+                "[parse_tdx_quote] quote header needs {} bytes, but only {} provided",
+                TDX_QUOTE_HEADER_LEN,
+                quote.len()
+            ));
+        }
         let tdx_quote_header: TdxQuoteHeader = unsafe {
             transmute::<[u8; 48], TdxQuoteHeader>(
                 quote[0..48]
@@ -514,57 +631,157 @@ impl TdxQuote {
                     .expect("slice with incorrect length"),
             )
         };
-        if tdx_quote_header.version == TDX_QUOTE_VERSION_4 {
-            let tdx_quote_body: TdxQuoteBody = unsafe {
-                transmute::<[u8; 584], TdxQuoteBody>(
-                    quote[48..632]
-                        .try_into()
-                        .expect("slice with incorrect length"),
-                )
+
+        let (tdx_quote_body, body_descriptor, body_v5, sig_len_idx) = if tdx_quote_header.version
+            == TDX_QUOTE_VERSION_4
+        {
+            let body_end = TDX_QUOTE_HEADER_LEN + TDX_QUOTE_BODY_1_0_LEN;
+            TdxQuote::check_length(&quote, body_end + 4)?;
+            let tdx_quote_body =
+                TdxQuote::get_tdx_quote_body(&quote[TDX_QUOTE_HEADER_LEN..body_end]);
+            (tdx_quote_body, None, None, body_end)
+        } else if tdx_quote_header.version == TDX_QUOTE_VERSION_5 {
+            let body_idx = TDX_QUOTE_HEADER_LEN + TDX_QUOTE_BODY_DESCRIPTOR_LEN;
+            let body_descriptor = TdxQuoteBodyDescriptor::new(&quote[TDX_QUOTE_HEADER_LEN..])?;
+            let expected_size = match body_descriptor.body_type {
+                TdxQuoteBodyType::TD_1_0 => TDX_QUOTE_BODY_1_0_LEN,
+                TdxQuoteBodyType::TD_1_5 => TDX_QUOTE_BODY_1_5_LEN,
+                TdxQuoteBodyType::SGX_ENCLAVE => {
+                    return Err(anyhow!(
+                        "[parse_tdx_quote] SGX TD Quote Body type is not supported"
+                    ))
+                }
             };
-            let sig_len = unsafe {
-                transmute::<[u8; 4], i32>(
-                    quote[632..636]
-                        .try_into()
-                        .expect("slice with incorrect length"),
-                )
+            if body_descriptor.size as usize != expected_size {
+                return Err(anyhow!(
+                    "[parse_tdx_quote] invalid size {} for TD Quote Body type {:?}",
+                    body_descriptor.size,
+                    body_descriptor.body_type
+                ));
             }
-            .to_le();
-            let sig_idx_end = 636 + sig_len;
-
-            if tdx_quote_header.ak_type == AttestationKeyType::ECDSA_P256 {
-                let tdx_quote_ecdsa256_sigature =
-                    TdxQuoteEcdsa256Sigature::new(quote[636..sig_idx_end as usize].to_vec());
-
-                Ok(TdxQuote {
-                    header: tdx_quote_header,
-                    body: tdx_quote_body,
-                    tdx_quote_signature: None,
-                    tdx_quote_ecdsa256_sigature: Some(tdx_quote_ecdsa256_sigature),
-                })
-            } else if tdx_quote_header.ak_type == AttestationKeyType::ECDSA_P384 {
-                let tdx_quote_signature = TdxQuoteSignature {
-                    data: quote[636..sig_idx_end as usize].to_vec(),
+            let body_end = body_idx + expected_size;
+            TdxQuote::check_length(&quote, body_end + 4)?;
+
+            let tdx_quote_body =
+                TdxQuote::get_tdx_quote_body(&quote[body_idx..body_idx + TDX_QUOTE_BODY_1_0_LEN]);
+            let body_v5 = if body_descriptor.body_type == TdxQuoteBodyType::TD_1_5 {
+                let tdx_quote_body_1_5: TdxQuoteBody15 = unsafe {
+                    transmute::<[u8; TDX_QUOTE_BODY_1_5_LEN], TdxQuoteBody15>(
+                        quote[body_idx..body_end]
+                            .try_into()
+                            .expect("slice with incorrect length"),
+                    )
                 };
-
-                Ok(TdxQuote {
-                    header: tdx_quote_header,
-                    body: tdx_quote_body,
-                    tdx_quote_signature: Some(tdx_quote_signature),
-                    tdx_quote_ecdsa256_sigature: None,
-                })
+                TdxQuoteV5Body::TD_1_5(tdx_quote_body_1_5)
             } else {
-                return Err(anyhow!("[parse_tdx_quote] unknown ak_type!"));
-            }
-        } else if tdx_quote_header.version == TDX_QUOTE_VERSION_5 {
-            // TODO: implement version 5
-            todo!()
+                TdxQuoteV5Body::TD_1_0(tdx_quote_body.clone())
+            };
+            (
+                tdx_quote_body,
+                Some(body_descriptor),
+                Some(body_v5),
+                body_end,
+            )
         } else {
             return Err(anyhow!(
                 "[parse_tdx_quote] unknown quote header version: {:}",
                 tdx_quote_header.version
             ));
+        };
+
+        let sig_len = u32::from_le_bytes(
+            quote[sig_len_idx..sig_len_idx + 4]
+                .try_into()
+                .expect("slice with incorrect length"),
+        );
+        let sig_idx = sig_len_idx + 4;
+        let sig_idx_end = sig_idx + sig_len as usize;
+        TdxQuote::check_length(&quote, sig_idx_end)?;
+
+        if tdx_quote_header.ak_type == AttestationKeyType::ECDSA_P256 {
+            let tdx_quote_ecdsa256_sigature =
+                match TdxQuoteEcdsa256Sigature::new(&quote[sig_idx..sig_idx_end]) {
+                    Ok(signature) => signature,
+                    Err(e) => {
+                        return Err(anyhow!(
+                            "[parse_tdx_quote] invalid quote signature data: {:?}",
+                            e
+                        ))
+                    }
+                };
+
+            Ok(TdxQuote {
+                header: tdx_quote_header,
+                body: tdx_quote_body,
+                body_descriptor,
+                body_v5,
+                tdx_quote_signature: None,
+                tdx_quote_ecdsa256_sigature: Some(tdx_quote_ecdsa256_sigature),
+            })
+        } else if tdx_quote_header.ak_type == AttestationKeyType::ECDSA_P384 {
+            let tdx_quote_signature = TdxQuoteSignature {
+                data: quote[sig_idx..sig_idx_end].to_vec(),
+            };
+
+            Ok(TdxQuote {
+                header: tdx_quote_header,
+                body: tdx_quote_body,
+                body_descriptor,
+                body_v5,
+                tdx_quote_signature: Some(tdx_quote_signature),
+                tdx_quote_ecdsa256_sigature: None,
+            })
+        } else {
+            Err(anyhow!("[parse_tdx_quote] unknown ak_type!"))
+        }
+    }
+
+    /***
+        Get the quote data covered by the quote signature, i.e. the Quote Header and the
+        TD Quote Body, including the TD Quote Body Descriptor for Version 5 quotes.
+    */
+    pub fn get_signed_data(&self) -> Vec<u8> {
+        let mut data =
+            unsafe { transmute::<TdxQuoteHeader, [u8; 48]>(self.header.clone()) }.to_vec();
+        match (&self.body_descriptor, &self.body_v5) {
+            (Some(descriptor), Some(body_v5)) => {
+                data.extend_from_slice(&(descriptor.body_type.clone() as u16).to_le_bytes());
+                data.extend_from_slice(&descriptor.size.to_le_bytes());
+                match body_v5 {
+                    TdxQuoteV5Body::TD_1_0(body) => data.extend_from_slice(&unsafe {
+                        transmute::<TdxQuoteBody, [u8; TDX_QUOTE_BODY_1_0_LEN]>(body.clone())
+                    }),
+                    TdxQuoteV5Body::TD_1_5(body) => data.extend_from_slice(&unsafe {
+                        transmute::<TdxQuoteBody15, [u8; TDX_QUOTE_BODY_1_5_LEN]>(body.clone())
+                    }),
+                }
+            }
+            _ => data.extend_from_slice(&unsafe {
+                transmute::<TdxQuoteBody, [u8; TDX_QUOTE_BODY_1_0_LEN]>(self.body.clone())
+            }),
+        }
+        data
+    }
+
+    fn get_tdx_quote_body(data: &[u8]) -> TdxQuoteBody {
+        unsafe {
+            transmute::<[u8; TDX_QUOTE_BODY_1_0_LEN], TdxQuoteBody>(
+                data[0..TDX_QUOTE_BODY_1_0_LEN]
+                    .try_into()
+                    .expect("slice with incorrect length"),
+            )
+        }
+    }
+
+    fn check_length(quote: &[u8], needed: usize) -> Result<(), anyhow::Error> {
+        if quote.len() < needed {
+            return Err(anyhow!(
+                "[parse_tdx_quote] quote needs at least {} bytes, but only {} provided",
+                needed,
+                quote.len()
+            ));
         }
+        Ok(())
     }
 }
 
@@ -580,3 +797,107 @@ impl ParseCcReport<TdxQuote> for CcReport {
         }
     }
 }
+
+#[cfg(test)]
+mod test_parse_tdx_quote {
+    use super::*;
+
+    const QUOTE_V4: &[u8] = include_bytes!("../../test_data/quote.bin");
+
+    // build a version 5 quote out of the version 4 test quote
+    fn build_v5_quote(body_type: u16) -> Vec<u8> {
+        let mut quote = QUOTE_V4[0..48].to_vec();
+        quote[0..2].copy_from_slice(&TDX_QUOTE_VERSION_5.to_le_bytes());
+        quote.extend_from_slice(&body_type.to_le_bytes());
+        if body_type == TdxQuoteBodyType::TD_1_5 as u16 {
+            quote.extend_from_slice(&(TDX_QUOTE_BODY_1_5_LEN as u32).to_le_bytes());
+            quote.extend_from_slice(&QUOTE_V4[48..632]);
+            quote.extend_from_slice(&[0x11; 16]);
+            quote.extend_from_slice(&[0x22; 48]);
+        } else {
+            quote.extend_from_slice(&(TDX_QUOTE_BODY_1_0_LEN as u32).to_le_bytes());
+            quote.extend_from_slice(&QUOTE_V4[48..632]);
+        }
+        quote.extend_from_slice(&QUOTE_V4[632..]);
+        quote
+    }
+
+    #[test]
+    fn test_parse_tdx_quote_v4() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        assert_eq!(quote.header.version, TDX_QUOTE_VERSION_4);
+        assert!(quote.body_descriptor.is_none());
+        assert!(quote.body_v5.is_none());
+        assert!(quote.tdx_quote_ecdsa256_sigature.is_some());
+    }
+
+    #[test]
+    fn test_parse_tdx_quote_v5_td_1_0() {
+        let v4 = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let quote = TdxQuote::parse_tdx_quote(build_v5_quote(2)).unwrap();
+        assert_eq!(quote.header.version, TDX_QUOTE_VERSION_5);
+        let descriptor = quote.body_descriptor.unwrap();
+        assert_eq!(descriptor.body_type, TdxQuoteBodyType::TD_1_0);
+        assert_eq!(descriptor.size, 584);
+        assert!(matches!(quote.body_v5, Some(TdxQuoteV5Body::TD_1_0(_))));
+        assert_eq!(quote.body.rtmr0, v4.body.rtmr0);
+        assert_eq!(
+            quote.tdx_quote_ecdsa256_sigature.unwrap().sig,
+            v4.tdx_quote_ecdsa256_sigature.unwrap().sig
+        );
+    }
+
+    #[test]
+    fn test_parse_tdx_quote_v5_td_1_5() {
+        let v4 = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let quote = TdxQuote::parse_tdx_quote(build_v5_quote(3)).unwrap();
+        assert_eq!(quote.body_descriptor.unwrap().size, 648);
+        match quote.body_v5 {
+            Some(TdxQuoteV5Body::TD_1_5(body)) => {
+                assert_eq!(body.body.report_data, v4.body.report_data);
+                assert_eq!(body.tee_tcb_svn2, [0x11; 16]);
+                assert_eq!(body.mrservicetd, [0x22; 48]);
+            }
+            _ => panic!("expect TD 1.5 quote body"),
+        }
+        assert_eq!(quote.body.mrtd, v4.body.mrtd);
+    }
+
+    #[test]
+    fn test_parse_tdx_quote_v5_invalid_body() {
+        // SGX body type is not supported
+        assert!(TdxQuote::parse_tdx_quote(build_v5_quote(1)).is_err());
+        // body size does not match body type
+        let mut quote = build_v5_quote(3);
+        quote[50..54].copy_from_slice(&584u32.to_le_bytes());
+        assert!(TdxQuote::parse_tdx_quote(quote).is_err());
+        // truncated quote
+        let quote = build_v5_quote(3);
+        assert!(TdxQuote::parse_tdx_quote(quote[0..600].to_vec()).is_err());
+    }
+
+    // version 4 quote with the quote signature data cut to the size
+    fn build_short_signature_quote(sig_len: usize) -> Vec<u8> {
+        let mut quote = QUOTE_V4[0..636 + sig_len].to_vec();
+        quote[632..636].copy_from_slice(&(sig_len as u32).to_le_bytes());
+        quote
+    }
+
+    #[test]
+    fn test_parse_tdx_quote_invalid_signature() {
+        // short signature and attestation key
+        assert!(TdxQuote::parse_tdx_quote(build_short_signature_quote(100)).is_err());
+        // short QE certification data header
+        assert!(TdxQuote::parse_tdx_quote(build_short_signature_quote(130)).is_err());
+        // short QE certification data
+        assert!(TdxQuote::parse_tdx_quote(build_short_signature_quote(200)).is_err());
+        // short QE report certification data
+        let mut quote = build_short_signature_quote(200);
+        quote[766..770].copy_from_slice(&66u32.to_le_bytes());
+        assert!(TdxQuote::parse_tdx_quote(quote).is_err());
+        // unknown QE certification data type
+        let mut quote = QUOTE_V4.to_vec();
+        quote[764..766].copy_from_slice(&9u16.to_le_bytes());
+        assert!(TdxQuote::parse_tdx_quote(quote).is_err());
+    }
+}
@@ -81,7 +81,7 @@ impl TeeTcbInfo {
         let attributes = data[120..128].try_into().unwrap();
 
         if tdx_version == TdxVersion::TDX_1_0 {
-            let reserved = data[128..].try_into().unwrap();
+            let reserved = data[128..].to_vec();
             TeeTcbInfo {
                 valid,
                 tee_tcb_svn,
@@ -93,7 +93,7 @@ impl TeeTcbInfo {
             }
         } else {
             // TDX 1.5
-            let reserved = data[144..].try_into().unwrap();
+            let reserved = data[144..].to_vec();
             TeeTcbInfo {
                 valid,
                 tee_tcb_svn,
@@ -168,7 +168,7 @@ impl TdInfo {
                 mrownerconfig,
                 rtmrs,
                 servtd_hash: None,
-                reserved: data[400..].try_into().unwrap(),
+                reserved: data[400..].to_vec(),
             }
         } else {
             // TDX 1.5
@@ -181,7 +181,7 @@ impl TdInfo {
                 mrownerconfig,
                 rtmrs,
                 servtd_hash: Some(data[400..448].try_into().unwrap()),
-                reserved: data[448..].try_into().unwrap(),
+                reserved: data[448..].to_vec(),
             }
         }
     }
@@ -1,6 +1,25 @@
 use crate::tcg::*;
 use anyhow::anyhow;
 
+/***
+    Get the RTMR index of a CC measurement register index used by CCEL,
+    where index 0 stands for MRTD and indexes 1 to 4 for RTMR 0 to 3.
+    Note that EventLogs takes pcrIndex - 1 as imr_index, so imr_index + 1 is the
+    CC measurement register index of an event log.
+
+    Args:
+        mr_index: CC measurement register index
+
+    Returns:
+        The RTMR index, None for MRTD and invalid indexes
+*/
+pub fn get_rtmr_index(mr_index: u32) -> Option<u8> {
+    match mr_index {
+        1..=4 => Some((mr_index - 1) as u8),
+        _ => None,
+    }
+}
+
 pub struct TdxRTMR {
     index: u8,
     digest: (u16, TcgDigest),
@@ -0,0 +1,403 @@
+use crate::api_data::{ReplayOptions, ReplayResult};
+use crate::eventlog::EventLogs;
+use crate::tcg::*;
+use crate::tdx::quote::*;
+use crate::tdx::report::TDReport;
+use crate::tdx::rtmr::get_rtmr_index;
+use anyhow::anyhow;
+use log::info;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/***
+    Result of the offline signature verification of a TDX quote.
+    Each attribute tells whether the corresponding check passed.
+
+    Attributes:
+        quote_signature: the quote signature over the Quote Header and TD Quote Body
+                         verifies against the attestation key.
+        qe_report_signature: the QE report signature verifies against the public key of
+                             the PCK leaf certificate.
+        qe_report_data: the first 32 bytes of the QE report data equal
+                        SHA256(attestation key || QE authentication data) and the
+                        remaining 32 bytes are zero.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TdxQuoteSignatureResult {
+    pub quote_signature: bool,
+    pub qe_report_signature: bool,
+    pub qe_report_data: bool,
+}
+
+impl TdxQuoteSignatureResult {
+    pub fn is_valid(&self) -> bool {
+        self.quote_signature && self.qe_report_signature && self.qe_report_data
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TdxQuoteSignatureResult");
+        info!("quote_signature = {}", self.quote_signature);
+        info!("qe_report_signature = {}", self.qe_report_signature);
+        info!("qe_report_data = {}", self.qe_report_data);
+    }
+}
+
+impl TdxQuote {
+    /***
+        Verify the signatures carried in the quote without any network access.
+
+        Following checks are done:
+        1. the quote signature over the Quote Header and TD Quote Body against the
+           attestation key.
+        2. the QE report signature against the PCK leaf certificate key.
+        3. the QE report data binding, i.e. SHA256(ak || qe_auth_data).
+
+        Note that the PCK certificate chain itself is not validated here, see
+        ``PckCertChain::verify``.
+
+        Returns:
+            TdxQuoteSignatureResult telling which check failed, or error if the quote
+            does not carry the data required by the checks
+    */
+    pub fn verify_signatures(&self) -> Result<TdxQuoteSignatureResult, anyhow::Error> {
+        let signature = match &self.tdx_quote_ecdsa256_sigature {
+            Some(signature) => signature,
+            None => {
+                return Err(anyhow!(
+                    "[verify_signatures] only ECDSA-256-with-P-256 quote signature is supported"
+                ))
+            }
+        };
+        let qe_report_cert = match &signature.qe_cert.cert_data_struct {
+            Some(qe_report_cert) => qe_report_cert,
+            None => {
+                return Err(anyhow!(
+                    "[verify_signatures] QE report certification data not found in quote"
+                ))
+            }
+        };
+        let pck_leaf_key = get_pck_leaf_key(&qe_report_cert.qe_auth_cert)?;
+
+        // 1. quote signature over header and body, signed by the attestation key
+        let ak = [&[0x04], &signature.ak[..]].concat();
+        let quote_signature = match VerifyingKey::from_sec1_bytes(&ak) {
+            Ok(ak) => verify_ecdsa_p256(&ak, &self.get_signed_data(), &signature.sig),
+            Err(_) => false,
+        };
+
+        // 2. QE report signature, signed by the PCK
+        let qe_report_signature = verify_ecdsa_p256(
+            &pck_leaf_key,
+            &qe_report_cert.qe_report.to_bytes(),
+            &qe_report_cert.qe_report_sig,
+        );
+
+        // 3. QE report data binds the attestation key and QE authentication data
+        let mut hasher = Sha256::new();
+        hasher.update(signature.ak);
+        hasher.update(&qe_report_cert.qe_auth_data);
+        let report_data = qe_report_cert.qe_report.report_data;
+        let qe_report_data = hasher.finalize()[..] == report_data[0..32]
+            && report_data[32..].iter().all(|b| *b == 0);
+
+        Ok(TdxQuoteSignatureResult {
+            quote_signature,
+            qe_report_signature,
+            qe_report_data,
+        })
+    }
+}
+
+fn verify_ecdsa_p256(key: &VerifyingKey, data: &[u8], sig: &[u8; 64]) -> bool {
+    match Signature::from_slice(sig) {
+        Ok(sig) => key.verify(data, &sig).is_ok(),
+        Err(_) => false,
+    }
+}
+
+// get the public key of the PCK leaf certificate carried in QE certification data
+fn get_pck_leaf_key(qe_cert: &TdxQuoteQeCert) -> Result<VerifyingKey, anyhow::Error> {
+    let pck_cert_chain = qe_cert.get_pck_cert_chain()?;
+    let key = pck_cert_chain
+        .get_leaf_cert()
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .raw_bytes();
+    match VerifyingKey::from_sec1_bytes(key) {
+        Ok(key) => Ok(key),
+        Err(e) => Err(anyhow!(
+            "[get_pck_leaf_key] invalid PCK leaf public key: {:?}",
+            e
+        )),
+    }
+}
+
+/***
+    Result of the comparison of an RTMR against the replayed event logs.
+
+    Attributes:
+        rtmr_index: the RTMR index, 0 to 3
+        mr_index: the CC measurement register index used by CCEL, i.e. rtmr_index + 1
+        rtmr: the RTMR value in the quote or TDREPORT
+        replayed: the replayed value, zero if no event log is extended to the RTMR
+        matched: whether rtmr equals replayed
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TdxRtmrResult {
+    pub rtmr_index: u8,
+    pub mr_index: u32,
+    pub rtmr: Vec<u8>,
+    pub replayed: Vec<u8>,
+    pub matched: bool,
+}
+
+/***
+    Result of the verification of the RTMRs against the event logs.
+
+    Attributes:
+        rtmrs: the comparison of each RTMR, ordered by RTMR index
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TdxRtmrVerifyResult {
+    pub rtmrs: Vec<TdxRtmrResult>,
+}
+
+impl TdxRtmrVerifyResult {
+    pub fn is_valid(&self) -> bool {
+        self.rtmrs.iter().all(|rtmr| rtmr.matched)
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TdxRtmrVerifyResult");
+        for rtmr in &self.rtmrs {
+            info!(
+                "RTMR[{}] (MR index {}) matched = {}",
+                rtmr.rtmr_index, rtmr.mr_index, rtmr.matched
+            );
+            info!("    rtmr     = {}", hex::encode(&rtmr.rtmr));
+            info!("    replayed = {}", hex::encode(&rtmr.replayed));
+        }
+    }
+}
+
+/***
+    Compare the RTMR values against the replayed event logs.
+    The SHA384 replay result of each IMR is mapped to the RTMR of its CC measurement
+    register index, i.e. imr_index + 1, see ``get_rtmr_index``. Results of other
+    indexes, e.g. MRTD, are not part of the comparison.
+
+    Args:
+        rtmrs: the values of RTMR 0 to 3
+        replay_results: output of EventLogs::replay()
+
+    Returns:
+        TdxRtmrVerifyResult telling which RTMR mismatches
+*/
+pub fn verify_rtmrs(rtmrs: &[[u8; 48]], replay_results: &[ReplayResult]) -> TdxRtmrVerifyResult {
+    let mut result = TdxRtmrVerifyResult::default();
+    for (rtmr_index, rtmr) in rtmrs.iter().enumerate() {
+        let replayed = replay_results
+            .iter()
+            .filter(|replay_result| {
+                get_rtmr_index(replay_result.imr_index + 1) == Some(rtmr_index as u8)
+            })
+            .flat_map(|replay_result| replay_result.digests.iter())
+            .find(|digest| digest.algo_id == TPM_ALG_SHA384)
+            .map(|digest| digest.hash.clone())
+            .unwrap_or_else(|| vec![0; 48]);
+        result.rtmrs.push(TdxRtmrResult {
+            rtmr_index: rtmr_index as u8,
+            mr_index: rtmr_index as u32 + 1,
+            rtmr: rtmr.to_vec(),
+            matched: replayed == rtmr.to_vec(),
+            replayed,
+        });
+    }
+    result
+}
+
+// Replay the event logs the way TDX extends RTMRs, where IMA extends SHA384 template hashes
+fn replay_rtmrs(eventlogs: Vec<EventLogEntry>) -> Result<Vec<ReplayResult>, anyhow::Error> {
+    let options = ReplayOptions {
+        ima_extend_algo_id: Some(TPM_ALG_SHA384),
+        ..Default::default()
+    };
+    Ok(EventLogs::replay_with_options(eventlogs, &options)?.replay_results)
+}
+
+impl TdxQuote {
+    /***
+        Verify the RTMRs in the TD Quote Body against the event logs, i.e. the output
+        of get_cc_eventlog(), which are replayed to get the RTMR values.
+        IMA events are extended as SHA384 template hashes, see
+        ``EventLogs::replay_with_options``.
+
+        Args:
+            eventlogs: event logs to be replayed
+
+        Returns:
+            TdxRtmrVerifyResult telling which RTMR mismatches
+    */
+    pub fn verify_rtmrs(
+        &self,
+        eventlogs: Vec<EventLogEntry>,
+    ) -> Result<TdxRtmrVerifyResult, anyhow::Error> {
+        let rtmrs = [
+            self.body.rtmr0,
+            self.body.rtmr1,
+            self.body.rtmr2,
+            self.body.rtmr3,
+        ];
+        Ok(verify_rtmrs(&rtmrs, &replay_rtmrs(eventlogs)?))
+    }
+}
+
+impl TDReport {
+    /***
+        Verify the RTMRs in TDINFO against the event logs, as ``TdxQuote::verify_rtmrs``.
+
+        Args:
+            eventlogs: event logs to be replayed
+
+        Returns:
+            TdxRtmrVerifyResult telling which RTMR mismatches
+    */
+    pub fn verify_rtmrs(
+        &self,
+        eventlogs: Vec<EventLogEntry>,
+    ) -> Result<TdxRtmrVerifyResult, anyhow::Error> {
+        Ok(verify_rtmrs(&self.td_info.rtmrs, &replay_rtmrs(eventlogs)?))
+    }
+}
+
+#[cfg(test)]
+mod test_verify_signatures {
+    use super::*;
+
+    const QUOTE_V4: &[u8] = include_bytes!("../../test_data/quote.bin");
+
+    #[test]
+    fn test_verify_signatures_valid_quote() {
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let result = quote.verify_signatures().unwrap();
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_verify_signatures_tampered_body() {
+        let mut data = QUOTE_V4.to_vec();
+        // flip one byte of REPORTDATA
+        data[600] ^= 0xff;
+        let quote = TdxQuote::parse_tdx_quote(data).unwrap();
+        let result = quote.verify_signatures().unwrap();
+        assert!(!result.quote_signature);
+        assert!(result.qe_report_signature);
+        assert!(result.qe_report_data);
+    }
+
+    #[test]
+    fn test_verify_signatures_tampered_ak() {
+        let mut quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let signature = quote.tdx_quote_ecdsa256_sigature.as_mut().unwrap();
+        signature.ak[0] ^= 0xff;
+        let result = quote.verify_signatures().unwrap();
+        assert!(!result.quote_signature);
+        assert!(result.qe_report_signature);
+        assert!(!result.qe_report_data);
+    }
+}
+
+#[cfg(test)]
+mod test_verify_rtmrs {
+    use super::*;
+    use crate::tdx::common::{Tdx, TdxVersion};
+    use sha2::Sha384;
+
+    const QUOTE_V4: &[u8] = include_bytes!("../../test_data/quote.bin");
+    // offset of RTMR0 in the quote, after the header and the TD Quote Body fields before it
+    const QUOTE_RTMR0_OFFSET: usize = 48 + 328;
+    // offset of RTMR0 in TDREPORT, in TDINFO
+    const TDREPORT_RTMR0_OFFSET: usize = 512 + 208;
+
+    fn get_event(imr_index: u32, event_type: u32, byte: u8) -> EventLogEntry {
+        EventLogEntry::TcgImrEvent(TcgImrEvent {
+            imr_index,
+            event_type,
+            digests: vec![TcgDigest {
+                algo_id: TPM_ALG_SHA384,
+                hash: vec![byte; 48],
+            }],
+            event_size: 0,
+            event: Vec::new(),
+            ima_template: None,
+        })
+    }
+
+    fn extend(rtmr: &[u8], byte: u8) -> [u8; 48] {
+        Sha384::digest([rtmr, &[byte; 48]].concat()).into()
+    }
+
+    // event logs of RTMR 0 and RTMR 2 and the expected RTMR 0 to 3
+    fn get_eventlogs() -> (Vec<EventLogEntry>, [[u8; 48]; 4]) {
+        let eventlogs = vec![
+            get_event(0, EV_NO_ACTION, 0xff),
+            get_event(0, EV_POST_CODE, 0x01),
+            get_event(0, EV_POST_CODE, 0x02),
+            get_event(2, EV_IPL, 0x03),
+        ];
+        let rtmr0 = extend(&extend(&[0; 48], 0x01), 0x02);
+        let rtmr2 = extend(&[0; 48], 0x03);
+        (eventlogs, [rtmr0, [0; 48], rtmr2, [0; 48]])
+    }
+
+    #[test]
+    fn test_get_rtmr_index() {
+        assert_eq!(get_rtmr_index(0), None);
+        assert_eq!(get_rtmr_index(1), Some(0));
+        assert_eq!(get_rtmr_index(4), Some(3));
+        assert_eq!(get_rtmr_index(5), None);
+    }
+
+    #[test]
+    fn test_verify_rtmrs_quote() {
+        let (eventlogs, rtmrs) = get_eventlogs();
+        let mut data = QUOTE_V4.to_vec();
+        for (index, rtmr) in rtmrs.iter().enumerate() {
+            let offset = QUOTE_RTMR0_OFFSET + index * 48;
+            data[offset..offset + 48].copy_from_slice(rtmr);
+        }
+        let quote = TdxQuote::parse_tdx_quote(data).unwrap();
+        let result = quote.verify_rtmrs(eventlogs.clone()).unwrap();
+        assert!(result.is_valid());
+        assert_eq!(result.rtmrs.len(), 4);
+        assert_eq!(result.rtmrs[2].mr_index, 3);
+        assert_eq!(result.rtmrs[2].replayed, rtmrs[2].to_vec());
+
+        // the original quote does not match the event logs
+        let quote = TdxQuote::parse_tdx_quote(QUOTE_V4.to_vec()).unwrap();
+        let result = quote.verify_rtmrs(eventlogs).unwrap();
+        assert!(!result.is_valid());
+    }
+
+    #[test]
+    fn test_verify_rtmrs_tdreport() {
+        let (mut eventlogs, rtmrs) = get_eventlogs();
+        let mut data = vec![0; 1024];
+        for (index, rtmr) in rtmrs.iter().enumerate() {
+            let offset = TDREPORT_RTMR0_OFFSET + index * 48;
+            data[offset..offset + 48].copy_from_slice(rtmr);
+        }
+        let report = Tdx::parse_td_report(&data, TdxVersion::TDX_1_5).unwrap();
+        assert!(report.verify_rtmrs(eventlogs.clone()).unwrap().is_valid());
+
+        // an event log missing from the RTMR
+        eventlogs.push(get_event(3, EV_IPL, 0x04));
+        let result = report.verify_rtmrs(eventlogs).unwrap();
+        assert!(!result.is_valid());
+        let matched: Vec<bool> = result.rtmrs.iter().map(|rtmr| rtmr.matched).collect();
+        assert_eq!(matched, vec![true, true, true, false]);
+        assert_eq!(result.rtmrs[3].replayed, extend(&[0; 48], 0x04).to_vec());
+    }
+}
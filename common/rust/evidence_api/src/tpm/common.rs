@@ -0,0 +1,21 @@
+/***
+    TPM 2.0 constants defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_TPM2_r1p59_Part2_Structures_pub.pdf
+*/
+// TPM_GENERATED: value of the magic field of TPMS_ATTEST
+pub const TPM_GENERATED_VALUE: u32 = 0xff544347;
+
+// TPM_ST: structure tags
+pub const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+pub const TPM_ST_SESSIONS: u16 = 0x8002;
+pub const TPM_ST_ATTEST_QUOTE: u16 = 0x8018;
+
+// TPM_RC_SUCCESS: response code of a successful command
+pub const TPM_RC_SUCCESS: u32 = 0;
+
+// number of PCRs of a PC Client TPM
+pub const TPM_PCR_COUNT: u32 = 24;
+
+// TPM_ECC_CURVE: curve identifiers
+pub const TPM_ECC_NIST_P256: u16 = 0x0003;
+pub const TPM_ECC_NIST_P384: u16 = 0x0004;
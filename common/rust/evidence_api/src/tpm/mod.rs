@@ -1 +1,3 @@
+pub mod common;
 pub mod quote;
+pub mod verify;
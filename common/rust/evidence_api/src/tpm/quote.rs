@@ -1,18 +1,461 @@
 use crate::api::ParseCcReport;
 use crate::api_data::CcReport;
+use crate::binary_blob::BinaryReader;
+use crate::tcg::*;
+use crate::tpm::common::*;
+use anyhow::anyhow;
+use log::info;
 
-// return of API parse_cc_report()
-pub struct TpmQuote {}
+/***
+    TPMS_CLOCK_INFO defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_TPM2_r1p59_Part2_Structures_pub.pdf
+    Table 120 — Definition of TPMS_CLOCK_INFO Structure
+    Definition:
+    typedef struct {
+        UINT64      clock;
+        UINT32      resetCount;
+        UINT32      restartCount;
+        TPMI_YES_NO safe;
+    } TPMS_CLOCK_INFO;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmsClockInfo {
+    pub clock: u64,
+    pub reset_count: u32,
+    pub restart_count: u32,
+    pub safe: bool,
+}
+
+/***
+    TPMS_PCR_SELECTION defined at
+    Table 94 — Definition of TPMS_PCR_SELECTION Structure
+    Definition:
+    typedef struct {
+        TPMI_ALG_HASH hash;
+        UINT8         sizeofSelect;
+        BYTE          pcrSelect[sizeofSelect];
+    } TPMS_PCR_SELECTION;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmsPcrSelection {
+    pub hash: u16,
+    pub pcr_select: Vec<u8>,
+}
+
+impl TpmsPcrSelection {
+    // Get the selected PCR indexes in ascending order
+    pub fn get_pcr_indexes(&self) -> Vec<u32> {
+        let mut indexes = Vec::new();
+        for (byte_index, byte) in self.pcr_select.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (1 << bit) != 0 {
+                    indexes.push((byte_index * 8 + bit) as u32);
+                }
+            }
+        }
+        indexes
+    }
+}
+
+/***
+    TPMS_QUOTE_INFO defined at
+    Table 123 — Definition of TPMS_QUOTE_INFO Structure
+    Definition:
+    typedef struct {
+        TPML_PCR_SELECTION pcrSelect;
+        TPM2B_DIGEST       pcrDigest;
+    } TPMS_QUOTE_INFO;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmsQuoteInfo {
+    pub pcr_select: Vec<TpmsPcrSelection>,
+    pub pcr_digest: Vec<u8>,
+}
+
+/***
+    TPMS_ATTEST defined at
+    Table 131 — Definition of TPMS_ATTEST Structure
+    Definition:
+    typedef struct {
+        TPM_GENERATED   magic;
+        TPMI_ST_ATTEST  type;
+        TPM2B_NAME      qualifiedSigner;
+        TPM2B_DATA      extraData;
+        TPMS_CLOCK_INFO clockInfo;
+        UINT64          firmwareVersion;
+        TPMU_ATTEST     attested;
+    } TPMS_ATTEST;
+    Only TPM_ST_ATTEST_QUOTE is supported, where attested is a TPMS_QUOTE_INFO.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmsAttest {
+    pub magic: u32,
+    pub attest_type: u16,
+    pub qualified_signer: Vec<u8>,
+    pub extra_data: Vec<u8>,
+    pub clock_info: TpmsClockInfo,
+    pub firmware_version: u64,
+    pub quote_info: TpmsQuoteInfo,
+}
+
+impl TpmsAttest {
+    pub fn new(data: &[u8]) -> Result<TpmsAttest, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let magic = reader.read_u32_be()?;
+        if magic != TPM_GENERATED_VALUE {
+            return Err(anyhow!("[TpmsAttest] invalid magic: {:#x}", magic));
+        }
+        let attest_type = reader.read_u16_be()?;
+        if attest_type != TPM_ST_ATTEST_QUOTE {
+            return Err(anyhow!(
+                "[TpmsAttest] unsupported attestation type: {:#x}",
+                attest_type
+            ));
+        }
+        let qualified_signer = read_tpm2b(&mut reader)?;
+        let extra_data = read_tpm2b(&mut reader)?;
+        let clock_info = TpmsClockInfo {
+            clock: reader.read_u64_be()?,
+            reset_count: reader.read_u32_be()?,
+            restart_count: reader.read_u32_be()?,
+            safe: reader.read_u8()? != 0,
+        };
+        let firmware_version = reader.read_u64_be()?;
+
+        let count = reader.read_u32_be()?;
+        let mut pcr_select = Vec::new();
+        for _ in 0..count {
+            let hash = reader.read_u16_be()?;
+            let size = reader.read_u8()?;
+            pcr_select.push(TpmsPcrSelection {
+                hash,
+                pcr_select: reader.read_bytes(size as usize)?.to_vec(),
+            });
+        }
+        let pcr_digest = read_tpm2b(&mut reader)?;
+
+        Ok(TpmsAttest {
+            magic,
+            attest_type,
+            qualified_signer,
+            extra_data,
+            clock_info,
+            firmware_version,
+            quote_info: TpmsQuoteInfo {
+                pcr_select,
+                pcr_digest,
+            },
+        })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TpmsAttest");
+        info!("magic = {:#x}", self.magic);
+        info!("type = {:#x}", self.attest_type);
+        info!("qualified_signer = {:02X?}", self.qualified_signer);
+        info!("extra_data = {:02X?}", self.extra_data);
+        info!("clock_info = {:?}", self.clock_info);
+        info!("firmware_version = {:#x}", self.firmware_version);
+        for selection in &self.quote_info.pcr_select {
+            info!(
+                "pcr_select = {} {:?}",
+                TcgDigest {
+                    algo_id: selection.hash,
+                    hash: Vec::new()
+                }
+                .get_algorithm_id_str(),
+                selection.get_pcr_indexes()
+            );
+        }
+        info!("pcr_digest = {:02X?}", self.quote_info.pcr_digest);
+    }
+}
+
+/***
+    TPMU_SIGNATURE defined at
+    Table 186 — Definition of TPMU_SIGNATURE Union
+    The variant is selected by the sigAlg of TPMT_SIGNATURE.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum TpmuSignature {
+    // TPMS_SIGNATURE_RSA for TPM_ALG_RSASSA and TPM_ALG_RSAPSS
+    Rsa {
+        hash: u16,
+        sig: Vec<u8>,
+    },
+    // TPMS_SIGNATURE_ECC for TPM_ALG_ECDSA, TPM_ALG_ECDAA, TPM_ALG_SM2 and TPM_ALG_ECSCHNORR
+    Ecc {
+        hash: u16,
+        signature_r: Vec<u8>,
+        signature_s: Vec<u8>,
+    },
+    // TPMT_HA for TPM_ALG_HMAC
+    Hmac {
+        hash: u16,
+        digest: Vec<u8>,
+    },
+    // TPM_ALG_NULL
+    Null,
+}
+
+/***
+    TPMT_SIGNATURE defined at
+    Table 187 — Definition of TPMT_SIGNATURE Structure
+    Definition:
+    typedef struct {
+        TPMI_ALG_SIG_SCHEME sigAlg;
+        TPMU_SIGNATURE      [sigAlg]signature;
+    } TPMT_SIGNATURE;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmtSignature {
+    pub sig_alg: u16,
+    pub signature: TpmuSignature,
+}
+
+impl TpmtSignature {
+    fn unmarshal(reader: &mut BinaryReader) -> Result<TpmtSignature, anyhow::Error> {
+        let sig_alg = reader.read_u16_be()?;
+        let signature = match sig_alg {
+            TPM_ALG_RSASSA | TPM_ALG_RSAPSS => TpmuSignature::Rsa {
+                hash: reader.read_u16_be()?,
+                sig: read_tpm2b(reader)?,
+            },
+            TPM_ALG_ECDSA | TPM_ALG_ECDAA | TPM_ALG_SM2 | TPM_ALG_ECSCHNORR => TpmuSignature::Ecc {
+                hash: reader.read_u16_be()?,
+                signature_r: read_tpm2b(reader)?,
+                signature_s: read_tpm2b(reader)?,
+            },
+            TPM_ALG_HMAC => {
+                let hash = reader.read_u16_be()?;
+                let size = TcgDigest::get_digest_size_from_algorithm_id(hash);
+                if size == 0 {
+                    return Err(anyhow!("[TpmtSignature] unknown HMAC hash: {:#x}", hash));
+                }
+                TpmuSignature::Hmac {
+                    hash,
+                    digest: reader.read_bytes(size as usize)?.to_vec(),
+                }
+            }
+            TPM_ALG_NULL => TpmuSignature::Null,
+            _ => {
+                return Err(anyhow!(
+                    "[TpmtSignature] unsupported signature algorithm: {:#x}",
+                    sig_alg
+                ))
+            }
+        };
+        Ok(TpmtSignature { sig_alg, signature })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TpmtSignature");
+        info!("sig_alg = {:#x}", self.sig_alg);
+        info!("signature = {:02X?}", self.signature);
+    }
+}
+
+// read a TPM2B structure, i.e. UINT16 size followed by size bytes
+pub(crate) fn read_tpm2b(reader: &mut BinaryReader) -> Result<Vec<u8>, anyhow::Error> {
+    let size = reader.read_u16_be()?;
+    Ok(reader.read_bytes(size as usize)?.to_vec())
+}
+
+/***
+    TPM Quote, i.e. the response parameters of TPM2_Quote.
+    Definition reference:
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_TPM2_r1p59_Part3_Commands_pub.pdf
+    Table 91 — TPM2_Quote Response
+        Type            Name            Description
+        TPM_ST          tag             see clause 6
+        UINT32          responseSize
+        TPM_RC          responseCode
+        TPM2B_ATTEST    quoted          the quoted information
+        TPMT_SIGNATURE  signature       the signature over quoted
+    The raw quote could be either the full response or only quoted followed by signature.
+
+    Attributes:
+        quoted: raw TPMS_ATTEST data, i.e. the data covered by the signature.
+        attest: the parsed TPMS_ATTEST.
+        signature: the parsed TPMT_SIGNATURE.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmQuote {
+    pub quoted: Vec<u8>,
+    pub attest: TpmsAttest,
+    pub signature: TpmtSignature,
+}
 
 impl TpmQuote {
-    pub fn parse_tpm_quote(_quote: Vec<u8>) -> Result<TpmQuote, anyhow::Error> {
-        todo!()
+    pub fn parse_tpm_quote(quote: Vec<u8>) -> Result<TpmQuote, anyhow::Error> {
+        let mut reader = BinaryReader::new(&quote);
+
+        // strip the response header of TPM2_Quote if present
+        if quote.len() >= 10 {
+            let tag = u16::from_be_bytes(quote[0..2].try_into().unwrap());
+            let size = u32::from_be_bytes(quote[2..6].try_into().unwrap());
+            if (tag == TPM_ST_NO_SESSIONS || tag == TPM_ST_SESSIONS) && size as usize == quote.len()
+            {
+                reader.read_bytes(6)?;
+                let rc = reader.read_u32_be()?;
+                if rc != TPM_RC_SUCCESS {
+                    return Err(anyhow!("[parse_tpm_quote] TPM2_Quote failed: {:#x}", rc));
+                }
+                if tag == TPM_ST_SESSIONS {
+                    // parameterSize
+                    reader.read_u32_be()?;
+                }
+            }
+        }
+
+        let quoted = read_tpm2b(&mut reader)?;
+        let attest = TpmsAttest::new(&quoted)?;
+        let signature = TpmtSignature::unmarshal(&mut reader)?;
+
+        Ok(TpmQuote {
+            quoted,
+            attest,
+            signature,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TpmQuote");
+        self.attest.show();
+        self.signature.show();
     }
 }
 
 // API function parses raw cc report to TpmQuote struct
 impl ParseCcReport<TpmQuote> for CcReport {
-    fn parse_cc_report(_report: Vec<u8>) -> Result<TpmQuote, anyhow::Error> {
-        todo!()
+    fn parse_cc_report(report: Vec<u8>) -> Result<TpmQuote, anyhow::Error> {
+        match TpmQuote::parse_tpm_quote(report) {
+            Ok(tpm_quote) => Ok(tpm_quote),
+            Err(e) => Err(anyhow!("[parse_cc_report] error parse tpm quote: {:?}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_parse_tpm_quote {
+    use super::*;
+
+    fn build_attest() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&TPM_GENERATED_VALUE.to_be_bytes());
+        data.extend_from_slice(&TPM_ST_ATTEST_QUOTE.to_be_bytes());
+        // qualifiedSigner
+        data.extend_from_slice(&[0x00, 0x02, 0xaa, 0xbb]);
+        // extraData
+        data.extend_from_slice(&[0x00, 0x04, 0x01, 0x02, 0x03, 0x04]);
+        // clockInfo
+        data.extend_from_slice(&0x1122334455667788u64.to_be_bytes());
+        data.extend_from_slice(&5u32.to_be_bytes());
+        data.extend_from_slice(&6u32.to_be_bytes());
+        data.push(1);
+        // firmwareVersion
+        data.extend_from_slice(&0x2000000000000000u64.to_be_bytes());
+        // pcrSelect: sha256 with PCR 0, 1, 2, 10
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(&[0x03, 0x07, 0x04, 0x00]);
+        // pcrDigest
+        data.extend_from_slice(&32u16.to_be_bytes());
+        data.extend_from_slice(&[0x5a; 32]);
+        data
+    }
+
+    fn build_quote(attest: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(attest.len() as u16).to_be_bytes());
+        data.extend_from_slice(attest);
+        data.extend_from_slice(&TPM_ALG_ECDSA.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x02, 0x11, 0x22]);
+        data.extend_from_slice(&[0x00, 0x02, 0x33, 0x44]);
+        data
+    }
+
+    #[test]
+    fn test_parse_quoted_and_signature() {
+        let attest = build_attest();
+        let quote = TpmQuote::parse_tpm_quote(build_quote(&attest)).unwrap();
+        assert_eq!(quote.quoted, attest);
+        assert_eq!(quote.attest.qualified_signer, vec![0xaa, 0xbb]);
+        assert_eq!(quote.attest.extra_data, vec![0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(quote.attest.clock_info.clock, 0x1122334455667788);
+        assert_eq!(quote.attest.clock_info.reset_count, 5);
+        assert_eq!(quote.attest.clock_info.restart_count, 6);
+        assert!(quote.attest.clock_info.safe);
+        assert_eq!(quote.attest.firmware_version, 0x2000000000000000);
+        assert_eq!(quote.attest.quote_info.pcr_select.len(), 1);
+        assert_eq!(quote.attest.quote_info.pcr_select[0].hash, TPM_ALG_SHA256);
+        assert_eq!(
+            quote.attest.quote_info.pcr_select[0].get_pcr_indexes(),
+            vec![0, 1, 2, 10]
+        );
+        assert_eq!(quote.attest.quote_info.pcr_digest, vec![0x5a; 32]);
+        assert_eq!(
+            quote.signature,
+            TpmtSignature {
+                sig_alg: TPM_ALG_ECDSA,
+                signature: TpmuSignature::Ecc {
+                    hash: TPM_ALG_SHA256,
+                    signature_r: vec![0x11, 0x22],
+                    signature_s: vec![0x33, 0x44],
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_quote_response() {
+        let attest = build_attest();
+        let params = build_quote(&attest);
+        let mut response = Vec::new();
+        response.extend_from_slice(&TPM_ST_SESSIONS.to_be_bytes());
+        response.extend_from_slice(&((14 + params.len() + 5) as u32).to_be_bytes());
+        response.extend_from_slice(&TPM_RC_SUCCESS.to_be_bytes());
+        response.extend_from_slice(&(params.len() as u32).to_be_bytes());
+        response.extend_from_slice(&params);
+        // empty password session
+        response.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x00]);
+        let quote = TpmQuote::parse_tpm_quote(response).unwrap();
+        assert_eq!(quote.quoted, attest);
+        assert_eq!(quote.signature.sig_alg, TPM_ALG_ECDSA);
+    }
+
+    #[test]
+    fn test_parse_rsa_signature() {
+        let attest = build_attest();
+        let mut data = Vec::new();
+        data.extend_from_slice(&(attest.len() as u16).to_be_bytes());
+        data.extend_from_slice(&attest);
+        data.extend_from_slice(&TPM_ALG_RSASSA.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x03, 0x01, 0x02, 0x03]);
+        let quote = TpmQuote::parse_tpm_quote(data).unwrap();
+        assert_eq!(
+            quote.signature.signature,
+            TpmuSignature::Rsa {
+                hash: TPM_ALG_SHA256,
+                sig: vec![0x01, 0x02, 0x03]
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut attest = build_attest();
+        attest[0] = 0;
+        assert!(TpmQuote::parse_tpm_quote(build_quote(&attest)).is_err());
+    }
+
+    #[test]
+    fn test_truncated_quote() {
+        let quote = build_quote(&build_attest());
+        for len in [0, 1, 10, quote.len() - 1] {
+            assert!(TpmQuote::parse_tpm_quote(quote[..len].to_vec()).is_err());
+        }
     }
 }
@@ -0,0 +1,703 @@
+use crate::api_data::ReplayResult;
+use crate::binary_blob::BinaryReader;
+use crate::eventlog::{compute_digest, EventLogs};
+use crate::tcg::*;
+use crate::tpm::common::*;
+use crate::tpm::quote::*;
+use anyhow::anyhow;
+use log::info;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pkcs1v15Sign, Pss, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+
+/***
+    TPMU_PUBLIC_PARMS defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_TPM2_r1p59_Part2_Structures_pub.pdf
+    Table 202 — Definition of TPMU_PUBLIC_PARMS Union
+    Only the RSA and ECC parameters are supported, the symmetric definition and the
+    KDF scheme are skipped as they are not relevant for signature verification.
+
+    Attributes:
+        scheme: the signing scheme of the key, TPM_ALG_NULL if not restricted.
+        scheme_hash: the hash algorithm of the signing scheme.
+        key_bits / exponent: RSA key size and public exponent.
+        curve_id: TPM_ECC_CURVE of the ECC key.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum TpmuPublicParms {
+    Rsa {
+        scheme: u16,
+        scheme_hash: u16,
+        key_bits: u16,
+        exponent: u32,
+    },
+    Ecc {
+        scheme: u16,
+        scheme_hash: u16,
+        curve_id: u16,
+    },
+}
+
+/***
+    TPMU_PUBLIC_ID defined at
+    Table 199 — Definition of TPMU_PUBLIC_ID Union
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub enum TpmuPublicId {
+    Rsa { modulus: Vec<u8> },
+    Ecc { x: Vec<u8>, y: Vec<u8> },
+}
+
+/***
+    TPMT_PUBLIC defined at
+    Table 204 — Definition of TPMT_PUBLIC Structure
+    Definition:
+    typedef struct {
+        TPMI_ALG_PUBLIC   type;
+        TPMI_ALG_HASH     nameAlg;
+        TPMA_OBJECT       objectAttributes;
+        TPM2B_DIGEST      authPolicy;
+        TPMU_PUBLIC_PARMS [type]parameters;
+        TPMU_PUBLIC_ID    [type]unique;
+    } TPMT_PUBLIC;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct TpmtPublic {
+    pub key_type: u16,
+    pub name_alg: u16,
+    pub object_attributes: u32,
+    pub auth_policy: Vec<u8>,
+    pub parameters: TpmuPublicParms,
+    pub unique: TpmuPublicId,
+}
+
+impl TpmtPublic {
+    /***
+        Parse the public area of an attestation key.
+
+        Args:
+            data: TPM2B_PUBLIC, or TPMT_PUBLIC without the size prefix
+
+        Returns:
+            The parsed TpmtPublic or error if the data is malformed
+    */
+    pub fn new(data: &[u8]) -> Result<TpmtPublic, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        if data.len() > 2 && u16::from_be_bytes([data[0], data[1]]) as usize == data.len() - 2 {
+            reader.read_u16_be()?;
+        }
+
+        let key_type = reader.read_u16_be()?;
+        let name_alg = reader.read_u16_be()?;
+        let object_attributes = reader.read_u32_be()?;
+        let auth_policy = read_tpm2b(&mut reader)?;
+
+        // TPMT_SYM_DEF_OBJECT symmetric
+        let symmetric = reader.read_u16_be()?;
+        if symmetric != TPM_ALG_NULL {
+            // keyBits and mode
+            reader.read_u32_be()?;
+        }
+
+        let (parameters, unique) = match key_type {
+            TPM_ALG_RSA => {
+                let (scheme, scheme_hash) = read_scheme(&mut reader)?;
+                let key_bits = reader.read_u16_be()?;
+                let exponent = reader.read_u32_be()?;
+                (
+                    TpmuPublicParms::Rsa {
+                        scheme,
+                        scheme_hash,
+                        key_bits,
+                        exponent,
+                    },
+                    TpmuPublicId::Rsa {
+                        modulus: read_tpm2b(&mut reader)?,
+                    },
+                )
+            }
+            TPM_ALG_ECC => {
+                let (scheme, scheme_hash) = read_scheme(&mut reader)?;
+                if scheme == TPM_ALG_ECDAA {
+                    // count
+                    reader.read_u16_be()?;
+                }
+                let curve_id = reader.read_u16_be()?;
+                // TPMT_KDF_SCHEME kdf
+                read_scheme(&mut reader)?;
+                (
+                    TpmuPublicParms::Ecc {
+                        scheme,
+                        scheme_hash,
+                        curve_id,
+                    },
+                    TpmuPublicId::Ecc {
+                        x: read_tpm2b(&mut reader)?,
+                        y: read_tpm2b(&mut reader)?,
+                    },
+                )
+            }
+            _ => {
+                return Err(anyhow!(
+                    "[TpmtPublic] unsupported key type: {:#x}",
+                    key_type
+                ))
+            }
+        };
+
+        Ok(TpmtPublic {
+            key_type,
+            name_alg,
+            object_attributes,
+            auth_policy,
+            parameters,
+            unique,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TpmtPublic");
+        info!("type = {:#x}", self.key_type);
+        info!("name_alg = {:#x}", self.name_alg);
+        info!("object_attributes = {:#x}", self.object_attributes);
+        info!("auth_policy = {:02X?}", self.auth_policy);
+        info!("parameters = {:?}", self.parameters);
+        info!("unique = {:02X?}", self.unique);
+    }
+}
+
+// read a scheme structure, i.e. the scheme algorithm followed by its hash if not TPM_ALG_NULL
+fn read_scheme(reader: &mut BinaryReader) -> Result<(u16, u16), anyhow::Error> {
+    let scheme = reader.read_u16_be()?;
+    if scheme == TPM_ALG_NULL {
+        return Ok((scheme, TPM_ALG_NULL));
+    }
+    Ok((scheme, reader.read_u16_be()?))
+}
+
+/***
+    Result of the verification of a TPM quote.
+
+    Attributes:
+        signature: the quote signature over TPMS_ATTEST verifies against the attestation key.
+        pcr_digest: the pcrDigest in TPMS_QUOTE_INFO equals the digest of the selected PCR
+                    values produced by the event log replay.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TpmQuoteVerifyResult {
+    pub signature: bool,
+    pub pcr_digest: bool,
+}
+
+impl TpmQuoteVerifyResult {
+    pub fn is_valid(&self) -> bool {
+        self.signature && self.pcr_digest
+    }
+
+    pub fn show(&self) {
+        info!("show the data of TpmQuoteVerifyResult");
+        info!("signature = {}", self.signature);
+        info!("pcr_digest = {}", self.pcr_digest);
+    }
+}
+
+impl TpmQuote {
+    /***
+        Verify the quote against the attestation key and the event logs, i.e. the
+        output of get_cc_eventlog(), which are replayed to get the PCR values.
+
+        Args:
+            ak_pub: TPM2B_PUBLIC of the attestation key
+            eventlogs: event logs to be replayed
+
+        Returns:
+            TpmQuoteVerifyResult telling which check failed, or error if the quote or
+            the key is not supported
+    */
+    pub fn verify(
+        &self,
+        ak_pub: &[u8],
+        eventlogs: Vec<EventLogEntry>,
+    ) -> Result<TpmQuoteVerifyResult, anyhow::Error> {
+        let ak_pub = TpmtPublic::new(ak_pub)?;
+        let replay_results = EventLogs::replay(eventlogs)?;
+        Ok(TpmQuoteVerifyResult {
+            signature: self.verify_signature(&ak_pub)?,
+            pcr_digest: self.verify_pcr_digest(&replay_results)?,
+        })
+    }
+
+    /***
+        Verify the signature over the quoted TPMS_ATTEST.
+        Supported schemes are TPM_ALG_RSASSA, TPM_ALG_RSAPSS and TPM_ALG_ECDSA with
+        NIST P-256 or P-384.
+
+        Args:
+            ak_pub: public area of the attestation key
+
+        Returns:
+            true if the signature is valid
+    */
+    pub fn verify_signature(&self, ak_pub: &TpmtPublic) -> Result<bool, anyhow::Error> {
+        match (
+            &self.signature.signature,
+            &ak_pub.parameters,
+            &ak_pub.unique,
+        ) {
+            (
+                TpmuSignature::Rsa { hash, sig },
+                TpmuPublicParms::Rsa { exponent, .. },
+                TpmuPublicId::Rsa { modulus },
+            ) => {
+                let exponent = if *exponent == 0 { 65537 } else { *exponent };
+                let key = match RsaPublicKey::new(
+                    BigUint::from_bytes_be(modulus),
+                    BigUint::from(exponent),
+                ) {
+                    Ok(key) => key,
+                    Err(e) => return Err(anyhow!("[verify_signature] invalid RSA key: {:?}", e)),
+                };
+                let digest = compute_digest(*hash, &self.quoted)?;
+                match self.signature.sig_alg {
+                    TPM_ALG_RSASSA => Ok(key.verify(get_pkcs1v15(*hash)?, &digest, sig).is_ok()),
+                    TPM_ALG_RSAPSS => {
+                        // the salt is the digest size for FIPS compliant TPMs, or the
+                        // largest size allowed by the key otherwise
+                        let em_len = (key.n().bits() + 6) / 8;
+                        let max_salt = em_len.saturating_sub(digest.len() + 2);
+                        Ok([digest.len(), max_salt].iter().any(|salt| {
+                            get_pss(*hash, *salt)
+                                .map(|pss| key.verify(pss, &digest, sig).is_ok())
+                                .unwrap_or(false)
+                        }))
+                    }
+                    _ => Err(anyhow!(
+                        "[verify_signature] unsupported RSA signature scheme: {:#x}",
+                        self.signature.sig_alg
+                    )),
+                }
+            }
+            (
+                TpmuSignature::Ecc {
+                    hash,
+                    signature_r,
+                    signature_s,
+                },
+                TpmuPublicParms::Ecc { curve_id, .. },
+                TpmuPublicId::Ecc { x, y },
+            ) => {
+                if self.signature.sig_alg != TPM_ALG_ECDSA {
+                    return Err(anyhow!(
+                        "[verify_signature] unsupported ECC signature scheme: {:#x}",
+                        self.signature.sig_alg
+                    ));
+                }
+                let digest = compute_digest(*hash, &self.quoted)?;
+                let size = match *curve_id {
+                    TPM_ECC_NIST_P256 => 32,
+                    TPM_ECC_NIST_P384 => 48,
+                    _ => {
+                        return Err(anyhow!(
+                            "[verify_signature] unsupported ECC curve: {:#x}",
+                            curve_id
+                        ))
+                    }
+                };
+                let point = [&[0x04], &pad(x, size)[..], &pad(y, size)[..]].concat();
+                let sig = [pad(signature_r, size), pad(signature_s, size)].concat();
+                if *curve_id == TPM_ECC_NIST_P256 {
+                    let key = match p256::ecdsa::VerifyingKey::from_sec1_bytes(&point) {
+                        Ok(key) => key,
+                        Err(_) => return Ok(false),
+                    };
+                    Ok(match p256::ecdsa::Signature::from_slice(&sig) {
+                        Ok(sig) => key.verify_prehash(&digest, &sig).is_ok(),
+                        Err(_) => false,
+                    })
+                } else {
+                    let key = match p384::ecdsa::VerifyingKey::from_sec1_bytes(&point) {
+                        Ok(key) => key,
+                        Err(_) => return Ok(false),
+                    };
+                    Ok(match p384::ecdsa::Signature::from_slice(&sig) {
+                        Ok(sig) => key.verify_prehash(&digest, &sig).is_ok(),
+                        Err(_) => false,
+                    })
+                }
+            }
+            _ => Err(anyhow!(
+                "[verify_signature] signature algorithm {:#x} does not match key type {:#x}",
+                self.signature.sig_alg,
+                ak_pub.key_type
+            )),
+        }
+    }
+
+    /***
+        Compute the digest of the PCR values selected by the quote, using the hash
+        algorithm of the signature scheme as TPM2_Quote does.
+        PCRs not found in the replay results are taken as never extended, i.e. all 0xFF for
+        the DRTM PCRs 17 to 22 and zero for the others.
+
+        Args:
+            replay_results: output of EventLogs::replay()
+
+        Returns:
+            The expected pcrDigest
+    */
+    pub fn compute_pcr_digest(
+        &self,
+        replay_results: &[ReplayResult],
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let hash = match &self.signature.signature {
+            TpmuSignature::Rsa { hash, .. }
+            | TpmuSignature::Ecc { hash, .. }
+            | TpmuSignature::Hmac { hash, .. } => *hash,
+            TpmuSignature::Null => {
+                return Err(anyhow!(
+                    "[compute_pcr_digest] unsigned quote has no digest algorithm"
+                ))
+            }
+        };
+
+        let mut pcr_values = Vec::new();
+        for selection in &self.attest.quote_info.pcr_select {
+            let digest_size = TcgDigest::get_digest_size_from_algorithm_id(selection.hash);
+            if digest_size == 0 {
+                return Err(anyhow!(
+                    "[compute_pcr_digest] unsupported PCR bank: {:#x}",
+                    selection.hash
+                ));
+            }
+            for pcr_index in selection.get_pcr_indexes() {
+                let value = replay_results
+                    .iter()
+                    .filter(|result| result.imr_index == pcr_index)
+                    .flat_map(|result| result.digests.iter())
+                    .find(|digest| digest.algo_id == selection.hash)
+                    .map(|digest| digest.hash.clone())
+                    .unwrap_or_else(|| vec![get_pcr_reset_value(pcr_index); digest_size as usize]);
+                pcr_values.extend_from_slice(&value);
+            }
+        }
+        compute_digest(hash, &pcr_values)
+    }
+
+    /***
+        Check the pcrDigest of the quote against the replayed PCR values.
+
+        Args:
+            replay_results: output of EventLogs::replay()
+
+        Returns:
+            true if pcrDigest matches
+    */
+    pub fn verify_pcr_digest(
+        &self,
+        replay_results: &[ReplayResult],
+    ) -> Result<bool, anyhow::Error> {
+        Ok(self.compute_pcr_digest(replay_results)? == self.attest.quote_info.pcr_digest)
+    }
+}
+
+fn get_pkcs1v15(algo_id: u16) -> Result<Pkcs1v15Sign, anyhow::Error> {
+    match algo_id {
+        TPM_ALG_SHA1 => Ok(Pkcs1v15Sign::new::<Sha1>()),
+        TPM_ALG_SHA256 => Ok(Pkcs1v15Sign::new::<Sha256>()),
+        TPM_ALG_SHA384 => Ok(Pkcs1v15Sign::new::<Sha384>()),
+        TPM_ALG_SHA512 => Ok(Pkcs1v15Sign::new::<Sha512>()),
+        _ => Err(anyhow!(
+            "[get_pkcs1v15] unsupported hash algorithm: {:#x}",
+            algo_id
+        )),
+    }
+}
+
+fn get_pss(algo_id: u16, salt_len: usize) -> Result<Pss, anyhow::Error> {
+    match algo_id {
+        TPM_ALG_SHA1 => Ok(Pss::new_with_salt::<Sha1>(salt_len)),
+        TPM_ALG_SHA256 => Ok(Pss::new_with_salt::<Sha256>(salt_len)),
+        TPM_ALG_SHA384 => Ok(Pss::new_with_salt::<Sha384>(salt_len)),
+        TPM_ALG_SHA512 => Ok(Pss::new_with_salt::<Sha512>(salt_len)),
+        _ => Err(anyhow!(
+            "[get_pss] unsupported hash algorithm: {:#x}",
+            algo_id
+        )),
+    }
+}
+
+// left pad the big endian integer to the given size
+fn pad(data: &[u8], size: usize) -> Vec<u8> {
+    if data.len() >= size {
+        return data.to_vec();
+    }
+    [vec![0; size - data.len()], data.to_vec()].concat()
+}
+
+// Value of the PCR bytes after TPM reset, the DRTM PCRs 17 to 22 are reset to all 0xFF
+fn get_pcr_reset_value(pcr_index: u32) -> u8 {
+    match pcr_index {
+        17..=22 => 0xff,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod test_verify_tpm_quote {
+    use super::*;
+    use p256::ecdsa::signature::hazmat::PrehashSigner;
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::rand_core::{CryptoRng, Error, RngCore};
+    use rsa::RsaPrivateKey;
+    use sha2::Digest;
+
+    // deterministic random source used to produce RSA-PSS salts in tests
+    struct TestRng(u8);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut buf = [0; 4];
+            self.fill_bytes(&mut buf);
+            u32::from_le_bytes(buf)
+        }
+        fn next_u64(&mut self) -> u64 {
+            let mut buf = [0; 8];
+            self.fill_bytes(&mut buf);
+            u64::from_le_bytes(buf)
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for b in dest.iter_mut() {
+                self.0 = self.0.wrapping_add(1);
+                *b = self.0;
+            }
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    fn get_eventlogs() -> Vec<EventLogEntry> {
+        let mut eventlogs = Vec::new();
+        for (imr_index, byte) in [(0, 0x11), (1, 0x22), (0, 0x33)] {
+            eventlogs.push(EventLogEntry::TcgImrEvent(TcgImrEvent {
+                imr_index,
+                event_type: 0x80000008,
+                digests: vec![TcgDigest {
+                    algo_id: TPM_ALG_SHA256,
+                    hash: vec![byte; 32],
+                }],
+                event_size: 0,
+                event: Vec::new(),
+                ima_template: None,
+            }));
+        }
+        eventlogs
+    }
+
+    fn get_attest(pcr_digest: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&TPM_GENERATED_VALUE.to_be_bytes());
+        data.extend_from_slice(&TPM_ST_ATTEST_QUOTE.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x00]);
+        data.extend_from_slice(&[0x00, 0x04, 0xde, 0xad, 0xbe, 0xef]);
+        data.extend_from_slice(&[0; 17]);
+        data.extend_from_slice(&[0; 8]);
+        // sha256 bank with PCR 0, 1, 2
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(&[0x03, 0x07, 0x00, 0x00]);
+        data.extend_from_slice(&(pcr_digest.len() as u16).to_be_bytes());
+        data.extend_from_slice(pcr_digest);
+        data
+    }
+
+    fn get_expected_pcr_digest() -> Vec<u8> {
+        let mut pcr0 = vec![0; 32];
+        for byte in [0x11, 0x33] {
+            pcr0 = Sha256::digest([pcr0, vec![byte; 32]].concat()).to_vec();
+        }
+        let pcr1 = Sha256::digest([vec![0; 32], vec![0x22; 32]].concat()).to_vec();
+        Sha256::digest([pcr0, pcr1, vec![0; 32]].concat()).to_vec()
+    }
+
+    fn get_quote(pcr_digest: &[u8], signature: TpmtSignature) -> TpmQuote {
+        let quoted = get_attest(pcr_digest);
+        TpmQuote {
+            attest: TpmsAttest::new(&quoted).unwrap(),
+            quoted,
+            signature,
+        }
+    }
+
+    fn get_tpm2b_public(key_type: u16, scheme: u16, tail: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&key_type.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(&0x00050072u32.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x00]);
+        data.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+        data.extend_from_slice(&scheme.to_be_bytes());
+        data.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        data.extend_from_slice(tail);
+        [(data.len() as u16).to_be_bytes().to_vec(), data].concat()
+    }
+
+    fn get_ecc_quote(pcr_digest: &[u8]) -> (TpmQuote, Vec<u8>) {
+        let key = p256::ecdsa::SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let mut tail = Vec::new();
+        tail.extend_from_slice(&TPM_ECC_NIST_P256.to_be_bytes());
+        tail.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+        tail.extend_from_slice(&[0x00, 0x20]);
+        tail.extend_from_slice(point.x().unwrap());
+        tail.extend_from_slice(&[0x00, 0x20]);
+        tail.extend_from_slice(point.y().unwrap());
+        let ak_pub = get_tpm2b_public(TPM_ALG_ECC, TPM_ALG_ECDSA, &tail);
+
+        let digest = Sha256::digest(get_attest(pcr_digest));
+        let sig: p256::ecdsa::Signature = key.sign_prehash(&digest).unwrap();
+        let (r, s) = sig.split_bytes();
+        let signature = TpmtSignature {
+            sig_alg: TPM_ALG_ECDSA,
+            signature: TpmuSignature::Ecc {
+                hash: TPM_ALG_SHA256,
+                signature_r: r.to_vec(),
+                signature_s: s.to_vec(),
+            },
+        };
+        (get_quote(pcr_digest, signature), ak_pub)
+    }
+
+    fn get_rsa_quote(pcr_digest: &[u8], sig_alg: u16) -> (TpmQuote, Vec<u8>) {
+        let pem = include_str!("../../test_data/tpm_ak_rsa.pem");
+        let key = RsaPrivateKey::from_pkcs8_pem(pem).unwrap();
+        let mut tail = Vec::new();
+        tail.extend_from_slice(&2048u16.to_be_bytes());
+        tail.extend_from_slice(&0u32.to_be_bytes());
+        let modulus = key.n().to_bytes_be();
+        tail.extend_from_slice(&(modulus.len() as u16).to_be_bytes());
+        tail.extend_from_slice(&modulus);
+        let ak_pub = get_tpm2b_public(TPM_ALG_RSA, sig_alg, &tail);
+
+        let digest = Sha256::digest(get_attest(pcr_digest));
+        let sig = if sig_alg == TPM_ALG_RSASSA {
+            key.sign(Pkcs1v15Sign::new::<Sha256>(), &digest).unwrap()
+        } else {
+            key.sign_with_rng(&mut TestRng(0), Pss::new::<Sha256>(), &digest)
+                .unwrap()
+        };
+        let signature = TpmtSignature {
+            sig_alg,
+            signature: TpmuSignature::Rsa {
+                hash: TPM_ALG_SHA256,
+                sig,
+            },
+        };
+        (get_quote(pcr_digest, signature), ak_pub)
+    }
+
+    #[test]
+    fn test_verify_ecdsa_quote() {
+        let (quote, ak_pub) = get_ecc_quote(&get_expected_pcr_digest());
+        let result = quote.verify(&ak_pub, get_eventlogs()).unwrap();
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_verify_rsassa_quote() {
+        let (quote, ak_pub) = get_rsa_quote(&get_expected_pcr_digest(), TPM_ALG_RSASSA);
+        let result = quote.verify(&ak_pub, get_eventlogs()).unwrap();
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_verify_rsapss_quote() {
+        let (quote, ak_pub) = get_rsa_quote(&get_expected_pcr_digest(), TPM_ALG_RSAPSS);
+        let result = quote.verify(&ak_pub, get_eventlogs()).unwrap();
+        assert!(result.is_valid());
+    }
+
+    #[test]
+    fn test_tampered_quote() {
+        let (mut quote, ak_pub) = get_ecc_quote(&get_expected_pcr_digest());
+        quote.quoted[10] ^= 0xff;
+        let result = quote.verify(&ak_pub, get_eventlogs()).unwrap();
+        assert!(!result.signature);
+        assert!(result.pcr_digest);
+    }
+
+    #[test]
+    fn test_pcr_digest_mismatch() {
+        let (quote, ak_pub) = get_rsa_quote(&[0; 32], TPM_ALG_RSASSA);
+        let result = quote.verify(&ak_pub, get_eventlogs()).unwrap();
+        assert!(result.signature);
+        assert!(!result.pcr_digest);
+
+        let mut eventlogs = get_eventlogs();
+        eventlogs.pop();
+        let (quote, ak_pub) = get_ecc_quote(&get_expected_pcr_digest());
+        assert!(!quote.verify(&ak_pub, eventlogs).unwrap().pcr_digest);
+    }
+
+    // TPM event log logged with PCR indexes, extending the SHA256 digests of
+    // get_eventlogs() along with SHA1 digests of other values
+    fn get_tpm_event_log() -> Vec<u8> {
+        let mut spec_id_event = b"Spec ID Event03\0".to_vec();
+        spec_id_event.extend_from_slice(&[0, 0, 0, 0, 0, 2, 0, 2]);
+        spec_id_event.extend_from_slice(&2u32.to_le_bytes());
+        spec_id_event.extend_from_slice(&TPM_ALG_SHA1.to_le_bytes());
+        spec_id_event.extend_from_slice(&20u16.to_le_bytes());
+        spec_id_event.extend_from_slice(&TPM_ALG_SHA256.to_le_bytes());
+        spec_id_event.extend_from_slice(&32u16.to_le_bytes());
+        spec_id_event.push(0);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&EV_NO_ACTION.to_le_bytes());
+        data.extend_from_slice(&[0; 20]);
+        data.extend_from_slice(&(spec_id_event.len() as u32).to_le_bytes());
+        data.extend_from_slice(&spec_id_event);
+        for (pcr_index, byte) in [(0u32, 0x11), (1, 0x22), (0, 0x33)] {
+            data.extend_from_slice(&pcr_index.to_le_bytes());
+            data.extend_from_slice(&0x80000008u32.to_le_bytes());
+            data.extend_from_slice(&2u32.to_le_bytes());
+            data.extend_from_slice(&TPM_ALG_SHA1.to_le_bytes());
+            data.extend_from_slice(&[!byte; 20]);
+            data.extend_from_slice(&TPM_ALG_SHA256.to_le_bytes());
+            data.extend_from_slice(&[byte; 32]);
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_verify_tpm_event_log_quote() {
+        let mut eventlogs = EventLogs::new(get_tpm_event_log(), Vec::new(), TCG_PCCLIENT_FORMAT);
+        let eventlogs = eventlogs.select(None, None).unwrap();
+        let (quote, ak_pub) = get_ecc_quote(&get_expected_pcr_digest());
+        assert!(quote.verify(&ak_pub, eventlogs).unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_compute_pcr_digest_reset_value() {
+        let (mut quote, _) = get_ecc_quote(&get_expected_pcr_digest());
+        // sha256 bank with PCR 0, 17, 22 and 23
+        quote.attest.quote_info.pcr_select[0].pcr_select = vec![0x01, 0x00, 0xc2];
+        let expected =
+            Sha256::digest([vec![0; 32], vec![0xff; 32], vec![0xff; 32], vec![0; 32]].concat())
+                .to_vec();
+        assert_eq!(quote.compute_pcr_digest(&[]).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_key_type_mismatch() {
+        let (quote, _) = get_ecc_quote(&get_expected_pcr_digest());
+        let (_, ak_pub) = get_rsa_quote(&get_expected_pcr_digest(), TPM_ALG_RSASSA);
+        assert!(quote.verify(&ak_pub, get_eventlogs()).is_err());
+    }
+}
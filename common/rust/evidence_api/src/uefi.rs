@@ -0,0 +1,1013 @@
+use crate::binary_blob::*;
+use crate::tcg::*;
+use anyhow::anyhow;
+use log::info;
+
+/***
+    UEFI device path types and sub types defined at
+    https://uefi.org/specs/UEFI/2.10/10_Protocols_Device_Path_Protocol.html
+*/
+pub const DEVICE_PATH_HARDWARE: u8 = 0x1;
+pub const DEVICE_PATH_ACPI: u8 = 0x2;
+pub const DEVICE_PATH_MESSAGING: u8 = 0x3;
+pub const DEVICE_PATH_MEDIA: u8 = 0x4;
+pub const DEVICE_PATH_BBS: u8 = 0x5;
+pub const DEVICE_PATH_END: u8 = 0x7F;
+
+pub const DEVICE_PATH_HW_PCI: u8 = 0x1;
+pub const DEVICE_PATH_HW_MEMMAP: u8 = 0x3;
+pub const DEVICE_PATH_HW_VENDOR: u8 = 0x4;
+pub const DEVICE_PATH_ACPI_ACPI: u8 = 0x1;
+pub const DEVICE_PATH_MSG_SCSI: u8 = 0x2;
+pub const DEVICE_PATH_MSG_USB: u8 = 0x5;
+pub const DEVICE_PATH_MSG_VENDOR: u8 = 0xA;
+pub const DEVICE_PATH_MSG_MAC: u8 = 0xB;
+pub const DEVICE_PATH_MSG_IPV4: u8 = 0xC;
+pub const DEVICE_PATH_MSG_SATA: u8 = 0x12;
+pub const DEVICE_PATH_MSG_NVME: u8 = 0x17;
+pub const DEVICE_PATH_MSG_URI: u8 = 0x18;
+pub const DEVICE_PATH_MEDIA_HARDDRIVE: u8 = 0x1;
+pub const DEVICE_PATH_MEDIA_CDROM: u8 = 0x2;
+pub const DEVICE_PATH_MEDIA_VENDOR: u8 = 0x3;
+pub const DEVICE_PATH_MEDIA_FILEPATH: u8 = 0x4;
+pub const DEVICE_PATH_MEDIA_PIWG_FV_FILE: u8 = 0x6;
+pub const DEVICE_PATH_MEDIA_PIWG_FV: u8 = 0x7;
+pub const DEVICE_PATH_MEDIA_OFFSET: u8 = 0x8;
+pub const DEVICE_PATH_BBS_BBS: u8 = 0x1;
+pub const DEVICE_PATH_END_INSTANCE: u8 = 0x1;
+pub const DEVICE_PATH_END_ENTIRE: u8 = 0xFF;
+
+// length of the Type, SubType and Length fields of a device path node
+const DEVICE_PATH_NODE_HEADER_LEN: usize = 4;
+// EISA ID of PCI root bridges
+const ACPI_HID_PCI_ROOT: u32 = 0x0A0341D0;
+const ACPI_HID_PCIE_ROOT: u32 = 0x0A0841D0;
+// length of UEFI_PARTITION_TABLE_HEADER
+const GPT_HEADER_LEN: usize = 92;
+// length of the defined fields of UEFI_PARTITION_ENTRY
+const GPT_ENTRY_LEN: usize = 128;
+
+/***
+    UEFI device path node defined at
+    https://uefi.org/specs/UEFI/2.10/10_Protocols_Device_Path_Protocol.html
+    Definition:
+    typedef struct _EFI_DEVICE_PATH_PROTOCOL {
+        UINT8 Type;
+        UINT8 SubType;
+        UINT8 Length[2];
+    } EFI_DEVICE_PATH_PROTOCOL;
+    followed by the node specific data.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiDevicePathNode {
+    pub node_type: u8,
+    pub sub_type: u8,
+    pub data: Vec<u8>,
+}
+
+impl UefiDevicePathNode {
+    // Get the text of the node, following the UEFI device path text representation
+    pub fn get_node_str(&self) -> String {
+        match self.decode_node() {
+            Ok(Some(text)) => text,
+            _ => format!(
+                "Path({},{},{})",
+                self.node_type,
+                self.sub_type,
+                hex::encode(&self.data)
+            ),
+        }
+    }
+
+    fn decode_node(&self) -> Result<Option<String>, anyhow::Error> {
+        let mut reader = BinaryReader::new(&self.data);
+        let text = match (self.node_type, self.sub_type) {
+            (DEVICE_PATH_HARDWARE, DEVICE_PATH_HW_PCI) => {
+                let function = reader.read_u8()?;
+                let device = reader.read_u8()?;
+                format!("Pci(0x{:x},0x{:x})", device, function)
+            }
+            (DEVICE_PATH_HARDWARE, DEVICE_PATH_HW_MEMMAP) => format!(
+                "MemoryMapped(0x{:x},0x{:x},0x{:x})",
+                reader.read_u32_le()?,
+                reader.read_u64_le()?,
+                reader.read_u64_le()?
+            ),
+            (DEVICE_PATH_HARDWARE, DEVICE_PATH_HW_VENDOR) => {
+                format!("VenHw({})", get_guid_str(&reader.read_array()?))
+            }
+            (DEVICE_PATH_ACPI, DEVICE_PATH_ACPI_ACPI) => {
+                let hid = reader.read_u32_le()?;
+                let uid = reader.read_u32_le()?;
+                match hid {
+                    ACPI_HID_PCI_ROOT => format!("PciRoot(0x{:x})", uid),
+                    ACPI_HID_PCIE_ROOT => format!("PcieRoot(0x{:x})", uid),
+                    _ => format!("Acpi(0x{:x},0x{:x})", hid, uid),
+                }
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_SCSI) => format!(
+                "Scsi(0x{:x},0x{:x})",
+                reader.read_u16_le()?,
+                reader.read_u16_le()?
+            ),
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_USB) => {
+                format!("USB(0x{:x},0x{:x})", reader.read_u8()?, reader.read_u8()?)
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_VENDOR) => {
+                format!("VenMsg({})", get_guid_str(&reader.read_array()?))
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_MAC) => {
+                let mac = reader.read_bytes(32)?;
+                let if_type = reader.read_u8()?;
+                format!("MAC({},0x{:x})", hex::encode(&mac[..6]), if_type)
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_IPV4) => {
+                let local = reader.read_bytes(4)?;
+                let remote = reader.read_bytes(4)?;
+                format!(
+                    "IPv4({}.{}.{}.{},{}.{}.{}.{})",
+                    remote[0],
+                    remote[1],
+                    remote[2],
+                    remote[3],
+                    local[0],
+                    local[1],
+                    local[2],
+                    local[3]
+                )
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_SATA) => format!(
+                "Sata(0x{:x},0x{:x},0x{:x})",
+                reader.read_u16_le()?,
+                reader.read_u16_le()?,
+                reader.read_u16_le()?
+            ),
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_NVME) => {
+                let nsid = reader.read_u32_le()?;
+                let eui = reader.read_bytes(8)?;
+                let eui: Vec<String> = eui.iter().map(|b| format!("{:02X}", b)).collect();
+                format!("NVMe(0x{:x},{})", nsid, eui.join("-"))
+            }
+            (DEVICE_PATH_MESSAGING, DEVICE_PATH_MSG_URI) => {
+                format!("Uri({})", String::from_utf8_lossy(&self.data))
+            }
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_HARDDRIVE) => {
+                let partition_number = reader.read_u32_le()?;
+                let partition_start = reader.read_u64_le()?;
+                let partition_size = reader.read_u64_le()?;
+                let signature: [u8; 16] = reader.read_array()?;
+                let _partition_format = reader.read_u8()?;
+                let signature = match reader.read_u8()? {
+                    1 => format!(
+                        "MBR,0x{:08x}",
+                        u32::from_le_bytes(signature[..4].try_into().unwrap())
+                    ),
+                    2 => format!("GPT,{}", get_guid_str(&signature)),
+                    _ => "0".to_string(),
+                };
+                format!(
+                    "HD({},{},0x{:x},0x{:x})",
+                    partition_number, signature, partition_start, partition_size
+                )
+            }
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_CDROM) => format!(
+                "CDROM(0x{:x},0x{:x},0x{:x})",
+                reader.read_u32_le()?,
+                reader.read_u64_le()?,
+                reader.read_u64_le()?
+            ),
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_VENDOR) => {
+                format!("VenMedia({})", get_guid_str(&reader.read_array()?))
+            }
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_FILEPATH) => get_ucs2_str(&self.data),
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_PIWG_FV_FILE) => {
+                format!("FvFile({})", get_guid_str(&reader.read_array()?))
+            }
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_PIWG_FV) => {
+                format!("Fv({})", get_guid_str(&reader.read_array()?))
+            }
+            (DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_OFFSET) => {
+                let _reserved = reader.read_u32_le()?;
+                format!(
+                    "Offset(0x{:x},0x{:x})",
+                    reader.read_u64_le()?,
+                    reader.read_u64_le()?
+                )
+            }
+            (DEVICE_PATH_BBS, DEVICE_PATH_BBS_BBS) => {
+                let device_type = reader.read_u16_le()?;
+                let status_flag = reader.read_u16_le()?;
+                let description = reader.read_bytes(reader.remaining())?;
+                format!(
+                    "BBS(0x{:x},{},0x{:x})",
+                    device_type,
+                    String::from_utf8_lossy(description).trim_end_matches('\0'),
+                    status_flag
+                )
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(text))
+    }
+}
+
+/***
+    UEFI device path, i.e. a list of device path nodes terminated by an end node.
+    Instances of a multi-instance path are separated by End Instance nodes.
+
+    Attributes:
+        nodes: the nodes of the path, the terminating End Entire node excluded
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiDevicePath {
+    pub nodes: Vec<UefiDevicePathNode>,
+}
+
+impl UefiDevicePath {
+    pub fn new(data: &[u8]) -> Result<UefiDevicePath, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let mut nodes = Vec::new();
+        while !reader.is_empty() {
+            let node_type = reader.read_u8()?;
+            let sub_type = reader.read_u8()?;
+            let length = reader.read_u16_le()? as usize;
+            if length < DEVICE_PATH_NODE_HEADER_LEN {
+                return Err(anyhow!(
+                    "[UefiDevicePath] invalid node length {} at offset {}",
+                    length,
+                    reader.position() - DEVICE_PATH_NODE_HEADER_LEN
+                ));
+            }
+            let node_data = reader.read_bytes(length - DEVICE_PATH_NODE_HEADER_LEN)?;
+            if node_type == DEVICE_PATH_END && sub_type == DEVICE_PATH_END_ENTIRE {
+                break;
+            }
+            nodes.push(UefiDevicePathNode {
+                node_type,
+                sub_type,
+                data: node_data.to_vec(),
+            });
+        }
+        Ok(UefiDevicePath { nodes })
+    }
+
+    // Get the text of the path, e.g. "PciRoot(0x0)/Pci(0x1,0x1)/Sata(0x0,0xffff,0x0)"
+    pub fn get_path_str(&self) -> String {
+        let mut text = String::new();
+        for node in &self.nodes {
+            if node.node_type == DEVICE_PATH_END {
+                text.push(',');
+                continue;
+            }
+            if !text.is_empty() && !text.ends_with(',') {
+                text.push('/');
+            }
+            text.push_str(&node.get_node_str());
+        }
+        text
+    }
+}
+
+/***
+    UEFI_VARIABLE_DATA defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClient_PFP_r1p05_v23_pub.pdf
+    Definition:
+    typedef struct tdUEFI_VARIABLE_DATA {
+        UEFI_GUID VariableName;
+        UINT64 UnicodeNameLength;
+        UINT64 VariableDataLength;
+        CHAR16 UnicodeName[];
+        INT8 VariableData[];
+    } UEFI_VARIABLE_DATA;
+
+    Attributes:
+        variable_name: GUID of the vendor of the variable
+        unicode_name: the name of the variable
+        variable_data: the value of the variable
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiVariableData {
+    pub variable_name: [u8; 16],
+    pub unicode_name: String,
+    pub variable_data: Vec<u8>,
+}
+
+impl UefiVariableData {
+    pub fn new(data: &[u8]) -> Result<UefiVariableData, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let variable_name = reader.read_array()?;
+        let name_length = reader.read_u64_le()? as usize;
+        let data_length = reader.read_u64_le()? as usize;
+        let unicode_name = match name_length.checked_mul(2) {
+            Some(length) => get_ucs2_str(reader.read_bytes(length)?),
+            None => return Err(anyhow!("[UefiVariableData] invalid name length")),
+        };
+        let variable_data = reader.read_bytes(data_length)?.to_vec();
+        Ok(UefiVariableData {
+            variable_name,
+            unicode_name,
+            variable_data,
+        })
+    }
+
+    // Decode the variable data as EFI_LOAD_OPTION for Boot#### and Driver#### variables
+    pub fn get_load_option(&self) -> Option<UefiLoadOption> {
+        let name = &self.unicode_name;
+        let is_load_option = ["Boot", "Driver", "SysPrep", "PlatformRecovery"]
+            .iter()
+            .any(|prefix| {
+                name.starts_with(prefix)
+                    && name.len() == prefix.len() + 4
+                    && name[prefix.len()..].chars().all(|c| c.is_ascii_hexdigit())
+            });
+        if !is_load_option {
+            return None;
+        }
+        UefiLoadOption::new(&self.variable_data).ok()
+    }
+
+    pub fn show(&self) {
+        info!(
+            "        Variable          : {} ({})",
+            self.unicode_name,
+            get_guid_str(&self.variable_name)
+        );
+        if let Some(load_option) = self.get_load_option() {
+            load_option.show();
+            return;
+        }
+        if self.unicode_name == "BootOrder" {
+            let boot_order: Vec<String> = self
+                .variable_data
+                .chunks_exact(2)
+                .map(|c| format!("Boot{:04X}", u16::from_le_bytes([c[0], c[1]])))
+                .collect();
+            info!("        BootOrder         : {}", boot_order.join(","));
+            return;
+        }
+        info!("        VariableData:");
+        dump_data(&self.variable_data);
+    }
+}
+
+/***
+    EFI_LOAD_OPTION defined at
+    https://uefi.org/specs/UEFI/2.10/03_Boot_Manager.html#load-options
+    Definition:
+    typedef struct _EFI_LOAD_OPTION {
+        UINT32 Attributes;
+        UINT16 FilePathListLength;
+        CHAR16 Description[];
+        EFI_DEVICE_PATH_PROTOCOL FilePathList[];
+        UINT8 OptionalData[];
+    } EFI_LOAD_OPTION;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiLoadOption {
+    pub attributes: u32,
+    pub description: String,
+    pub file_path_list: UefiDevicePath,
+    pub optional_data: Vec<u8>,
+}
+
+impl UefiLoadOption {
+    pub fn new(data: &[u8]) -> Result<UefiLoadOption, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let attributes = reader.read_u32_le()?;
+        let file_path_list_length = reader.read_u16_le()? as usize;
+        let mut description = Vec::new();
+        loop {
+            let c = reader.read_u16_le()?;
+            if c == 0 {
+                break;
+            }
+            description.push(c);
+        }
+        let file_path_list = UefiDevicePath::new(reader.read_bytes(file_path_list_length)?)?;
+        let optional_data = reader.read_bytes(reader.remaining())?.to_vec();
+        Ok(UefiLoadOption {
+            attributes,
+            description: String::from_utf16_lossy(&description),
+            file_path_list,
+            optional_data,
+        })
+    }
+
+    pub fn show(&self) {
+        info!("        Attributes        : 0x{:x}", self.attributes);
+        info!("        Description       : {}", self.description);
+        info!(
+            "        FilePathList      : {}",
+            self.file_path_list.get_path_str()
+        );
+        if !self.optional_data.is_empty() {
+            info!("        OptionalData:");
+            dump_data(&self.optional_data);
+        }
+    }
+}
+
+/***
+    UEFI_IMAGE_LOAD_EVENT defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClient_PFP_r1p05_v23_pub.pdf
+    Definition:
+    typedef struct tdUEFI_IMAGE_LOAD_EVENT {
+        UEFI_PHYSICAL_ADDRESS ImageLocationInMemory;
+        UINT64 ImageLengthInMemory;
+        UINT64 ImageLinkTimeAddress;
+        UINT64 LengthOfDevicePath;
+        UEFI_DEVICE_PATH DevicePath[LengthOfDevicePath];
+    } UEFI_IMAGE_LOAD_EVENT;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiImageLoadEvent {
+    pub image_location_in_memory: u64,
+    pub image_length_in_memory: u64,
+    pub image_link_time_address: u64,
+    pub device_path: UefiDevicePath,
+}
+
+impl UefiImageLoadEvent {
+    pub fn new(data: &[u8]) -> Result<UefiImageLoadEvent, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let image_location_in_memory = reader.read_u64_le()?;
+        let image_length_in_memory = reader.read_u64_le()?;
+        let image_link_time_address = reader.read_u64_le()?;
+        let device_path_length = reader.read_u64_le()? as usize;
+        let device_path = UefiDevicePath::new(reader.read_bytes(device_path_length)?)?;
+        Ok(UefiImageLoadEvent {
+            image_location_in_memory,
+            image_length_in_memory,
+            image_link_time_address,
+            device_path,
+        })
+    }
+
+    pub fn show(&self) {
+        info!(
+            "        ImageLocation     : 0x{:x}",
+            self.image_location_in_memory
+        );
+        info!(
+            "        ImageLength       : 0x{:x}",
+            self.image_length_in_memory
+        );
+        info!(
+            "        ImageLinkTimeAddr : 0x{:x}",
+            self.image_link_time_address
+        );
+        info!(
+            "        DevicePath        : {}",
+            self.device_path.get_path_str()
+        );
+    }
+}
+
+/***
+    UEFI_PARTITION_TABLE_HEADER defined at
+    https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiPartitionTableHeader {
+    pub signature: u64,
+    pub revision: u32,
+    pub header_size: u32,
+    pub header_crc32: u32,
+    pub my_lba: u64,
+    pub alternate_lba: u64,
+    pub first_usable_lba: u64,
+    pub last_usable_lba: u64,
+    pub disk_guid: [u8; 16],
+    pub partition_entry_lba: u64,
+    pub number_of_partition_entries: u32,
+    pub size_of_partition_entry: u32,
+    pub partition_entry_array_crc32: u32,
+}
+
+/***
+    UEFI_PARTITION_ENTRY defined at
+    https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiPartitionEntry {
+    pub partition_type_guid: [u8; 16],
+    pub unique_partition_guid: [u8; 16],
+    pub starting_lba: u64,
+    pub ending_lba: u64,
+    pub attributes: u64,
+    pub partition_name: String,
+}
+
+/***
+    UEFI_GPT_DATA defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClient_PFP_r1p05_v23_pub.pdf
+    Definition:
+    typedef struct tdUEFI_GPT_DATA {
+        UEFI_PARTITION_TABLE_HEADER UEFIPartitionHeader;
+        UINT64 NumberOfPartitions;
+        UEFI_PARTITION_ENTRY Partitions[NumberOfPartitions];
+    } UEFI_GPT_DATA;
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiGptData {
+    pub header: UefiPartitionTableHeader,
+    pub partitions: Vec<UefiPartitionEntry>,
+}
+
+impl UefiGptData {
+    pub fn new(data: &[u8]) -> Result<UefiGptData, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let mut header_reader = BinaryReader::new(reader.read_bytes(GPT_HEADER_LEN)?);
+        let header = UefiPartitionTableHeader {
+            signature: header_reader.read_u64_le()?,
+            revision: header_reader.read_u32_le()?,
+            header_size: header_reader.read_u32_le()?,
+            header_crc32: header_reader.read_u32_le()?,
+            my_lba: {
+                let _reserved = header_reader.read_u32_le()?;
+                header_reader.read_u64_le()?
+            },
+            alternate_lba: header_reader.read_u64_le()?,
+            first_usable_lba: header_reader.read_u64_le()?,
+            last_usable_lba: header_reader.read_u64_le()?,
+            disk_guid: header_reader.read_array()?,
+            partition_entry_lba: header_reader.read_u64_le()?,
+            number_of_partition_entries: header_reader.read_u32_le()?,
+            size_of_partition_entry: header_reader.read_u32_le()?,
+            partition_entry_array_crc32: header_reader.read_u32_le()?,
+        };
+
+        let entry_size = header.size_of_partition_entry as usize;
+        if entry_size < GPT_ENTRY_LEN {
+            return Err(anyhow!(
+                "[UefiGptData] invalid partition entry size {}",
+                entry_size
+            ));
+        }
+        let number_of_partitions = reader.read_u64_le()?;
+        let mut partitions = Vec::new();
+        for _ in 0..number_of_partitions {
+            let mut entry_reader = BinaryReader::new(reader.read_bytes(entry_size)?);
+            partitions.push(UefiPartitionEntry {
+                partition_type_guid: entry_reader.read_array()?,
+                unique_partition_guid: entry_reader.read_array()?,
+                starting_lba: entry_reader.read_u64_le()?,
+                ending_lba: entry_reader.read_u64_le()?,
+                attributes: entry_reader.read_u64_le()?,
+                partition_name: get_ucs2_str(entry_reader.read_bytes(72)?),
+            });
+        }
+        Ok(UefiGptData { header, partitions })
+    }
+
+    pub fn show(&self) {
+        info!(
+            "        DiskGUID          : {}",
+            get_guid_str(&self.header.disk_guid)
+        );
+        info!("        Partitions        : {}", self.partitions.len());
+        for (index, partition) in self.partitions.iter().enumerate() {
+            info!(
+                "        Partition[{}]      : {} type {} unique {} LBA 0x{:x}-0x{:x}",
+                index,
+                partition.partition_name,
+                get_guid_str(&partition.partition_type_guid),
+                get_guid_str(&partition.unique_partition_guid),
+                partition.starting_lba,
+                partition.ending_lba
+            );
+        }
+    }
+}
+
+/***
+    UEFI_PLATFORM_FIRMWARE_BLOB and UEFI_PLATFORM_FIRMWARE_BLOB2 defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClient_PFP_r1p05_v23_pub.pdf
+    Definition:
+    typedef struct tdUEFI_PLATFORM_FIRMWARE_BLOB2 {
+        UINT8 BlobDescriptionSize;
+        BYTE[BlobDescriptionSize] BlobDescription;
+        UEFI_PHYSICAL_ADDRESS BlobBase;
+        UINT64 BlobLength;
+    } UEFI_PLATFORM_FIRMWARE_BLOB2;
+    UEFI_PLATFORM_FIRMWARE_BLOB has no description.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiPlatformFirmwareBlob {
+    pub blob_description: Option<String>,
+    pub blob_base: u64,
+    pub blob_length: u64,
+}
+
+impl UefiPlatformFirmwareBlob {
+    pub fn new(data: &[u8], with_description: bool) -> Result<Self, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let blob_description = if with_description {
+            let size = reader.read_u8()? as usize;
+            Some(get_description_str(reader.read_bytes(size)?))
+        } else {
+            None
+        };
+        Ok(UefiPlatformFirmwareBlob {
+            blob_description,
+            blob_base: reader.read_u64_le()?,
+            blob_length: reader.read_u64_le()?,
+        })
+    }
+
+    pub fn show(&self) {
+        if let Some(description) = &self.blob_description {
+            info!("        BlobDescription   : {}", description);
+        }
+        info!("        BlobBase          : 0x{:x}", self.blob_base);
+        info!("        BlobLength        : 0x{:x}", self.blob_length);
+    }
+}
+
+// EFI_CONFIGURATION_TABLE, i.e. a vendor GUID and the pointer of its table
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiConfigurationTable {
+    pub vendor_guid: [u8; 16],
+    pub vendor_table: u64,
+}
+
+/***
+    UEFI_HANDOFF_TABLE_POINTERS and UEFI_HANDOFF_TABLE_POINTERS2 defined at
+    https://trustedcomputinggroup.org/wp-content/uploads/TCG_PCClient_PFP_r1p05_v23_pub.pdf
+    Definition:
+    typedef struct tdUEFI_HANDOFF_TABLE_POINTERS2 {
+        UINT8 TableDescriptionSize;
+        BYTE[TableDescriptionSize] TableDescription;
+        UINT64 NumberOfTables;
+        UEFI_CONFIGURATION_TABLE TableEntry[NumberOfTables];
+    } UEFI_HANDOFF_TABLE_POINTERS2;
+    UEFI_HANDOFF_TABLE_POINTERS has no description.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct UefiHandoffTablePointers {
+    pub table_description: Option<String>,
+    pub tables: Vec<UefiConfigurationTable>,
+}
+
+impl UefiHandoffTablePointers {
+    pub fn new(data: &[u8], with_description: bool) -> Result<Self, anyhow::Error> {
+        let mut reader = BinaryReader::new(data);
+        let table_description = if with_description {
+            let size = reader.read_u8()? as usize;
+            Some(get_description_str(reader.read_bytes(size)?))
+        } else {
+            None
+        };
+        let number_of_tables = reader.read_u64_le()?;
+        let mut tables = Vec::new();
+        for _ in 0..number_of_tables {
+            tables.push(UefiConfigurationTable {
+                vendor_guid: reader.read_array()?,
+                vendor_table: reader.read_u64_le()?,
+            });
+        }
+        Ok(UefiHandoffTablePointers {
+            table_description,
+            tables,
+        })
+    }
+
+    pub fn show(&self) {
+        if let Some(description) = &self.table_description {
+            info!("        TableDescription  : {}", description);
+        }
+        for (index, table) in self.tables.iter().enumerate() {
+            info!(
+                "        Table[{}]          : {} at 0x{:x}",
+                index,
+                get_guid_str(&table.vendor_guid),
+                table.vendor_table
+            );
+        }
+    }
+}
+
+// event data of TcgImrEvent decoded according to the event type
+#[derive(Clone, Debug, PartialEq)]
+pub enum TcgEventData {
+    VariableData(UefiVariableData),
+    ImageLoad(UefiImageLoadEvent),
+    GptData(UefiGptData),
+    FirmwareBlob(UefiPlatformFirmwareBlob),
+    HandoffTables(UefiHandoffTablePointers),
+}
+
+impl TcgEventData {
+    pub fn show(&self) {
+        match self {
+            TcgEventData::VariableData(data) => data.show(),
+            TcgEventData::ImageLoad(data) => data.show(),
+            TcgEventData::GptData(data) => data.show(),
+            TcgEventData::FirmwareBlob(data) => data.show(),
+            TcgEventData::HandoffTables(data) => data.show(),
+        }
+    }
+}
+
+impl TcgImrEvent {
+    /***
+        Decode the event data according to the event type.
+
+        Returns:
+            The decoded event data, None if the event type has no typed decoder,
+            or error if the event data is malformed
+    */
+    pub fn get_event_data(&self) -> Result<Option<TcgEventData>, anyhow::Error> {
+        let event = &self.event;
+        let event_data = match self.event_type {
+            EV_EFI_VARIABLE_DRIVER_CONFIG
+            | EV_EFI_VARIABLE_BOOT
+            | EV_EFI_VARIABLE_BOOT2
+            | EV_EFI_VARIABLE_AUTHORITY => {
+                TcgEventData::VariableData(UefiVariableData::new(event)?)
+            }
+            EV_EFI_BOOT_SERVICES_APPLICATION
+            | EV_EFI_BOOT_SERVICES_DRIVER
+            | EV_EFI_RUNTIME_SERVICES_DRIVER => {
+                TcgEventData::ImageLoad(UefiImageLoadEvent::new(event)?)
+            }
+            EV_EFI_GPT_EVENT | EV_EFI_GPT_EVENT2 => TcgEventData::GptData(UefiGptData::new(event)?),
+            EV_EFI_PLATFORM_FIRMWARE_BLOB => {
+                TcgEventData::FirmwareBlob(UefiPlatformFirmwareBlob::new(event, false)?)
+            }
+            EV_EFI_PLATFORM_FIRMWARE_BLOB2 => {
+                TcgEventData::FirmwareBlob(UefiPlatformFirmwareBlob::new(event, true)?)
+            }
+            EV_EFI_HANDOFF_TABLES => {
+                TcgEventData::HandoffTables(UefiHandoffTablePointers::new(event, false)?)
+            }
+            EV_EFI_HANDOFF_TABLES2 => {
+                TcgEventData::HandoffTables(UefiHandoffTablePointers::new(event, true)?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(event_data))
+    }
+}
+
+// descriptions of BLOB2 and HANDOFF_TABLES2 are ASCII, possibly null terminated
+fn get_description_str(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod test_uefi_event_data {
+    use super::*;
+
+    const DISK_GUID: [u8; 16] = [
+        0x8d, 0x75, 0xda, 0x63, 0x64, 0xe6, 0x64, 0x45, 0xad, 0xc5, 0xf4, 0xb9, 0x3b, 0xe8, 0xac,
+        0xcd,
+    ];
+
+    pub(crate) fn ucs2(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|c| c.to_le_bytes())
+            .collect()
+    }
+
+    fn node(node_type: u8, sub_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut node = vec![node_type, sub_type];
+        node.extend_from_slice(&((data.len() + 4) as u16).to_le_bytes());
+        node.extend_from_slice(data);
+        node
+    }
+
+    // PciRoot(0x0)/Pci(0x1f,0x2)/Sata(0x0,0xffff,0x0)/HD(1,GPT,...)/\EFI\BOOT\BOOTX64.EFI
+    fn get_device_path() -> Vec<u8> {
+        let mut hd = Vec::new();
+        hd.extend_from_slice(&1u32.to_le_bytes());
+        hd.extend_from_slice(&0x800u64.to_le_bytes());
+        hd.extend_from_slice(&0x100000u64.to_le_bytes());
+        hd.extend_from_slice(&DISK_GUID);
+        hd.extend_from_slice(&[2, 2]);
+        [
+            node(
+                DEVICE_PATH_ACPI,
+                DEVICE_PATH_ACPI_ACPI,
+                &[0xd0, 0x41, 0x03, 0x0a, 0, 0, 0, 0],
+            ),
+            node(DEVICE_PATH_HARDWARE, DEVICE_PATH_HW_PCI, &[0x2, 0x1f]),
+            node(
+                DEVICE_PATH_MESSAGING,
+                DEVICE_PATH_MSG_SATA,
+                &[0, 0, 0xff, 0xff, 0, 0],
+            ),
+            node(DEVICE_PATH_MEDIA, DEVICE_PATH_MEDIA_HARDDRIVE, &hd),
+            node(
+                DEVICE_PATH_MEDIA,
+                DEVICE_PATH_MEDIA_FILEPATH,
+                &ucs2("\\EFI\\BOOT\\BOOTX64.EFI"),
+            ),
+            node(DEVICE_PATH_END, DEVICE_PATH_END_ENTIRE, &[]),
+        ]
+        .concat()
+    }
+
+    const DEVICE_PATH_STR: &str = "PciRoot(0x0)/Pci(0x1f,0x2)/Sata(0x0,0xffff,0x0)/HD(1,GPT,63da758d-e664-4564-adc5-f4b93be8accd,0x800,0x100000)/\\EFI\\BOOT\\BOOTX64.EFI";
+
+    fn get_event(event_type: u32, event: Vec<u8>) -> TcgImrEvent {
+        TcgImrEvent {
+            imr_index: 0,
+            event_type,
+            digests: Vec::new(),
+            event_size: event.len() as u32,
+            event,
+            ima_template: None,
+        }
+    }
+
+    fn get_variable(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut event = DISK_GUID.to_vec();
+        event.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        event.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        let name = ucs2(name);
+        event.extend_from_slice(&name[..name.len() - 2]);
+        event.extend_from_slice(data);
+        event
+    }
+
+    #[test]
+    fn test_device_path() {
+        let path = UefiDevicePath::new(&get_device_path()).unwrap();
+        assert_eq!(path.nodes.len(), 5);
+        assert_eq!(path.get_path_str(), DEVICE_PATH_STR);
+
+        // unknown node is shown raw
+        let path = UefiDevicePath::new(&node(0x6, 0x1, &[0xab])).unwrap();
+        assert_eq!(path.get_path_str(), "Path(6,1,ab)");
+        // node length shorter than its header
+        assert!(UefiDevicePath::new(&[0x1, 0x1, 0x2, 0x0]).is_err());
+    }
+
+    #[test]
+    fn test_variable_data() {
+        let event = get_event(
+            EV_EFI_VARIABLE_DRIVER_CONFIG,
+            get_variable("SecureBoot", &[1]),
+        );
+        let data = match event.get_event_data().unwrap() {
+            Some(TcgEventData::VariableData(data)) => data,
+            _ => panic!("not variable data"),
+        };
+        assert_eq!(data.unicode_name, "SecureBoot");
+        assert_eq!(data.variable_data, vec![1]);
+        assert!(data.get_load_option().is_none());
+
+        let device_path = get_device_path();
+        let mut load_option = Vec::new();
+        load_option.extend_from_slice(&1u32.to_le_bytes());
+        load_option.extend_from_slice(&(device_path.len() as u16).to_le_bytes());
+        load_option.extend_from_slice(&ucs2("UEFI OS"));
+        load_option.extend_from_slice(&device_path);
+        let event = get_event(EV_EFI_VARIABLE_BOOT, get_variable("Boot0001", &load_option));
+        let data = match event.get_event_data().unwrap() {
+            Some(TcgEventData::VariableData(data)) => data,
+            _ => panic!("not variable data"),
+        };
+        let load_option = data.get_load_option().unwrap();
+        assert_eq!(load_option.attributes, 1);
+        assert_eq!(load_option.description, "UEFI OS");
+        assert_eq!(load_option.file_path_list.get_path_str(), DEVICE_PATH_STR);
+        assert!(load_option.optional_data.is_empty());
+
+        // variable data shorter than declared
+        let mut variable = get_variable("SecureBoot", &[1]);
+        variable.pop();
+        assert!(get_event(EV_EFI_VARIABLE_DRIVER_CONFIG, variable)
+            .get_event_data()
+            .is_err());
+    }
+
+    #[test]
+    fn test_image_load_event() {
+        let device_path = get_device_path();
+        let mut event = Vec::new();
+        event.extend_from_slice(&0x7e000000u64.to_le_bytes());
+        event.extend_from_slice(&0x20000u64.to_le_bytes());
+        event.extend_from_slice(&0u64.to_le_bytes());
+        event.extend_from_slice(&(device_path.len() as u64).to_le_bytes());
+        event.extend_from_slice(&device_path);
+        let data = match get_event(EV_EFI_BOOT_SERVICES_APPLICATION, event)
+            .get_event_data()
+            .unwrap()
+        {
+            Some(TcgEventData::ImageLoad(data)) => data,
+            _ => panic!("not image load event"),
+        };
+        assert_eq!(data.image_location_in_memory, 0x7e000000);
+        assert_eq!(data.image_length_in_memory, 0x20000);
+        assert_eq!(data.device_path.get_path_str(), DEVICE_PATH_STR);
+    }
+
+    #[test]
+    fn test_gpt_data() {
+        let mut event = Vec::new();
+        event.extend_from_slice(b"EFI PART");
+        event.extend_from_slice(&0x10000u32.to_le_bytes());
+        event.extend_from_slice(&92u32.to_le_bytes());
+        event.extend_from_slice(&[0; 8]);
+        event.extend_from_slice(&1u64.to_le_bytes());
+        event.extend_from_slice(&0xffffu64.to_le_bytes());
+        event.extend_from_slice(&34u64.to_le_bytes());
+        event.extend_from_slice(&0xffdeu64.to_le_bytes());
+        event.extend_from_slice(&DISK_GUID);
+        event.extend_from_slice(&2u64.to_le_bytes());
+        event.extend_from_slice(&128u32.to_le_bytes());
+        event.extend_from_slice(&128u32.to_le_bytes());
+        event.extend_from_slice(&[0; 4]);
+        event.extend_from_slice(&1u64.to_le_bytes());
+        event.extend_from_slice(&[0x11; 16]);
+        event.extend_from_slice(&DISK_GUID);
+        event.extend_from_slice(&0x800u64.to_le_bytes());
+        event.extend_from_slice(&0x1007ffu64.to_le_bytes());
+        event.extend_from_slice(&0u64.to_le_bytes());
+        let mut name = ucs2("EFI System");
+        name.resize(72, 0);
+        event.extend_from_slice(&name);
+
+        let data = match get_event(EV_EFI_GPT_EVENT, event.clone())
+            .get_event_data()
+            .unwrap()
+        {
+            Some(TcgEventData::GptData(data)) => data,
+            _ => panic!("not GPT data"),
+        };
+        assert_eq!(data.header.signature, u64::from_le_bytes(*b"EFI PART"));
+        assert_eq!(data.header.my_lba, 1);
+        assert_eq!(data.header.disk_guid, DISK_GUID);
+        assert_eq!(data.header.number_of_partition_entries, 128);
+        assert_eq!(data.partitions.len(), 1);
+        assert_eq!(data.partitions[0].partition_name, "EFI System");
+        assert_eq!(data.partitions[0].starting_lba, 0x800);
+
+        // missing partition entry
+        event.truncate(event.len() - 1);
+        assert!(get_event(EV_EFI_GPT_EVENT2, event)
+            .get_event_data()
+            .is_err());
+    }
+
+    #[test]
+    fn test_blob_and_handoff_tables() {
+        let mut event = vec![9];
+        event.extend_from_slice(b"Fv(XXXX)\0");
+        event.extend_from_slice(&0xffc00000u64.to_le_bytes());
+        event.extend_from_slice(&0x400000u64.to_le_bytes());
+        let data = get_event(EV_EFI_PLATFORM_FIRMWARE_BLOB2, event)
+            .get_event_data()
+            .unwrap();
+        assert_eq!(
+            data,
+            Some(TcgEventData::FirmwareBlob(UefiPlatformFirmwareBlob {
+                blob_description: Some("Fv(XXXX)".to_string()),
+                blob_base: 0xffc00000,
+                blob_length: 0x400000,
+            }))
+        );
+
+        let mut event = Vec::new();
+        event.extend_from_slice(&0xffc00000u64.to_le_bytes());
+        event.extend_from_slice(&0x400000u64.to_le_bytes());
+        match get_event(EV_EFI_PLATFORM_FIRMWARE_BLOB, event)
+            .get_event_data()
+            .unwrap()
+        {
+            Some(TcgEventData::FirmwareBlob(data)) => assert!(data.blob_description.is_none()),
+            _ => panic!("not firmware blob"),
+        }
+
+        let mut event = vec![4];
+        event.extend_from_slice(b"ACPI");
+        event.extend_from_slice(&1u64.to_le_bytes());
+        event.extend_from_slice(&DISK_GUID);
+        event.extend_from_slice(&0x7fb7e000u64.to_le_bytes());
+        let data = get_event(EV_EFI_HANDOFF_TABLES2, event)
+            .get_event_data()
+            .unwrap();
+        assert_eq!(
+            data,
+            Some(TcgEventData::HandoffTables(UefiHandoffTablePointers {
+                table_description: Some("ACPI".to_string()),
+                tables: vec![UefiConfigurationTable {
+                    vendor_guid: DISK_GUID,
+                    vendor_table: 0x7fb7e000,
+                }],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_no_decoder() {
+        let event = get_event(EV_SEPARATOR, vec![0; 4]);
+        assert!(event.get_event_data().unwrap().is_none());
+        event.show();
+    }
+}
@@ -0,0 +1,434 @@
+use anyhow::anyhow;
+use p256::ecdsa::signature::Verifier;
+use rsa::pkcs1::{DecodeRsaPublicKey, RsaPssParams};
+use rsa::{Pss, RsaPublicKey};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fs;
+use std::time::SystemTime;
+use x509_cert::crl::CertificateList;
+use x509_cert::der::asn1::ObjectIdentifier;
+use x509_cert::der::{Decode, Encode};
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::Certificate;
+
+// OIDs of the supported public key and signature algorithms
+pub const OID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+pub const OID_SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+pub const OID_ECDSA_WITH_SHA256: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+pub const OID_SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+pub const OID_ECDSA_WITH_SHA384: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+pub const OID_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+pub const OID_RSASSA_PSS: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.10");
+pub const OID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+pub const OID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+pub const OID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+/***
+    Load X.509 certificates from PEM or DER encoded data.
+    PEM data may contain multiple certificates, which are returned in order.
+    Trailing NUL bytes, as found in quote certification data, are ignored.
+*/
+pub fn load_certificates(data: &[u8]) -> Result<Vec<Certificate>, anyhow::Error> {
+    let len = data.iter().rposition(|b| *b != 0).map_or(0, |pos| pos + 1);
+    let data = &data[..len];
+    if data.is_empty() {
+        return Err(anyhow!("[load_certificates] no certificate data provided"));
+    }
+
+    if data.starts_with(b"-----BEGIN") {
+        match Certificate::load_pem_chain(data) {
+            Ok(certs) => Ok(certs),
+            Err(e) => Err(anyhow!(
+                "[load_certificates] invalid PEM certificate: {:?}",
+                e
+            )),
+        }
+    } else {
+        match Certificate::from_der(data) {
+            Ok(cert) => Ok(vec![cert]),
+            Err(e) => Err(anyhow!(
+                "[load_certificates] invalid DER certificate: {:?}",
+                e
+            )),
+        }
+    }
+}
+
+// Load X.509 certificates from a PEM or DER encoded file
+pub fn load_certificate_file(path: &str) -> Result<Vec<Certificate>, anyhow::Error> {
+    match fs::read(path) {
+        Ok(data) => load_certificates(&data),
+        Err(e) => Err(anyhow!(
+            "[load_certificate_file] error reading {}: {:?}",
+            path,
+            e
+        )),
+    }
+}
+
+// Load an X.509 CRL from a PEM or DER encoded file
+pub fn load_crl_file(path: &str) -> Result<CertificateList, anyhow::Error> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => return Err(anyhow!("[load_crl_file] error reading {}: {:?}", path, e)),
+    };
+
+    let der = if data.starts_with(b"-----BEGIN") {
+        match x509_cert::der::pem::decode_vec(&data) {
+            Ok((_, der)) => der,
+            Err(e) => return Err(anyhow!("[load_crl_file] invalid PEM CRL {}: {:?}", path, e)),
+        }
+    } else {
+        data
+    };
+
+    match CertificateList::from_der(&der) {
+        Ok(crl) => Ok(crl),
+        Err(e) => Err(anyhow!("[load_crl_file] invalid CRL {}: {:?}", path, e)),
+    }
+}
+
+/***
+    Verify signature over data with the given public key.
+    Supported algorithms are ECDSA with P-256/SHA-256 and P-384/SHA-384, as used by
+    Intel PCK certificates and AMD VCEK/VLEK, and RSASSA-PSS as used by AMD ARK/ASK.
+
+    Args:
+        key: public key of the signer
+        algorithm: signature algorithm identifier
+        data: signed data
+        signature: DER encoded signature for ECDSA, or the raw RSA signature
+
+    Returns:
+        Error if the algorithm is not supported or the signature is invalid
+*/
+pub fn verify_signature(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &AlgorithmIdentifierOwned,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), anyhow::Error> {
+    let key_oid = key.algorithm.oid;
+    if key_oid == OID_RSA_ENCRYPTION || key_oid == OID_RSASSA_PSS {
+        if algorithm.oid != OID_RSASSA_PSS {
+            return Err(anyhow!(
+                "[verify_signature] unsupported signature algorithm {} for RSA key",
+                algorithm.oid
+            ));
+        }
+        return verify_rsa_pss(key, algorithm, data, signature);
+    }
+    if key_oid != OID_EC_PUBLIC_KEY {
+        return Err(anyhow!(
+            "[verify_signature] unsupported public key algorithm: {}",
+            key_oid
+        ));
+    }
+    let curve = match &key.algorithm.parameters {
+        Some(parameters) => parameters.decode_as::<ObjectIdentifier>().ok(),
+        None => None,
+    };
+
+    let verified = match (curve, algorithm.oid) {
+        (Some(OID_SECP256R1), OID_ECDSA_WITH_SHA256) => {
+            let key = match p256::ecdsa::VerifyingKey::from_sec1_bytes(
+                key.subject_public_key.raw_bytes(),
+            ) {
+                Ok(key) => key,
+                Err(e) => return Err(anyhow!("[verify_signature] invalid P-256 key: {:?}", e)),
+            };
+            let signature = match p256::ecdsa::Signature::from_der(signature) {
+                Ok(signature) => signature,
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[verify_signature] invalid ECDSA signature: {:?}",
+                        e
+                    ))
+                }
+            };
+            key.verify(data, &signature).is_ok()
+        }
+        (Some(OID_SECP384R1), OID_ECDSA_WITH_SHA384) => {
+            let key = match p384::ecdsa::VerifyingKey::from_sec1_bytes(
+                key.subject_public_key.raw_bytes(),
+            ) {
+                Ok(key) => key,
+                Err(e) => return Err(anyhow!("[verify_signature] invalid P-384 key: {:?}", e)),
+            };
+            let signature = match p384::ecdsa::Signature::from_der(signature) {
+                Ok(signature) => signature,
+                Err(e) => {
+                    return Err(anyhow!(
+                        "[verify_signature] invalid ECDSA signature: {:?}",
+                        e
+                    ))
+                }
+            };
+            key.verify(data, &signature).is_ok()
+        }
+        (curve, oid) => {
+            return Err(anyhow!(
+                "[verify_signature] unsupported signature algorithm {} with curve {:?}",
+                oid,
+                curve
+            ))
+        }
+    };
+
+    match verified {
+        true => Ok(()),
+        false => Err(anyhow!("[verify_signature] signature verification failed")),
+    }
+}
+
+// verify RSASSA-PSS signature with the hash and salt length given in algorithm parameters
+fn verify_rsa_pss(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &AlgorithmIdentifierOwned,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), anyhow::Error> {
+    let key = match RsaPublicKey::from_pkcs1_der(key.subject_public_key.raw_bytes()) {
+        Ok(key) => key,
+        Err(e) => return Err(anyhow!("[verify_rsa_pss] invalid RSA key: {:?}", e)),
+    };
+    let parameters = match &algorithm.parameters {
+        Some(parameters) => match parameters.to_der() {
+            Ok(parameters) => parameters,
+            Err(e) => return Err(anyhow!("[verify_rsa_pss] encoding error: {:?}", e)),
+        },
+        None => return Err(anyhow!("[verify_rsa_pss] missing RSASSA-PSS parameters")),
+    };
+    let parameters = match RsaPssParams::from_der(&parameters) {
+        Ok(parameters) => parameters,
+        Err(e) => {
+            return Err(anyhow!(
+                "[verify_rsa_pss] invalid RSASSA-PSS parameters: {:?}",
+                e
+            ))
+        }
+    };
+
+    let salt_len = parameters.salt_len as usize;
+    let (digest, scheme) = match parameters.hash.oid {
+        OID_SHA256 => (
+            Sha256::digest(data).to_vec(),
+            Pss::new_with_salt::<Sha256>(salt_len),
+        ),
+        OID_SHA384 => (
+            Sha384::digest(data).to_vec(),
+            Pss::new_with_salt::<Sha384>(salt_len),
+        ),
+        OID_SHA512 => (
+            Sha512::digest(data).to_vec(),
+            Pss::new_with_salt::<Sha512>(salt_len),
+        ),
+        oid => {
+            return Err(anyhow!(
+                "[verify_rsa_pss] unsupported hash algorithm: {}",
+                oid
+            ))
+        }
+    };
+
+    match key.verify(scheme, &digest, signature) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(anyhow!("[verify_rsa_pss] signature verification failed")),
+    }
+}
+
+// Verify the signature of cert made by issuer
+pub fn verify_cert_signature(
+    cert: &Certificate,
+    issuer: &Certificate,
+) -> Result<(), anyhow::Error> {
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return Err(anyhow!(
+            "[verify_cert_signature] issuer of {} does not match subject {}",
+            cert.tbs_certificate.subject,
+            issuer.tbs_certificate.subject
+        ));
+    }
+    let tbs = match cert.tbs_certificate.to_der() {
+        Ok(tbs) => tbs,
+        Err(e) => return Err(anyhow!("[verify_cert_signature] encoding error: {:?}", e)),
+    };
+    let signature = match cert.signature.as_bytes() {
+        Some(signature) => signature,
+        None => {
+            return Err(anyhow!(
+                "[verify_cert_signature] invalid signature bit string"
+            ))
+        }
+    };
+    match verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &cert.signature_algorithm,
+        &tbs,
+        signature,
+    ) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!(
+            "[verify_cert_signature] bad signature on {}: {:?}",
+            cert.tbs_certificate.subject,
+            e
+        )),
+    }
+}
+
+/***
+    Validate a certificate chain against a trust anchor.
+
+    Args:
+        chain: certificates ordered from the leaf to the certificate closest to the
+               trust anchor. The trust anchor itself may be the last element.
+        trust_anchor: the trusted root CA certificate
+        crls: CRLs used to check the revocation status of the chain
+        verify_time: the time at which the certificates and CRLs should be valid
+
+    Returns:
+        Error describing the first failed check
+*/
+pub fn verify_cert_chain(
+    chain: &[Certificate],
+    trust_anchor: &Certificate,
+    crls: &[CertificateList],
+    verify_time: SystemTime,
+) -> Result<(), anyhow::Error> {
+    if chain.is_empty() {
+        return Err(anyhow!("[verify_cert_chain] empty certificate chain"));
+    }
+
+    // the trust anchor is not required in the chain, but if present it must be exact
+    let mut certs: Vec<&Certificate> = chain.iter().collect();
+    if certs.len() > 1
+        && certs[certs.len() - 1].tbs_certificate.subject == trust_anchor.tbs_certificate.subject
+    {
+        if certs[certs.len() - 1] != trust_anchor {
+            return Err(anyhow!(
+                "[verify_cert_chain] root certificate in chain does not match the trust anchor"
+            ));
+        }
+        certs.pop();
+    }
+    certs.push(trust_anchor);
+
+    verify_cert_signature(trust_anchor, trust_anchor)?;
+    for (index, cert) in certs.iter().enumerate() {
+        check_validity(cert, verify_time)?;
+        if let Some(issuer) = certs.get(index + 1) {
+            verify_cert_signature(cert, issuer)?;
+        }
+        if index > 0 && !is_ca(cert) {
+            return Err(anyhow!(
+                "[verify_cert_chain] {} is not a CA certificate",
+                cert.tbs_certificate.subject
+            ));
+        }
+    }
+
+    for crl in crls {
+        check_crl(crl, &certs, verify_time)?;
+    }
+
+    Ok(())
+}
+
+fn check_validity(cert: &Certificate, verify_time: SystemTime) -> Result<(), anyhow::Error> {
+    let validity = &cert.tbs_certificate.validity;
+    if verify_time < validity.not_before.to_system_time()
+        || verify_time > validity.not_after.to_system_time()
+    {
+        return Err(anyhow!(
+            "[check_validity] {} is not valid at verification time, valid from {} to {}",
+            cert.tbs_certificate.subject,
+            validity.not_before,
+            validity.not_after
+        ));
+    }
+    Ok(())
+}
+
+fn is_ca(cert: &Certificate) -> bool {
+    match cert.tbs_certificate.get::<BasicConstraints>() {
+        Ok(Some((_, constraints))) => constraints.ca,
+        _ => false,
+    }
+}
+
+// verify the CRL is issued by a certificate of the chain and none of the chain is revoked
+fn check_crl(
+    crl: &CertificateList,
+    certs: &[&Certificate],
+    verify_time: SystemTime,
+) -> Result<(), anyhow::Error> {
+    let tbs = &crl.tbs_cert_list;
+    let issuer = match certs
+        .iter()
+        .find(|cert| cert.tbs_certificate.subject == tbs.issuer)
+    {
+        Some(issuer) => issuer,
+        None => {
+            return Err(anyhow!(
+                "[check_crl] CRL issuer {} not found in certificate chain",
+                tbs.issuer
+            ))
+        }
+    };
+
+    let tbs_der = match tbs.to_der() {
+        Ok(tbs_der) => tbs_der,
+        Err(e) => return Err(anyhow!("[check_crl] encoding error: {:?}", e)),
+    };
+    let signature = match crl.signature.as_bytes() {
+        Some(signature) => signature,
+        None => return Err(anyhow!("[check_crl] invalid signature bit string")),
+    };
+    if let Err(e) = verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &crl.signature_algorithm,
+        &tbs_der,
+        signature,
+    ) {
+        return Err(anyhow!(
+            "[check_crl] bad CRL signature from {}: {:?}",
+            tbs.issuer,
+            e
+        ));
+    }
+
+    if verify_time < tbs.this_update.to_system_time() {
+        return Err(anyhow!(
+            "[check_crl] CRL from {} is not yet valid",
+            tbs.issuer
+        ));
+    }
+    if let Some(next_update) = tbs.next_update {
+        if verify_time > next_update.to_system_time() {
+            return Err(anyhow!("[check_crl] CRL from {} has expired", tbs.issuer));
+        }
+    }
+
+    if let Some(revoked_certs) = &tbs.revoked_certificates {
+        for cert in certs {
+            if cert.tbs_certificate.issuer != tbs.issuer {
+                continue;
+            }
+            if revoked_certs
+                .iter()
+                .any(|revoked| revoked.serial_number == cert.tbs_certificate.serial_number)
+            {
+                return Err(anyhow!(
+                    "[check_crl] {} has been revoked",
+                    cert.tbs_certificate.subject
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+#!/usr/bin/env python3
+"""
+Generate tpm_event_log.bin, a crypto agile TPM 2.0 event log with SHA1, SHA256 and SHA384
+banks, laid out as OVMF measures a boot into binary_bios_measurements, and print the
+expected PCR values of each bank.
+
+The PCR values are computed here with hashlib, independently of the Rust replay, and are
+the known values the Rust tests check the replay against. Digests of the firmware volume
+and of the boot application are the hashes of images not carried by the log, as on real
+platforms.
+
+The log is synthesized, not captured. A log captured on a host with a TPM, from
+/sys/kernel/security/tpm0/binary_bios_measurements, is checked against the PCR values in
+/sys/class/tpm/tpm0/pcr-<bank>/ by test_replay_host_event_log when run as root there.
+
+Usage: python3 tpm_event_log.py [output]
+"""
+import hashlib
+import struct
+import sys
+
+EV_SEPARATOR = 0x4
+EV_NO_ACTION = 0x3
+EV_S_CRTM_VERSION = 0x8
+EV_EFI_VARIABLE_DRIVER_CONFIG = 0x80000001
+EV_EFI_VARIABLE_BOOT = 0x80000002
+EV_EFI_BOOT_SERVICES_APPLICATION = 0x80000003
+EV_EFI_ACTION = 0x80000007
+EV_EFI_PLATFORM_FIRMWARE_BLOB = 0x80000008
+
+BANKS = [(0x4, "sha1", 20), (0xB, "sha256", 32), (0xC, "sha384", 48)]
+
+EFI_GLOBAL_VARIABLE = bytes.fromhex("61dfe48bca93d211aa0d00e098032b8c")
+EFI_IMAGE_SECURITY_DATABASE = bytes.fromhex("cbb219d73a3d9645a3bcdad00e67656f")
+
+
+def ucs2(text, nul=True):
+    return (text + ("\0" if nul else "")).encode("utf-16-le")
+
+
+def spec_id_event():
+    event = b"Spec ID Event03\0"
+    event += struct.pack("<I", 0)  # platformClass
+    event += bytes([0, 2, 0, 2])  # specVersionMinor/Major, specErrata, uintnSize
+    event += struct.pack("<I", len(BANKS))
+    for algo_id, _, size in BANKS:
+        event += struct.pack("<HH", algo_id, size)
+    event += bytes([0])  # vendorInfoSize
+    return struct.pack("<II", 0, EV_NO_ACTION) + bytes(20) + struct.pack("<I", len(event)) + event
+
+
+def variable_data(guid, name, data):
+    return guid + struct.pack("<QQ", len(name), len(data)) + ucs2(name, nul=False) + data
+
+
+def image_load_event():
+    # UEFI_IMAGE_LOAD_EVENT with the end of device path node
+    device_path = bytes([0x7F, 0xFF, 0x04, 0x00])
+    return struct.pack("<QQQQ", 0x7E000000, 0x24000, 0, len(device_path)) + device_path
+
+
+# (PCR index, event type, event data, data hashed into the digests)
+MEASUREMENTS = [
+    (0, EV_S_CRTM_VERSION, ucs2(""), None),
+    (0, EV_EFI_PLATFORM_FIRMWARE_BLOB, struct.pack("<QQ", 0x820000, 0xE0000), b"FVMAIN"),
+    (7, EV_EFI_VARIABLE_DRIVER_CONFIG, variable_data(EFI_GLOBAL_VARIABLE, "SecureBoot", b"\x00"), None),
+    (7, EV_EFI_VARIABLE_DRIVER_CONFIG, variable_data(EFI_GLOBAL_VARIABLE, "PK", b""), None),
+    (7, EV_EFI_VARIABLE_DRIVER_CONFIG, variable_data(EFI_GLOBAL_VARIABLE, "KEK", b""), None),
+    (7, EV_EFI_VARIABLE_DRIVER_CONFIG, variable_data(EFI_IMAGE_SECURITY_DATABASE, "db", b""), None),
+    (7, EV_EFI_VARIABLE_DRIVER_CONFIG, variable_data(EFI_IMAGE_SECURITY_DATABASE, "dbx", b""), None),
+    (1, EV_EFI_VARIABLE_BOOT, variable_data(EFI_GLOBAL_VARIABLE, "BootOrder", b"\x00\x00"), None),
+    (4, EV_EFI_ACTION, b"Calling EFI Application from Boot Option", None),
+] + [(pcr, EV_SEPARATOR, bytes(4), None) for pcr in range(8)] + [
+    (4, EV_EFI_BOOT_SERVICES_APPLICATION, image_load_event(), b"BOOTX64.EFI"),
+    (5, EV_EFI_ACTION, b"Exit Boot Services Invocation", None),
+    (5, EV_EFI_ACTION, b"Exit Boot Services Returned with Success", None),
+]
+
+
+def main():
+    output = sys.argv[1] if len(sys.argv) > 1 else "tpm_event_log.bin"
+    data = spec_id_event()
+    pcrs = {name: {pcr: bytes(size) for pcr in range(8)} for _, name, size in BANKS}
+    for pcr, event_type, event, hashed in MEASUREMENTS:
+        data += struct.pack("<III", pcr, event_type, len(BANKS))
+        for algo_id, name, _ in BANKS:
+            digest = hashlib.new(name, event if hashed is None else hashed).digest()
+            data += struct.pack("<H", algo_id) + digest
+            pcrs[name][pcr] = hashlib.new(name, pcrs[name][pcr] + digest).digest()
+        data += struct.pack("<I", len(event)) + event
+
+    with open(output, "wb") as f:
+        f.write(data)
+    for _, name, _ in BANKS:
+        for pcr in range(8):
+            print(name, pcr, pcrs[name][pcr].hex())
+
+
+if __name__ == "__main__":
+    main()